@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::oracle;
+use crate::events::CircuitBreakerTrippedEvent;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Why a vault was frozen. Stored in `Vault::freeze_reason`; `Manual` covers `emergency_freeze`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum FreezeReason {
+    Manual,
+    Outflow,
+    PriceMove,
+    Insolvent,
+}
+
+impl From<u8> for FreezeReason {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => FreezeReason::Outflow,
+            2 => FreezeReason::PriceMove,
+            3 => FreezeReason::Insolvent,
+            _ => FreezeReason::Manual,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct CircuitBreakerParams {
+    pub max_outflow_bps: u16,
+    pub outflow_window: u32,
+    pub max_price_move_bps: u16,
+}
+
+impl CircuitBreakerParams {
+    pub const MAX_WINDOW: u32 = 7 * 24 * 60 * 60;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.max_outflow_bps <= 10000, FluxError::InvalidVaultConfig);
+        require!(self.max_price_move_bps <= 10000, FluxError::InvalidVaultConfig);
+        require!(
+            self.max_outflow_bps == 0 || (self.outflow_window > 0 && self.outflow_window <= Self::MAX_WINDOW),
+            FluxError::InvalidVaultConfig
+        );
+        Ok(())
+    }
+}
+
+pub fn params(vault: &Vault) -> CircuitBreakerParams {
+    CircuitBreakerParams {
+        max_outflow_bps: vault.max_outflow_bps,
+        outflow_window: vault.outflow_window,
+        max_price_move_bps: vault.max_price_move_bps,
+    }
+}
+
+pub fn set_params(vault: &mut Vault, params: &CircuitBreakerParams, now: i64) {
+    vault.max_outflow_bps = params.max_outflow_bps;
+    vault.outflow_window = params.outflow_window;
+    vault.max_price_move_bps = params.max_price_move_bps;
+    reset_tracking(vault, now);
+}
+
+/// Start a fresh outflow window and re-anchor the price on the next read.
+pub fn reset_tracking(vault: &mut Vault, now: i64) {
+    vault.window_start = now;
+    vault.window_start_assets = vault.total_assets;
+    vault.window_outflow = 0;
+    vault.window_inflow = 0;
+    vault.last_oracle_price = 0;
+}
+
+fn roll_window(vault: &mut Vault, now: i64) {
+    if now.saturating_sub(vault.window_start) >= vault.outflow_window as i64 {
+        vault.window_start = now;
+        vault.window_start_assets = vault.total_assets;
+        vault.window_outflow = 0;
+        vault.window_inflow = 0;
+    }
+}
+
+/// Credit funds entering the vault against the current outflow window.
+pub fn record_inflow(vault: &mut Vault, amount: u64, now: i64) {
+    roll_window(vault, now);
+    vault.window_inflow = vault.window_inflow.saturating_add(amount);
+}
+
+/// Record `outflow` and the latest price from the vault's collateral `feed`, then check
+/// every breaker. Returns `true` if the vault was frozen; the caller must then return `Ok(())`
+/// without moving funds, since failing the instruction would roll back the freeze.
+pub fn check(vault: &mut Account<Vault>, outflow: u64, feed: Option<&AccountInfo>, now: i64) -> Result<bool> {
+    // Only the pinned oracle may move the price anchor, whatever the caller's own constraints
+    let price = match feed {
+        Some(feed) => {
+            require_keys_eq!(feed.key(), vault.oracle_config, FluxError::OracleMismatch);
+            Some(oracle::read_price(feed)?)
+        }
+        None => None,
+    };
+
+    if !vault.is_solvent() {
+        let (observed, limit) = (vault.total_liabilities, vault.total_assets);
+        return trip(vault, FreezeReason::Insolvent, observed, limit, now);
+    }
+
+    if let Some(price) = price {
+        let last = vault.last_oracle_price;
+        vault.last_oracle_price = price.price;
+        if vault.max_price_move_bps > 0 && last > 0 {
+            let moved_bps = (price.price.abs_diff(last) as u128)
+                .checked_mul(10000).ok_or(error!(FluxError::ArithmeticError))?
+                / last as u128;
+            if moved_bps > vault.max_price_move_bps as u128 {
+                let limit = vault.max_price_move_bps as u64;
+                return trip(vault, FreezeReason::PriceMove, moved_bps as u64, limit, now);
+            }
+        }
+    }
+
+    if vault.max_outflow_bps > 0 {
+        roll_window(vault, now);
+        let net_outflow = vault.window_outflow
+            .saturating_add(outflow)
+            .saturating_sub(vault.window_inflow);
+        let limit = (vault.window_start_assets as u128 * vault.max_outflow_bps as u128 / 10000) as u64;
+        if net_outflow > limit {
+            return trip(vault, FreezeReason::Outflow, net_outflow, limit, now);
+        }
+        vault.window_outflow = vault.window_outflow.saturating_add(outflow);
+    }
+    Ok(false)
+}
+
+fn trip(vault: &mut Account<Vault>, reason: FreezeReason, observed: u64, limit: u64, now: i64) -> Result<bool> {
+    vault.is_frozen = true;
+    vault.freeze_reason = reason as u8;
+
+    msg!("CIRCUIT BREAKER: Vault {} frozen ({:?}: {} > {})", vault.key(), reason, observed, limit);
+    emit!(CircuitBreakerTrippedEvent {
+        vault: vault.key(),
+        reason,
+        observed,
+        limit,
+        timestamp: now,
+    });
+    Ok(true)
+}
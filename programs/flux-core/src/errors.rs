@@ -13,7 +13,7 @@ pub enum FluxError {
     InvalidAmount,
 
     // Liquidation Errors
-    #[msg("Slippage tolerance exceeded during liquidation")]
+    #[msg("Slippage tolerance exceeded during swap")]
     SlippageExceeded,
     #[msg("Vault is currently healthy, liquidation rejected")]
     VaultHealthy,
@@ -47,4 +47,182 @@ pub enum FluxError {
     ProtocolPaused,
     #[msg("Feature not yet implemented")]
     NotImplemented,
+
+    // Compliance Case Management
+    #[msg("Account is not flagged for AML review")]
+    AccountNotFlagged,
+    #[msg("Travel-rule data required for transfers above the threshold")]
+    TravelRuleRequired,
+    #[msg("Travel-rule payload does not match the transfer record")]
+    TravelRuleMismatch,
+
+    // Profile Lifecycle
+    #[msg("Profile still has active loans")]
+    ActiveLoansOutstanding,
+    #[msg("Profile has compliance case history and must be retained")]
+    CaseHistoryRetained,
+    #[msg("Account is not a valid user profile")]
+    InvalidProfileLayout,
+    #[msg("Action history account required for this profile")]
+    MissingActionHistory,
+    #[msg("Invalid action history capacity")]
+    InvalidHistoryCapacity,
+
+    // Borrowing & Reputation
+    #[msg("Borrow limit for this reputation tier exceeded")]
+    BorrowLimitExceeded,
+    #[msg("Insufficient collateral for requested debt")]
+    InsufficientCollateral,
+    #[msg("Invalid reputation configuration")]
+    InvalidReputationConfig,
+
+    // Governance
+    #[msg("Vault configuration values out of range or inconsistent")]
+    InvalidVaultConfig,
+    #[msg("Timelock for this change has not elapsed")]
+    TimelockNotElapsed,
+    #[msg("Invalid new authority")]
+    InvalidAuthority,
+
+    // Admin Multisig
+    #[msg("Invalid multisig signers, threshold or expiry")]
+    InvalidMultisigConfig,
+    #[msg("Signer is not a multisig member")]
+    NotMultisigMember,
+    #[msg("Member has already approved this proposal")]
+    AlreadyApproved,
+    #[msg("Proposal has expired")]
+    ProposalExpired,
+    #[msg("Proposal has already been executed")]
+    ProposalAlreadyExecuted,
+    #[msg("Multisig membership changed since this proposal was created")]
+    StaleProposal,
+    #[msg("Not enough approvals to execute proposal")]
+    InsufficientApprovals,
+
+    // Circuit Breakers
+    #[msg("Vault was frozen by a circuit breaker; only the guardian can reset it")]
+    CircuitBreakerTripped,
+    #[msg("Vault is not frozen by a circuit breaker")]
+    CircuitBreakerNotTripped,
+
+    // Risk Engine
+    #[msg("Price feed does not match the vault's oracle")]
+    OracleMismatch,
+
+    // Flash Loans
+    #[msg("A flash loan is open on this vault")]
+    FlashLoanActive,
+    #[msg("No flash_repay for this vault follows in the transaction")]
+    FlashLoanNotRepaid,
+    #[msg("No flash loan is open on this vault")]
+    NoActiveFlashLoan,
+    #[msg("Flash loans cannot be taken through CPI")]
+    FlashLoanCpiForbidden,
+
+    // Reserves
+    #[msg("Invalid reserve configuration")]
+    InvalidReserveConfig,
+    #[msg("Reserve deposit cap exceeded")]
+    ReserveDepositCapExceeded,
+    #[msg("Reserve accounts missing, duplicated or not owned by this position")]
+    ReserveAccountsMismatch,
+    #[msg("Position has reached the maximum number of reserve deposits")]
+    TooManyReserves,
+
+    // Exposure Caps
+    #[msg("Deposit would exceed the vault supply cap")]
+    SupplyCapExceeded,
+    #[msg("Borrow would exceed the vault or per-user borrow cap")]
+    BorrowCapExceeded,
+
+    // Risk Categories
+    #[msg("Invalid risk category configuration")]
+    InvalidRiskCategory,
+    #[msg("Risk category account does not match the position or reserve")]
+    RiskCategoryMismatch,
+    #[msg("Borrow would exceed the isolated category's debt ceiling")]
+    DebtCeilingExceeded,
+    #[msg("Entering or leaving isolated mode requires repaying all debt")]
+    CategoryChangeWithDebt,
+
+    // Withdrawal Queue
+    #[msg("Vault has a withdrawal queue; pass it so pending withdrawals are filled first")]
+    MissingWithdrawalQueue,
+    #[msg("Withdrawal ticket has nothing to claim yet")]
+    NothingToClaim,
+
+    // Time-Locked Deposits
+    #[msg("Invalid lock terms")]
+    InvalidLockConfig,
+    #[msg("Lock account has reached the maximum number of open locks")]
+    TooManyLocks,
+    #[msg("No lock at this index")]
+    InvalidLockIndex,
+    #[msg("Lock has not matured; use early exit")]
+    LockNotMatured,
+    #[msg("Lock has matured; withdraw it without penalty")]
+    LockMatured,
+    #[msg("Boost budget cannot fund this lock")]
+    InsufficientBoostBudget,
+
+    // Deleverage
+    #[msg("Invalid auto-deleverage settings")]
+    InvalidAutoDeleverage,
+    #[msg("Position health is above the auto-deleverage trigger")]
+    DeleverageNotTriggered,
+    #[msg("Deleverage must improve the position's health factor")]
+    DeleverageWorsensHealth,
+    #[msg("Keeper repayment exceeds the per-call limit")]
+    DeleverageLimitExceeded,
+
+    // Conditional Orders
+    #[msg("Invalid order parameters")]
+    InvalidOrder,
+    #[msg("Oracle price has not reached the order trigger")]
+    OrderNotTriggered,
+
+    // Leverage
+    #[msg("Leverage target must be above 1x and within the vault's max_leverage")]
+    LeverageExceeded,
+
+    // Rebalance Pipeline
+    #[msg("Pipeline must have between one and eight steps with non-zero amounts and at most one Xfer")]
+    InvalidPipeline,
+    #[msg("Remaining accounts do not match the pipeline's liquidation steps")]
+    PipelineAccountsMismatch,
+    #[msg("Pipeline broke a vault invariant (fee accounting, solvency or health)")]
+    PipelineInvariantViolated,
+
+    // Protocol Pause
+    #[msg("Pause flags contain bits outside pause_flags::ALL")]
+    InvalidPauseFlags,
+
+    // Flash Loan Fees
+    #[msg("Insurance fund must be a non-default key")]
+    InvalidInsuranceFund,
+    #[msg("Insurance fund account does not match the protocol config")]
+    InsuranceFundMismatch,
+
+    // Reserve Withdrawals
+    #[msg("Collateral deposit still holds a balance")]
+    CollateralDepositNotEmpty,
+
+    // Supply Shares
+    #[msg("Not enough unlocked supply shares")]
+    InsufficientShares,
+
+    // Pipeline Transfers
+    #[msg("Xfer recipient is not an allowed venue")]
+    VenueNotAllowed,
+    #[msg("Xfer steps require the authority's profile and the compliance config")]
+    MissingComplianceAccounts,
+
+    // Vault Migration
+    #[msg("Vault is not a legacy-sized vault account")]
+    InvalidVaultLayout,
+
+    // Travel Rule
+    #[msg("Travel-rule counterparty VASP identifier must be set")]
+    InvalidCounterpartyVasp,
 }
@@ -0,0 +1,467 @@
+use anchor_lang::prelude::*;
+use crate::state::reputation::ReputationParams;
+use crate::state::user_profile::UserRole;
+use crate::state::config::ProtocolRoles;
+use crate::state::vault::{VaultConfig, VaultConfigUpdate};
+use crate::circuit_breaker::{CircuitBreakerParams, FreezeReason};
+use crate::state::reserve::{ReserveConfig, RiskCategoryKind, RiskCategoryConfig};
+use crate::state::deposit_lock::{LockTerm, LockTerms};
+use crate::state::position::AutoDeleverageParams;
+use crate::state::order::OrderParams;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+// --- Compliance ---
+
+#[event]
+pub struct AccountFlaggedEvent {
+    pub user_profile: Pubkey,
+    pub owner: Pubkey,
+    pub officer: Pubkey,
+    pub case_id: u64,
+    pub reason_code: u16,
+    pub sequence: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccountFlagClearedEvent {
+    pub user_profile: Pubkey,
+    pub owner: Pubkey,
+    pub officer: Pubkey,
+    pub case_id: u64,
+    pub reason_code: u16,
+    pub sequence: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TravelRuleTransferEvent {
+    pub transfer_record: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub payload_hash: [u8; 32],
+    pub counterparty_vasp: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ComplianceConfigUpdatedEvent {
+    pub authority: Pubkey,
+    pub travel_rule_threshold: u64,
+    pub timestamp: i64,
+}
+
+// --- Reputation ---
+
+#[event]
+pub struct ReputationConfigUpdatedEvent {
+    pub authority: Pubkey,
+    pub params: ReputationParams,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RolePromotedEvent {
+    pub user_profile: Pubkey,
+    pub owner: Pubkey,
+    pub reputation_score: u8,
+    pub role: UserRole,
+    pub timestamp: i64,
+}
+
+// --- Administration ---
+
+#[event]
+pub struct ProtocolRolesUpdatedEvent {
+    pub previous: ProtocolRoles,
+    pub current: ProtocolRoles,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InsuranceFundUpdatedEvent {
+    pub previous: Pubkey,
+    pub current: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserRoleAssignedEvent {
+    pub user_profile: Pubkey,
+    pub owner: Pubkey,
+    pub previous: UserRole,
+    pub role: UserRole,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PauseFlagsUpdatedEvent {
+    pub authority: Pubkey,
+    pub previous: u16,
+    pub paused: u16,
+    pub timestamp: i64,
+}
+
+// --- Vault Configuration ---
+
+#[event]
+pub struct ConfigChangeProposedEvent {
+    pub vault: Pubkey,
+    pub proposer: Pubkey,
+    pub update: VaultConfigUpdate,
+    pub eta: i64,
+}
+
+/// Before/after snapshot of an applied change. `changed_fields` is a `config_fields` mask.
+#[event]
+pub struct VaultConfigChangedEvent {
+    pub vault: Pubkey,
+    pub proposer: Pubkey,
+    pub previous: VaultConfig,
+    pub current: VaultConfig,
+    pub changed_fields: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConfigChangeCancelledEvent {
+    pub vault: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+// --- Authority Transfer ---
+
+#[event]
+pub struct AuthorityProposedEvent {
+    pub target: Pubkey,
+    pub current_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityAcceptedEvent {
+    pub target: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferCancelledEvent {
+    pub target: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+// --- Admin Multisig ---
+
+#[event]
+pub struct MultisigMembersUpdatedEvent {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub config_version: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AdminProposalCreatedEvent {
+    pub nonce: u64,
+    pub proposer: Pubkey,
+    pub expires_at: i64,
+}
+
+#[event]
+pub struct AdminProposalApprovedEvent {
+    pub nonce: u64,
+    pub member: Pubkey,
+    pub approvals: u8,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct AdminProposalExecutedEvent {
+    pub nonce: u64,
+    pub executor: Pubkey,
+    pub timestamp: i64,
+}
+
+// --- Circuit Breakers ---
+
+#[event]
+pub struct CircuitBreakerTrippedEvent {
+    pub vault: Pubkey,
+    pub reason: FreezeReason,
+    pub observed: u64,
+    pub limit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerResetEvent {
+    pub vault: Pubkey,
+    pub guardian: Pubkey,
+    pub reason: FreezeReason,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CircuitBreakerUpdatedEvent {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub previous: CircuitBreakerParams,
+    pub current: CircuitBreakerParams,
+    pub timestamp: i64,
+}
+
+// --- Risk Engine ---
+
+#[event]
+pub struct RiskRefreshedEvent {
+    pub vault: Pubkey,
+    pub price: u64,
+    pub volatility_bps: u16,
+    pub risk_factor: u16,
+    pub liquidation_penalty: u16,
+    pub timestamp: i64,
+}
+
+// --- Flash Loans ---
+
+#[event]
+pub struct FlashBorrowEvent {
+    pub vault: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct FlashRepayEvent {
+    pub vault: Pubkey,
+    pub repayer: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub depositor_share: u64,
+    pub insurance_share: u64,
+    pub incinerator_share: u64,
+}
+
+// --- Reserves & Liquidation ---
+
+#[event]
+pub struct ReserveConfiguredEvent {
+    pub vault: Pubkey,
+    pub reserve: Pubkey,
+    pub mint: Pubkey,
+    pub previous: Option<ReserveConfig>, // None when the reserve is added
+    pub current: ReserveConfig,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionLiquidatedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub liquidator: Pubkey,
+    pub collateral_reserve: Option<Pubkey>, // None for the base collateral
+    pub repaid: u64,
+    pub seized: u64,
+    pub health_factor: u64,
+    pub timestamp: i64,
+}
+
+// --- Risk Categories ---
+
+#[event]
+pub struct RiskCategoryConfiguredEvent {
+    pub vault: Pubkey,
+    pub category: Pubkey,
+    pub id: u8,
+    pub kind: RiskCategoryKind,
+    pub previous: Option<RiskCategoryConfig>, // None when the category is added
+    pub current: RiskCategoryConfig,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionCategoryChangedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub previous: u8,
+    pub current: u8,
+    pub timestamp: i64,
+}
+
+// --- Withdrawal Queue ---
+
+#[event]
+pub struct WithdrawalRequestedEvent {
+    pub vault: Pubkey,
+    pub ticket: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    pub queued_ahead: u64, // unfilled amount ahead of this ticket
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalsFilledEvent {
+    pub vault: Pubkey,
+    pub filled: u64,
+    pub total_filled: u64,
+    pub pending: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalClaimedEvent {
+    pub vault: Pubkey,
+    pub ticket: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+// --- Time-Locked Deposits ---
+
+#[event]
+pub struct LockTermsUpdatedEvent {
+    pub vault: Pubkey,
+    pub previous: Option<LockTerms>, // None when the lock program is opened
+    pub current: LockTerms,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositLockedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub term: LockTerm,
+    pub boost_bps: u16,
+    pub boost: u64,
+    pub unlocks_at: i64,
+}
+
+#[event]
+pub struct DepositUnlockedEvent {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub boost: u64,   // 0 on early exit
+    pub penalty: u64, // sent to the incinerator on early exit
+    pub early: bool,
+    pub timestamp: i64,
+}
+
+// --- Deleverage ---
+
+#[event]
+pub struct PositionDelegateSetEvent {
+    pub position: Pubkey,
+    pub delegate: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AutoDeleverageUpdatedEvent {
+    pub position: Pubkey,
+    pub params: Option<AutoDeleverageParams>, // None when disabled
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionDeleveragedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub caller: Pubkey,
+    pub collateral_sold: u64,
+    pub repaid: u64,
+    pub keeper_fee: u64, // 0 unless a keeper ran it via `AutoDeleverage`
+    pub health_factor_before: u64,
+    pub health_factor_after: u64,
+    pub timestamp: i64,
+}
+
+// --- Conditional Orders ---
+
+#[event]
+pub struct OrderPlacedEvent {
+    pub position: Pubkey,
+    pub order: Pubkey,
+    pub params: OrderParams,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderCancelledEvent {
+    pub position: Pubkey,
+    pub order: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OrderExecutedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub order: Pubkey,
+    pub executor: Pubkey,
+    pub price: u64,
+    pub collateral_sold: u64,
+    pub repaid: u64,
+    pub keeper_tip: u64,
+    pub timestamp: i64,
+}
+
+// --- Leverage ---
+
+#[event]
+pub struct PositionLeveragedEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub borrowed: u64,
+    pub collateral_bought: u64,
+    pub leverage_bps: u64, // collateral over equity; 10000 = 1x
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionUnwoundEvent {
+    pub vault: Pubkey,
+    pub position: Pubkey,
+    pub collateral_sold: u64,
+    pub repaid: u64,
+    pub leverage_bps: u64,
+    pub timestamp: i64,
+}
+
+// --- Rebalance Pipeline ---
+
+#[event]
+pub struct RebalancedEvent {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub steps: u8,
+    pub fetched: u64,
+    pub repaid: u64,
+    pub seized: u64,
+    pub transferred: u64,
+    pub health_factor_before: u64, // vault-wide
+    pub health_factor_after: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct XferVenueUpdatedEvent {
+    pub venue: Pubkey,
+    pub allowed: bool,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
@@ -1,12 +1,419 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use crate::state::config::{ProtocolConfig, ProtocolRoles, pause_flags};
+use crate::state::user_profile::{UserProfile, UserRole};
+use crate::state::vault::{Vault, VaultConfigUpdate};
+use crate::state::governance::PendingConfigChange;
+use crate::circuit_breaker::{self, CircuitBreakerParams, FreezeReason};
+use crate::events::{
+    ProtocolRolesUpdatedEvent, InsuranceFundUpdatedEvent, UserRoleAssignedEvent, PauseFlagsUpdatedEvent,
+    ConfigChangeProposedEvent, VaultConfigChangedEvent, ConfigChangeCancelledEvent,
+    CircuitBreakerResetEvent, CircuitBreakerUpdatedEvent,
+};
+use crate::errors::FluxError;
 
-pub fn update_config_handler(ctx: Context<crate::UpdateConfig>, new_risk_factor: u16) -> Result<()> {
+#[derive(Accounts)]
+pub struct InitProtocolConfig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = ProtocolConfig::LEN,
+        seeds = [b"protocol_config"],
+        bump
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    /// Only the upgrade authority may create the singleton, so it cannot be front-run after deploy
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ FluxError::UnauthorizedAccess)]
+    pub program: Program<'info, crate::program::FluxCore>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()) @ FluxError::UnauthorizedAccess)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolRoles<'info> {
+    #[account(
+        mut,
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = admin @ FluxError::UnauthorizedAccess,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPauseFlags<'info> {
+    #[account(mut, seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeConfigChange<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = authority,
+        space = PendingConfigChange::LEN,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump
+    )]
+    pub pending_config: Account<'info, PendingConfigChange>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless once the timelock has elapsed.
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump = pending_config.bump,
+        has_one = vault,
+        has_one = proposer,
+    )]
+    pub pending_config: Account<'info, PendingConfigChange>,
+    /// CHECK: Receives the proposal's rent; matched via `has_one`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_config", vault.key().as_ref()],
+        bump = pending_config.bump,
+        has_one = vault,
+        has_one = proposer,
+    )]
+    pub pending_config: Account<'info, PendingConfigChange>,
+    /// CHECK: Receives the proposal's rent; matched via `has_one`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AssignUserRole<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", user_profile.load()?.owner.as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = admin @ FluxError::UnauthorizedAccess,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    /// CHECK: Legacy-sized vault; owner, discriminator and length are verified by the handler
+    #[account(mut, owner = crate::ID @ FluxError::InvalidVaultLayout)]
+    pub vault: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = admin @ FluxError::UnauthorizedAccess,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    /// Pays the rent for the added fields
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_protocol_config_handler(
+    ctx: Context<InitProtocolConfig>,
+    guardian: Pubkey,
+    risk_manager: Pubkey,
+    fee_manager: Pubkey,
+    insurance_fund: Pubkey,
+) -> Result<()> {
+    require!(insurance_fund != Pubkey::default(), FluxError::InvalidInsuranceFund);
+    let config = &mut ctx.accounts.protocol_config;
+    config.set_roles(&ProtocolRoles {
+        admin: ctx.accounts.admin.key(),
+        guardian,
+        risk_manager,
+        fee_manager,
+    });
+    config.insurance_fund = insurance_fund;
+    config.bump = ctx.bumps.protocol_config;
+
+    msg!("Protocol config initialized. Admin: {}", config.admin);
+    Ok(())
+}
+
+pub fn update_protocol_roles_handler(ctx: Context<UpdateProtocolRoles>, roles: ProtocolRoles) -> Result<()> {
+    let config = &mut ctx.accounts.protocol_config;
+    let previous = config.roles();
+    config.set_roles(&roles);
+
+    msg!("Protocol roles updated");
+    emit!(ProtocolRolesUpdatedEvent {
+        previous,
+        current: roles,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn set_insurance_fund_handler(ctx: Context<UpdateProtocolRoles>, insurance_fund: Pubkey) -> Result<()> {
+    require!(insurance_fund != Pubkey::default(), FluxError::InvalidInsuranceFund);
+    let config = &mut ctx.accounts.protocol_config;
+    let previous = config.insurance_fund;
+    config.insurance_fund = insurance_fund;
+
+    msg!("Insurance fund: {} -> {}", previous, insurance_fund);
+    emit!(InsuranceFundUpdatedEvent {
+        previous,
+        current: insurance_fund,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn set_pause_flags_handler(ctx: Context<SetPauseFlags>, paused: u16) -> Result<()> {
+    let config = &mut ctx.accounts.protocol_config;
+    let authority = ctx.accounts.authority.key();
+    require!(paused & !pause_flags::ALL == 0, FluxError::InvalidPauseFlags);
+
+    // The guardian may only add pause bits; lifting a pause is an admin decision
+    let previous = config.paused;
+    let unpausing = previous & !paused != 0;
+    if unpausing {
+        require!(config.is_admin(&authority), FluxError::UnauthorizedAccess);
+    } else {
+        require!(config.can_freeze(&authority), FluxError::UnauthorizedAccess);
+    }
+    config.paused = paused;
+
+    msg!("Protocol pause flags: {:#08b} -> {:#08b}", previous, paused);
+    emit!(PauseFlagsUpdatedEvent {
+        authority,
+        previous,
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn assign_user_role_handler(ctx: Context<AssignUserRole>, role: UserRole) -> Result<()> {
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    let previous = profile.role();
+    profile.role = role as u8;
+
+    msg!("Profile {} role changed: {:?} -> {:?}", profile.owner, previous, role);
+    emit!(UserRoleAssignedEvent {
+        user_profile: ctx.accounts.user_profile.key(),
+        owner: profile.owner,
+        previous,
+        role,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Grow a vault created before `total_collateral` and `debt_oracle` existed. Collateral
+/// was then counted in `total_assets`, so `total_collateral` is moved out of it.
+pub fn migrate_vault_handler(ctx: Context<MigrateVault>, total_collateral: u64, debt_oracle: Pubkey) -> Result<()> {
+    let info = ctx.accounts.vault.to_account_info();
+    require!(debt_oracle != Pubkey::default(), FluxError::InvalidVaultConfig);
+
+    // 1. Only legacy vaults; the old layout is a prefix of the current one
+    {
+        let data = info.try_borrow_data()?;
+        require!(data.len() == Vault::LEGACY_LEN, FluxError::InvalidVaultLayout);
+        require!(data[..8] == Vault::DISCRIMINATOR, FluxError::InvalidVaultLayout);
+    }
+
+    // 2. Resize, topping up rent from the admin
+    let rent_due = Rent::get()?.minimum_balance(Vault::LEN).saturating_sub(info.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer { from: ctx.accounts.admin.to_account_info(), to: info.clone() },
+            ),
+            rent_due,
+        )?;
+    }
+    info.realloc(Vault::LEN, true)?;
+
+    // 3. Fill the new fields
+    let mut data = info.try_borrow_mut_data()?;
+    let mut vault = Vault::try_deserialize(&mut &data[..])?;
+    vault.total_assets = vault.total_assets.checked_sub(total_collateral).ok_or(error!(FluxError::InvalidAmount))?;
+    vault.total_collateral = total_collateral;
+    vault.debt_oracle = debt_oracle;
+    vault.reserved = [0; 3];
+    vault.try_serialize(&mut &mut data[..])?;
+
+    msg!("Vault {} migrated: {} -> {} bytes, {} collateral moved out of assets",
+        info.key(), Vault::LEGACY_LEN, Vault::LEN, total_collateral);
+    Ok(())
+}
+
+pub fn propose_config_change_handler(ctx: Context<ProposeConfigChange>, update: VaultConfigUpdate) -> Result<()> {
+    let protocol_config = &ctx.accounts.protocol_config;
+    let authority = ctx.accounts.authority.key();
+    require!(!update.is_empty(), FluxError::InvalidVaultConfig);
+    if update.touches_risk() {
+        require!(protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    }
+    if update.touches_fees() {
+        require!(protocol_config.is_fee_manager(&authority), FluxError::UnauthorizedAccess);
+    }
+
+    // Fail fast; the merged config is validated again at execution
+    let (merged, _) = ctx.accounts.vault.config().merge(&update);
+    merged.validate()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let pending = &mut ctx.accounts.pending_config;
+    pending.vault = ctx.accounts.vault.key();
+    pending.proposer = authority;
+    pending.update = update;
+    pending.proposed_at = now;
+    pending.eta = now.checked_add(Vault::CONFIG_TIMELOCK).ok_or(error!(FluxError::ArithmeticError))?;
+    pending.bump = ctx.bumps.pending_config;
+
+    msg!("Config change queued for vault {}. ETA: {}", pending.vault, pending.eta);
+    emit!(ConfigChangeProposedEvent {
+        vault: pending.vault,
+        proposer: pending.proposer,
+        update,
+        eta: pending.eta,
+    });
+    Ok(())
+}
+
+pub fn execute_config_change_handler(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+    let pending = &ctx.accounts.pending_config;
     let vault = &mut ctx.accounts.vault;
-    require!(ctx.accounts.authority.key() == vault.authority, crate::errors::FluxError::UnauthorizedAccess);
-    
-    vault.risk_factor = new_risk_factor;
-    msg!("Vault configuration updated. New risk factor: {}", new_risk_factor);
-    
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(now >= pending.eta, FluxError::TimelockNotElapsed);
+    vault.require_no_flash_loan()?;
+    vault.require_config_cooldown(now)?;
+
+    let previous = vault.config();
+    let (current, changed_fields) = previous.merge(&pending.update);
+    current.validate()?;
+    vault.apply_config(&current, now);
+
+    msg!("Config change executed for vault {}. Changed fields: {:#019b}", vault.key(), changed_fields);
+    emit!(VaultConfigChangedEvent {
+        vault: vault.key(),
+        proposer: pending.proposer,
+        previous,
+        current,
+        changed_fields,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+pub fn cancel_config_change_handler(ctx: Context<CancelConfigChange>) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    let authority = ctx.accounts.authority.key();
+    require!(
+        config.is_risk_manager(&authority) || config.is_fee_manager(&authority) || config.is_admin(&authority),
+        FluxError::UnauthorizedAccess
+    );
+
+    msg!("Pending config change for vault {} cancelled", ctx.accounts.pending_config.vault);
+    emit!(ConfigChangeCancelledEvent {
+        vault: ctx.accounts.pending_config.vault,
+        cancelled_by: authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     Ok(())
 }
 
+pub fn emergency_freeze_handler(ctx: Context<crate::UpdateConfig>) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    require!(config.can_freeze(&ctx.accounts.authority.key()), FluxError::UnauthorizedAccess);
+
+    let vault = &mut ctx.accounts.vault;
+    vault.is_frozen = true;
+    vault.freeze_reason = FreezeReason::Manual as u8;
+    msg!("EMERGENCY: Vault {} has been FROZEN.", vault.key());
+    Ok(())
+}
+
+pub fn emergency_unfreeze_handler(ctx: Context<crate::UpdateConfig>) -> Result<()> {
+    let config = &ctx.accounts.protocol_config;
+    require!(config.is_admin(&ctx.accounts.authority.key()), FluxError::UnauthorizedAccess);
+
+    let vault = &mut ctx.accounts.vault;
+    require!(vault.freeze_reason == FreezeReason::Manual as u8, FluxError::CircuitBreakerTripped);
+    vault.is_frozen = false;
+    msg!("Vault {} has been restored to active status.", vault.key());
+    Ok(())
+}
+
+pub fn set_circuit_breaker_handler(ctx: Context<crate::UpdateConfig>, params: CircuitBreakerParams) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    params.validate()?;
+
+    let vault = &mut ctx.accounts.vault;
+    let now = Clock::get()?.unix_timestamp;
+    let previous = circuit_breaker::params(vault);
+    circuit_breaker::set_params(vault, &params, now);
+
+    msg!("Circuit breakers updated for vault {}", vault.key());
+    emit!(CircuitBreakerUpdatedEvent {
+        vault: vault.key(),
+        authority,
+        previous,
+        current: params,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+/// Clear a breaker trip once the guardian has reviewed it. Tracking restarts from the current state.
+pub fn reset_circuit_breaker_handler(ctx: Context<crate::UpdateConfig>) -> Result<()> {
+    let guardian = ctx.accounts.authority.key();
+    require_keys_eq!(ctx.accounts.protocol_config.guardian, guardian, FluxError::UnauthorizedAccess);
+
+    let vault = &mut ctx.accounts.vault;
+    let reason = FreezeReason::from(vault.freeze_reason);
+    require!(vault.is_frozen && reason != FreezeReason::Manual, FluxError::CircuitBreakerNotTripped);
+    let now = Clock::get()?.unix_timestamp;
+    vault.is_frozen = false;
+    vault.freeze_reason = FreezeReason::Manual as u8;
+    circuit_breaker::reset_tracking(vault, now);
+
+    msg!("Circuit breaker ({:?}) reset for vault {}", reason, vault.key());
+    emit!(CircuitBreakerResetEvent {
+        vault: vault.key(),
+        guardian,
+        reason,
+        timestamp: now,
+    });
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::state::governance::PendingAuthority;
+use crate::events::{AuthorityProposedEvent, AuthorityAcceptedEvent, AuthorityTransferCancelledEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(has_one = authority @ FluxError::UnauthorizedAccess)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = authority,
+        space = PendingAuthority::LEN,
+        seeds = [b"pending_authority", vault.key().as_ref()],
+        bump
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_authority", vault.key().as_ref()],
+        bump = pending_authority.bump,
+        has_one = vault,
+        has_one = proposer,
+        has_one = new_authority @ FluxError::UnauthorizedAccess,
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    /// CHECK: Receives the proposal's rent; matched via `has_one`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(has_one = authority @ FluxError::UnauthorizedAccess)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pending_authority", vault.key().as_ref()],
+        bump = pending_authority.bump,
+        has_one = vault,
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+pub fn propose_handler(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+    require_keys_neq!(new_authority, ctx.accounts.authority.key(), FluxError::InvalidAuthority);
+    require_keys_neq!(new_authority, Pubkey::default(), FluxError::InvalidAuthority);
+
+    let pending = &mut ctx.accounts.pending_authority;
+    pending.vault = ctx.accounts.vault.key();
+    pending.proposer = ctx.accounts.authority.key();
+    pending.new_authority = new_authority;
+    pending.proposed_at = Clock::get()?.unix_timestamp;
+    pending.bump = ctx.bumps.pending_authority;
+
+    msg!("Authority transfer proposed for vault {}: {} -> {}", pending.vault, pending.proposer, new_authority);
+    emit!(AuthorityProposedEvent {
+        target: pending.vault,
+        current_authority: pending.proposer,
+        new_authority,
+        timestamp: pending.proposed_at,
+    });
+    Ok(())
+}
+
+pub fn accept_handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let pending = &ctx.accounts.pending_authority;
+    // A proposal only stays valid while its proposer still holds the vault
+    require_keys_eq!(vault.authority, pending.proposer, FluxError::UnauthorizedAccess);
+
+    let previous = vault.authority;
+    vault.authority = pending.new_authority;
+
+    msg!("Vault {} authority transferred: {} -> {}", vault.key(), previous, vault.authority);
+    emit!(AuthorityAcceptedEvent {
+        target: vault.key(),
+        previous_authority: previous,
+        new_authority: vault.authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn cancel_handler(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+    let pending = &ctx.accounts.pending_authority;
+
+    msg!("Authority transfer for vault {} cancelled", pending.vault);
+    emit!(AuthorityTransferCancelledEvent {
+        target: pending.vault,
+        cancelled_by: ctx.accounts.authority.key(),
+        new_authority: pending.new_authority,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
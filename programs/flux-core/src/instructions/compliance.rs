@@ -0,0 +1,186 @@
+use anchor_lang::prelude::*;
+use crate::state::user_profile::UserProfile;
+use crate::state::compliance::{CaseRecord, CaseAction, ComplianceConfig};
+use crate::state::config::ProtocolConfig;
+use crate::events::{AccountFlaggedEvent, AccountFlagClearedEvent, ComplianceConfigUpdatedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct UpdateAmlFlag<'info> {
+    /// Profile under review
+    #[account(mut)]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    
+    /// Appended to the subject's case log, never overwritten
+    #[account(
+        init,
+        payer = officer,
+        space = CaseRecord::LEN,
+        seeds = [b"case", user_profile.key().as_ref(), &user_profile.load()?.case_count.to_le_bytes()],
+        bump
+    )]
+    pub case_record: Account<'info, CaseRecord>,
+    
+    /// Profile proving the signer's auditor / compliance role
+    #[account(
+        seeds = [b"profile", officer.key().as_ref()],
+        bump = officer_profile.load()?.bump,
+        constraint = officer_profile.load()?.is_compliance_officer() @ FluxError::UnauthorizedAccess,
+    )]
+    pub officer_profile: AccountLoader<'info, UserProfile>,
+    #[account(mut)]
+    pub officer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitComplianceConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ComplianceConfig::LEN,
+        seeds = [b"compliance_config"],
+        bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    /// Only the upgrade authority may create the singleton, so it cannot be front-run after deploy
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ FluxError::UnauthorizedAccess)]
+    pub program: Program<'info, crate::program::FluxCore>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ FluxError::UnauthorizedAccess)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateComplianceConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"compliance_config"],
+        bump = compliance_config.bump,
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+pub fn init_compliance_config_handler(ctx: Context<InitComplianceConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.compliance_config;
+    config.travel_rule_threshold = ComplianceConfig::DEFAULT_TRAVEL_RULE_THRESHOLD;
+    config.bump = ctx.bumps.compliance_config;
+    
+    msg!("Compliance config initialized. Travel-rule threshold: {}", config.travel_rule_threshold);
+    Ok(())
+}
+
+pub fn set_travel_rule_threshold_handler(ctx: Context<UpdateComplianceConfig>, threshold: u64) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_admin(&authority), FluxError::UnauthorizedAccess);
+
+    let config = &mut ctx.accounts.compliance_config;
+    config.travel_rule_threshold = threshold;
+    
+    msg!("Travel-rule threshold updated to {}", threshold);
+    emit!(ComplianceConfigUpdatedEvent {
+        authority,
+        travel_rule_threshold: threshold,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn flag_account_handler(ctx: Context<UpdateAmlFlag>, case_id: u64, reason_code: u16) -> Result<()> {
+    let subject = ctx.accounts.user_profile.key();
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    require!(!profile.is_aml_flagged(), FluxError::AccountFlagged);
+    
+    let clock = Clock::get()?;
+    let sequence = append_case(
+        &mut ctx.accounts.case_record,
+        subject,
+        &mut profile,
+        ctx.accounts.officer.key(),
+        CaseAction::Flag,
+        case_id,
+        reason_code,
+        ctx.bumps.case_record,
+        &clock,
+    )?;
+    profile.set_aml_flagged(true);
+    
+    msg!("Profile {} flagged for AML review. Case: {}", subject, case_id);
+    emit!(AccountFlaggedEvent {
+        user_profile: subject,
+        owner: profile.owner,
+        officer: ctx.accounts.officer.key(),
+        case_id,
+        reason_code,
+        sequence,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+pub fn clear_flag_handler(ctx: Context<UpdateAmlFlag>, case_id: u64, reason_code: u16) -> Result<()> {
+    let subject = ctx.accounts.user_profile.key();
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    require!(profile.is_aml_flagged(), FluxError::AccountNotFlagged);
+    
+    let clock = Clock::get()?;
+    let sequence = append_case(
+        &mut ctx.accounts.case_record,
+        subject,
+        &mut profile,
+        ctx.accounts.officer.key(),
+        CaseAction::Clear,
+        case_id,
+        reason_code,
+        ctx.bumps.case_record,
+        &clock,
+    )?;
+    profile.set_aml_flagged(false);
+    
+    msg!("AML flag cleared for profile {}. Case: {}", subject, case_id);
+    emit!(AccountFlagClearedEvent {
+        user_profile: subject,
+        owner: profile.owner,
+        officer: ctx.accounts.officer.key(),
+        case_id,
+        reason_code,
+        sequence,
+        timestamp: clock.unix_timestamp,
+    });
+    
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn append_case(
+    record: &mut CaseRecord,
+    subject: Pubkey,
+    profile: &mut UserProfile,
+    officer: Pubkey,
+    action: CaseAction,
+    case_id: u64,
+    reason_code: u16,
+    bump: u8,
+    clock: &Clock,
+) -> Result<u32> {
+    let sequence = profile.case_count;
+    
+    record.subject = subject;
+    record.sequence = sequence;
+    record.case_id = case_id;
+    record.action = action;
+    record.reason_code = reason_code;
+    record.officer = officer;
+    record.timestamp = clock.unix_timestamp;
+    record.slot = clock.slot;
+    record.bump = bump;
+    
+    profile.case_count = sequence.checked_add(1).ok_or(error!(FluxError::ArithmeticError))?;
+    Ok(sequence)
+}
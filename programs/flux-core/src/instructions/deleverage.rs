@@ -0,0 +1,317 @@
+use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::{Position, AutoDeleverage, AutoDeleverageParams};
+use crate::state::reserve::RiskCategory;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::user_profile::UserProfile;
+use crate::instructions::reserve::{add_position_collateral, position_category};
+use crate::instructions::position::settle_debt;
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+use crate::oracle::{self, OraclePrice};
+use crate::risk::{self, Portfolio, RiskParams};
+use crate::swap::{self, SwapAccounts};
+use crate::events::{PositionDelegateSetEvent, AutoDeleverageUpdatedEvent, PositionDeleveragedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct SetPositionDelegate<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct EnableAutoDeleverage<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = AutoDeleverage::LEN,
+        seeds = [b"auto_deleverage", position.key().as_ref()],
+        bump
+    )]
+    pub automation: Account<'info, AutoDeleverage>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisableAutoDeleverage<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"auto_deleverage", position.key().as_ref()],
+        bump = automation.bump,
+    )]
+    pub automation: Account<'info, AutoDeleverage>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Deleverage<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    /// The owner's profile, whose loan closes if the debt is repaid in full
+    #[account(
+        mut,
+        seeds = [b"profile", position.owner.as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Required when a keeper, rather than the owner or delegate, calls
+    #[account(
+        seeds = [b"auto_deleverage", position.key().as_ref()],
+        bump = automation.bump,
+    )]
+    pub automation: Option<Account<'info, AutoDeleverage>>,
+    /// Required once the position has opted into a risk category
+    #[account(mut)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>, // Owner, delegate or keeper
+    /// CHECK: Keeper token account (receives the keeper fee)
+    #[account(mut)]
+    pub keeper_token_account: UncheckedAccount<'info>,
+    /// CHECK: Owner token account (receives swap output beyond the debt)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Jupiter aggregator program V6
+    pub jupiter_program: UncheckedAccount<'info>,
+    /// CHECK: Token account source (Collateral)
+    #[account(mut)]
+    pub token_in: UncheckedAccount<'info>,
+    /// CHECK: Token account destination (Debt Asset)
+    #[account(mut)]
+    pub token_out: UncheckedAccount<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+pub fn set_delegate_handler(ctx: Context<SetPositionDelegate>, delegate: Option<Pubkey>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.delegate = delegate.unwrap_or_default();
+
+    msg!("Position {} delegate set to {:?}", position.key(), delegate);
+    emit!(PositionDelegateSetEvent {
+        position: position.key(),
+        delegate,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn enable_auto_handler(ctx: Context<EnableAutoDeleverage>, params: AutoDeleverageParams) -> Result<()> {
+    params.validate()?;
+
+    let automation = &mut ctx.accounts.automation;
+    automation.position = ctx.accounts.position.key();
+    automation.trigger_health_factor = params.trigger_health_factor;
+    automation.max_repay_bps = params.max_repay_bps;
+    automation.keeper_fee = params.keeper_fee;
+    automation.max_slippage_bps = params.max_slippage_bps;
+    automation.bump = ctx.bumps.automation;
+
+    msg!("Auto-deleverage enabled below health factor {}", params.trigger_health_factor);
+    emit!(AutoDeleverageUpdatedEvent {
+        position: automation.position,
+        params: Some(params),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn disable_auto_handler(ctx: Context<DisableAutoDeleverage>) -> Result<()> {
+    msg!("Auto-deleverage disabled for {}", ctx.accounts.position.key());
+    emit!(AutoDeleverageUpdatedEvent {
+        position: ctx.accounts.position.key(),
+        params: None,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn handler(ctx: Context<Deleverage>, collateral_amount: u64, min_amount_out: u64) -> Result<()> {
+    require!(collateral_amount > 0, FluxError::InvalidAmount);
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let caller = ctx.accounts.authority.key();
+    let clock = Clock::get()?;
+
+    // 0. Security Checks
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::LIQUIDATIONS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+    require!(position.debt_amount > 0, FluxError::InvalidAmount);
+
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let health_before = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?
+        .health_factor()?;
+
+    // 1. The owner and delegate act freely; a keeper only below the owner's trigger, for its fee
+    let keeper_terms = if position.can_manage(&caller) {
+        None
+    } else {
+        let automation = ctx.accounts.automation.as_ref().ok_or(error!(FluxError::UnauthorizedAccess))?;
+        require!(health_before < automation.trigger_health_factor, FluxError::DeleverageNotTriggered);
+        Some((automation.max_repay_bps, automation.keeper_fee, automation.max_slippage_bps))
+    };
+    let keeper_fee = keeper_terms.map_or(0, |(_, fee, _)| fee);
+    // A keeper cannot loosen the owner's slippage bound around the oracle quote, only tighten it
+    let min_amount_out = match keeper_terms {
+        Some((_, _, max_slippage_bps)) => {
+            let quote = risk::amount_for_value(risk::value(collateral_amount, collateral_price)?, debt_price)?;
+            let floor = (quote as u128 * (10000 - max_slippage_bps as u128) / 10000) as u64;
+            min_amount_out.max(floor)
+        }
+        None => min_amount_out,
+    };
+    let sold = collateral_amount.checked_add(keeper_fee).ok_or(error!(FluxError::ArithmeticError))?;
+    require!(sold <= position.collateral_amount, FluxError::InsufficientCollateral);
+
+    // Collateral leaves the vault; an insolvent vault is frozen rather than deleveraged
+    if circuit_breaker::check(vault, sold, Some(ctx.accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(());
+    }
+
+    // 2. Swap collateral for the debt asset and repay. No liquidation penalty applies.
+    let debt_before = position.debt_amount;
+    let repaid = sell_collateral_for_debt(
+        vault,
+        position,
+        ctx.accounts.risk_category.as_mut(),
+        &mut *ctx.accounts.user_profile.load_mut()?,
+        &SwapAccounts {
+            jupiter_program: &ctx.accounts.jupiter_program.to_account_info(),
+            token_in: &ctx.accounts.token_in.to_account_info(),
+            token_out: &ctx.accounts.token_out.to_account_info(),
+        },
+        collateral_amount,
+        keeper_fee,
+        (collateral_price, debt_price),
+        min_amount_out,
+        clock.unix_timestamp,
+    )?;
+    if let Some((max_repay_bps, _, _)) = keeper_terms {
+        let limit = (debt_before as u128 * max_repay_bps as u128 / 10000) as u64;
+        require!(repaid <= limit, FluxError::DeleverageLimitExceeded);
+    }
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+    // Token transfer of 'keeper_fee' to 'keeper_token_account' would go here
+
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let health_after = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?
+        .health_factor()?;
+    require!(health_after > health_before, FluxError::DeleverageWorsensHealth);
+
+    msg!("Deleveraged: sold {} collateral, repaid {}. Health: {} -> {}",
+        collateral_amount, repaid, health_before, health_after);
+    emit!(PositionDeleveragedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        caller,
+        collateral_sold: collateral_amount,
+        repaid,
+        keeper_fee,
+        health_factor_before: health_before,
+        health_factor_after: health_after,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Swap `collateral_amount` of the position's base collateral for the debt asset and repay
+/// with the output, settling it against the owner's `profile`; any excess goes to the owner.
+/// `fee` more collateral is taken for the caller. Returns the debt repaid.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn sell_collateral_for_debt(
+    vault: &mut Account<Vault>,
+    position: &mut Account<Position>,
+    risk_category: Option<&mut Account<RiskCategory>>,
+    profile: &mut UserProfile,
+    swap_accounts: &SwapAccounts,
+    collateral_amount: u64,
+    fee: u64,
+    (collateral_price, debt_price): (OraclePrice, OraclePrice),
+    min_amount_out: u64,
+    now: i64,
+) -> Result<u64> {
+    let sold = collateral_amount.checked_add(fee).ok_or(error!(FluxError::ArithmeticError))?;
+    require!(sold <= position.collateral_amount, FluxError::InsufficientCollateral);
+
+    let amount_out = swap::swap(swap_accounts, collateral_amount, collateral_price, debt_price, min_amount_out)?;
+    let repaid = amount_out.min(position.debt_amount);
+    require!(repaid > 0, FluxError::InvalidAmount);
+
+    position.collateral_amount -= sold;
+    vault.total_collateral = vault.total_collateral.saturating_sub(sold);
+    settle_debt(vault, position, risk_category.map(|category| &mut **category), profile, repaid, now)?;
+    // Token transfer of any output beyond the debt to the owner would go here
+    Ok(repaid)
+}
+
+/// The position's full portfolio at vault-level limits, as liquidation sees it.
+pub(crate) fn position_portfolio(
+    vault: &Account<Vault>,
+    position: &Account<Position>,
+    category: Option<&RiskCategory>,
+    collateral_price: OraclePrice,
+    debt_price: OraclePrice,
+    accounts: &[AccountInfo],
+) -> Result<Portfolio> {
+    let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+    add_position_collateral(
+        &mut portfolio,
+        vault,
+        position,
+        category,
+        collateral_price,
+        &RiskParams::for_vault(vault)?,
+        accounts,
+    )?;
+    Ok(portfolio)
+}
@@ -1,13 +1,50 @@
 use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
 use crate::state::vault::Vault;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::supply::SupplyPosition;
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+
+#[derive(Accounts)]
+pub struct OpenSupplyPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = SupplyPosition::LEN,
+        seeds = [b"supply_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub supply_position: Account<'info, SupplyPosition>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
 
 #[derive(Accounts)]
 pub struct FetchAssets<'info> {
     #[account(mut)]
     pub vault: Account<'info, Vault>,
+    /// Credited with shares for the deposit
+    #[account(
+        mut,
+        seeds = [b"supply_position", vault.key().as_ref(), authority.key().as_ref()],
+        bump = supply_position.bump,
+    )]
+    pub supply_position: Account<'info, SupplyPosition>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     pub system_program: Program<'info, System>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
     
     // Additional accounts for "deep scanning"
     /// CHECK: Simulation account
@@ -16,14 +53,28 @@ pub struct FetchAssets<'info> {
     pub history_buffer: UncheckedAccount<'info>,
 }
 
+pub fn open_supply_handler(ctx: Context<OpenSupplyPosition>) -> Result<()> {
+    let supply_position = &mut ctx.accounts.supply_position;
+    supply_position.vault = ctx.accounts.vault.key();
+    supply_position.owner = ctx.accounts.owner.key();
+    supply_position.bump = ctx.bumps.supply_position;
+
+    msg!("Supply position opened in vault {} for {}", supply_position.vault, supply_position.owner);
+    Ok(())
+}
+
 pub fn handler(ctx: Context<FetchAssets>, amount: u64) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
     let clock = Clock::get()?;
     
     // 1. Pre-fetch validation
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::DEPOSITS)?;
+    require!(!vault.is_frozen, crate::errors::FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
     if amount == 0 {
         return Err(error!(crate::errors::FluxError::InvalidAmount));
     }
+    vault.require_supply_cap(amount)?;
     
     msg!("Init Fetch Protocol ID: {}", ctx.program_id);
     msg!("Target Vault: {}", vault.key());
@@ -58,16 +109,21 @@ pub fn handler(ctx: Context<FetchAssets>, amount: u64) -> Result<()> {
     // 4. Update Vault State
     // Accrue interest before modifying principal
     vault.accrue_interest(clock.unix_timestamp)?;
+    if circuit_breaker::check(vault, 0, None, clock.unix_timestamp)? {
+        return Ok(());
+    }
+    circuit_breaker::record_inflow(vault, amount, clock.unix_timestamp);
     
     let _previous_balance = vault.total_assets;
     vault.total_assets = vault.total_assets.checked_add(amount).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+    let supply_position = &mut ctx.accounts.supply_position;
+    supply_position.shares = supply_position.shares.checked_add(amount).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
     
     // 5. Update Historical Analytics
     // Mock updating a circular buffer of "Recent Deposits"
     msg!("Updating on-chain analytics...");
-    let utilization_rate = (vault.total_liabilities as u128)
-        .checked_mul(10000).unwrap()
-        .checked_div(vault.total_assets as u128).unwrap_or(0);
+    let utilization_rate = vault.utilization_bps();
         
     msg!("New Vault Utilization: {} bps", utilization_rate);
     
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::sysvar::instructions::{
+    self as ix_sysvar, load_current_index_checked, load_instruction_at_checked,
+};
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::events::{FlashBorrowEvent, FlashRepayEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct FlashBorrow<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub borrower: Signer<'info>,
+    /// CHECK: Borrower token account (receives the loan)
+    #[account(mut)]
+    pub borrower_token_account: UncheckedAccount<'info>,
+    /// CHECK: Instructions sysvar, used to find the matching `flash_repay`
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FlashRepay<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    pub repayer: Signer<'info>,
+    /// CHECK: Repayer token account (source of principal and fee)
+    #[account(mut)]
+    pub repayer_token_account: UncheckedAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    /// CHECK: Insurance fund token account; must be the one set on the protocol config
+    #[account(mut, address = protocol_config.insurance_fund @ FluxError::InsuranceFundMismatch)]
+    pub insurance_fund: UncheckedAccount<'info>,
+    /// CHECK: Incinerator program
+    pub incinerator_program: UncheckedAccount<'info>,
+}
+
+/// Fee shares in bps; the incinerator receives the remainder.
+pub const DEPOSITOR_FEE_SHARE_BPS: u64 = 7000;
+pub const INSURANCE_FEE_SHARE_BPS: u64 = 2000;
+
+pub fn borrow_handler(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    require!(amount > 0, FluxError::InvalidAmount);
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::BORROWS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    // Covers reentrancy: a second borrow before repayment sees the open loan
+    vault.require_no_flash_loan()?;
+
+    let available = vault.total_assets.saturating_sub(vault.total_liabilities);
+    require!(amount <= available, FluxError::InsufficientLiquidity);
+
+    // 1. Must be a top-level instruction, so the repayment check below sees the real transaction
+    let sysvar = ctx.accounts.instructions_sysvar.to_account_info();
+    let current = load_current_index_checked(&sysvar)? as usize;
+    let current_ix = load_instruction_at_checked(current, &sysvar)?;
+    require_keys_eq!(current_ix.program_id, crate::ID, FluxError::FlashLoanCpiForbidden);
+
+    // 2. A later top-level `flash_repay` for this vault must exist
+    let vault_key = vault.key();
+    let mut repaid = false;
+    let mut index = current + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, &sysvar) {
+        if ix.program_id == crate::ID
+            && ix.data.get(..8) == Some(&crate::instruction::FlashRepay::DISCRIMINATOR[..])
+            && ix.accounts.first().map(|meta| meta.pubkey) == Some(vault_key)
+        {
+            repaid = true;
+            break;
+        }
+        index += 1;
+    }
+    require!(repaid, FluxError::FlashLoanNotRepaid);
+
+    // 3. Open the loan
+    let fee = (amount as u128 * vault.flash_loan_fee_rate as u128).div_ceil(10000) as u64;
+    vault.flash_loan_amount = amount;
+    vault.flash_loan_fee = fee;
+
+    // Token transfer to 'borrower_token_account' would go here
+    msg!("Flash loan of {} issued. Due: {} + {} fee", amount, amount, fee);
+    emit!(FlashBorrowEvent {
+        vault: vault_key,
+        borrower: ctx.accounts.borrower.key(),
+        amount,
+        fee,
+    });
+    Ok(())
+}
+
+pub fn repay_handler(ctx: Context<FlashRepay>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let amount = vault.flash_loan_amount;
+    let fee = vault.flash_loan_fee;
+    require!(amount > 0, FluxError::NoActiveFlashLoan);
+
+    // Token transfer of principal + fee from 'repayer_token_account' would go here
+
+    // Fee split: depositors accrue through total_assets, the rest leaves the vault
+    let depositor_share = (fee as u128 * DEPOSITOR_FEE_SHARE_BPS as u128 / 10000) as u64;
+    let insurance_share = (fee as u128 * INSURANCE_FEE_SHARE_BPS as u128 / 10000) as u64;
+    let incinerator_share = fee - depositor_share - insurance_share;
+
+    vault.total_assets = vault.total_assets.checked_add(depositor_share).ok_or(error!(FluxError::ArithmeticError))?;
+    // Token transfer of 'insurance_share' to 'insurance_fund' would go here
+    // ... CPI to Flux Incinerator to burn 'incinerator_share' ...
+
+    vault.flash_loan_amount = 0;
+    vault.flash_loan_fee = 0;
+
+    msg!("Flash loan repaid: {} + {} fee", amount, fee);
+    emit!(FlashRepayEvent {
+        vault: vault.key(),
+        repayer: ctx.accounts.repayer.key(),
+        amount,
+        fee,
+        depositor_share,
+        insurance_share,
+        incinerator_share,
+    });
+    Ok(())
+}
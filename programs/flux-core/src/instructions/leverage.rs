@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::RiskCategory;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::user_profile::UserProfile;
+// Glob import brings in the client modules Anchor generates for the nested `ManageDebt`
+use crate::instructions::position::*;
+use crate::instructions::reserve::position_category;
+use crate::instructions::deleverage::{sell_collateral_for_debt, position_portfolio};
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+use crate::oracle;
+use crate::risk::{self, Portfolio};
+use crate::swap::{self, SwapAccounts};
+use crate::events::{PositionLeveragedEvent, PositionUnwoundEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct Leverage<'info> {
+    pub debt: ManageDebt<'info>,
+
+    /// CHECK: Jupiter aggregator program V6
+    pub jupiter_program: UncheckedAccount<'info>,
+    /// CHECK: Token account source (Debt Asset)
+    #[account(mut)]
+    pub token_in: UncheckedAccount<'info>,
+    /// CHECK: Token account destination (Collateral)
+    #[account(mut)]
+    pub token_out: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+#[derive(Accounts)]
+pub struct Unwind<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Required once the position has opted into a risk category
+    #[account(mut)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (receives swap output beyond the debt)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Jupiter aggregator program V6
+    pub jupiter_program: UncheckedAccount<'info>,
+    /// CHECK: Token account source (Collateral)
+    #[account(mut)]
+    pub token_in: UncheckedAccount<'info>,
+    /// CHECK: Token account destination (Debt Asset)
+    #[account(mut)]
+    pub token_out: UncheckedAccount<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+/// Equity (collateral less debt) in value units. Fails for positions already underwater.
+fn equity(portfolio: &Portfolio) -> Result<u128> {
+    let equity = portfolio.collateral_value.saturating_sub(portfolio.debt_value);
+    require!(equity > 0, FluxError::InsufficientCollateral);
+    Ok(equity)
+}
+
+pub fn leverage_handler(ctx: Context<Leverage>, target_leverage_bps: u64, min_collateral_out: u64) -> Result<()> {
+    let accounts = &mut ctx.accounts.debt;
+    let max_leverage_bps = accounts.vault.max_leverage as u64 * 10000;
+    require!(
+        target_leverage_bps > 10000 && target_leverage_bps <= max_leverage_bps,
+        FluxError::LeverageExceeded
+    );
+
+    // 1. Size the loan so collateral reaches `target` times equity at oracle prices
+    let collateral_price = oracle::read_price(&accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&accounts.price_feed_debt)?;
+    let category = position_category(&accounts.position, accounts.risk_category.as_ref())?;
+    let before = position_portfolio(
+        &accounts.vault, &accounts.position, category, collateral_price, debt_price, ctx.remaining_accounts,
+    )?;
+    let target_value = equity(&before)? * target_leverage_bps as u128 / 10000;
+    let loan_value = target_value.saturating_sub(before.collateral_value);
+    let amount = risk::amount_for_value(loan_value, debt_price)?;
+    require!(amount > 0, FluxError::InvalidAmount);
+
+    // 2. The loan acts as a flash borrow: swapped into collateral, deposited, and only
+    //    then checked against the position's limits, all within this instruction
+    let mut bought = 0;
+    let swap_accounts = SwapAccounts {
+        jupiter_program: &ctx.accounts.jupiter_program.to_account_info(),
+        token_in: &ctx.accounts.token_in.to_account_info(),
+        token_out: &ctx.accounts.token_out.to_account_info(),
+    };
+    let borrowed = borrow(accounts, ctx.remaining_accounts, amount, |collateral_price, debt_price| {
+        bought = swap::swap(&swap_accounts, amount, debt_price, collateral_price, min_collateral_out)?;
+        Ok(bought)
+    })?;
+    if !borrowed {
+        return Ok(());
+    }
+
+    // 3. Slippage can overshoot the target; never past the vault's cap
+    let category = position_category(&accounts.position, accounts.risk_category.as_ref())?;
+    let leverage_bps = position_portfolio(
+        &accounts.vault, &accounts.position, category, collateral_price, debt_price, ctx.remaining_accounts,
+    )?.leverage_bps()?;
+    require!(leverage_bps <= max_leverage_bps, FluxError::LeverageExceeded);
+
+    msg!("Leveraged to {} bps: borrowed {}, bought {} collateral", leverage_bps, amount, bought);
+    emit!(PositionLeveragedEvent {
+        vault: accounts.vault.key(),
+        position: accounts.position.key(),
+        borrowed: amount,
+        collateral_bought: bought,
+        leverage_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn unwind_handler(ctx: Context<Unwind>, target_leverage_bps: u64, min_amount_out: u64) -> Result<()> {
+    require!(target_leverage_bps >= 10000, FluxError::LeverageExceeded);
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let clock = Clock::get()?;
+
+    // Unwinding sells collateral the same way deleverage does
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::LIQUIDATIONS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+    require!(position.debt_amount > 0, FluxError::InvalidAmount);
+
+    // 1. Sell enough collateral to bring collateral down to `target` times equity; 1x repays everything
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let before = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?;
+    let target_value = equity(&before)? * target_leverage_bps as u128 / 10000;
+    let repay_value = before.collateral_value.saturating_sub(target_value).min(before.debt_value);
+    let collateral_amount = risk::amount_for_value(repay_value, collateral_price)?
+        .min(position.collateral_amount);
+    require!(collateral_amount > 0, FluxError::InvalidAmount);
+
+    if circuit_breaker::check(vault, collateral_amount, Some(ctx.accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(());
+    }
+
+    // 2. Swap and repay
+    let repaid = sell_collateral_for_debt(
+        vault,
+        position,
+        ctx.accounts.risk_category.as_mut(),
+        &mut *ctx.accounts.user_profile.load_mut()?,
+        &SwapAccounts {
+            jupiter_program: &ctx.accounts.jupiter_program.to_account_info(),
+            token_in: &ctx.accounts.token_in.to_account_info(),
+            token_out: &ctx.accounts.token_out.to_account_info(),
+        },
+        collateral_amount,
+        0,
+        (collateral_price, debt_price),
+        min_amount_out,
+        clock.unix_timestamp,
+    )?;
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let after = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?;
+    require!(after.health_factor()? >= before.health_factor()?, FluxError::DeleverageWorsensHealth);
+    let leverage_bps = after.leverage_bps()?;
+
+    msg!("Unwound to {} bps: sold {} collateral, repaid {}", leverage_bps, collateral_amount, repaid);
+    emit!(PositionUnwoundEvent {
+        vault: vault.key(),
+        position: position.key(),
+        collateral_sold: collateral_amount,
+        repaid,
+        leverage_bps,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
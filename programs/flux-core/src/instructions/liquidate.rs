@@ -1,11 +1,52 @@
 use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
 use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::{Reserve, CollateralDeposit, RiskCategory};
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::user_profile::{UserProfile, ActionType};
+use crate::instructions::position::settle_debt;
+use crate::instructions::reserve::{add_position_collateral, position_category, reserve_price};
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+use crate::oracle::{self, OraclePrice};
+use crate::swap;
+use crate::risk::{self, Portfolio, RiskParams};
+use crate::events::PositionLiquidatedEvent;
 use crate::errors::FluxError;
 
 #[derive(Accounts)]
 pub struct LiquidatePosition<'info> {
     #[account(mut)]
     pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    /// The owner's profile, which records the liquidation against its reputation
+    #[account(
+        mut,
+        seeds = [b"profile", position.owner.as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Collateral to seize; the position's base collateral when omitted
+    #[account(mut, has_one = vault)]
+    pub collateral_reserve: Option<Account<'info, Reserve>>,
+    #[account(mut, constraint = collateral_deposit.position == position.key() @ FluxError::ReserveAccountsMismatch)]
+    pub collateral_deposit: Option<Account<'info, CollateralDeposit>>,
+    /// Required once the position has opted into a risk category
+    #[account(mut)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
     /// CHECK: Jupiter aggregator program V6
     pub jupiter_program: UncheckedAccount<'info>,
     /// CHECK: Token account source (Collateral)
@@ -19,51 +60,81 @@ pub struct LiquidatePosition<'info> {
     pub liquidator_token_account: UncheckedAccount<'info>,
     
     pub authority: Signer<'info>, // Liquidator
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
     pub token_program: Program<'info, anchor_spl::token::Token>,
     pub system_program: Program<'info, System>,
     
     // Oracle Accounts for Price Feeds
-    /// CHECK: Collateral Price Feed
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
     pub price_feed_collateral: UncheckedAccount<'info>,
-    /// CHECK: Debt Price Feed
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
     pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
 }
 
 pub fn handler(ctx: Context<LiquidatePosition>) -> Result<()> {
     let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
     let clock = Clock::get()?;
 
     // 0. Security Checks
-    require!(!vault.is_frozen, FluxError::UnauthorizedAccess);
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::LIQUIDATIONS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
     vault.accrue_interest(clock.unix_timestamp)?;
 
-    msg!("Initiating Liquidation Sequence for Vault: {}", vault.key());
+    msg!("Initiating Liquidation Sequence for Position: {}", position.key());
 
-    // 1. Calculate Health Factor (Risk Engine)
-    // We assume mocked prices for now since we don't have real Pyth accounts in this context
-    let collateral_price = 150_000_000; // $150.00
-    let debt_price = 1_000_000; // $1.00 (USDC)
-    
-    let health_factor = _calculate_complex_health_factor(
+    // 1. Calculate Health Factor across every collateral asset (Risk Engine)
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+    add_position_collateral(
+        &mut portfolio,
         vault,
+        position,
+        category,
         collateral_price,
-        debt_price
+        &RiskParams::for_vault(vault)?,
+        ctx.remaining_accounts,
     )?;
+    let health_factor = portfolio.health_factor()?;
     
     msg!("Current Health Factor: {}", health_factor);
     
-    // Liquidation threshold is 1.0 (represented as 10000 bps or raw scaled value)
-    // If HF < 1.0, liquidation is allowed
-    let liquidation_threshold = 10000;
-    if health_factor >= liquidation_threshold {
-        msg!("Vault is solvent. Liquidation rejected.");
+    // Liquidation is allowed once HF drops below 1.0
+    if health_factor >= risk::HEALTH_FACTOR_ONE {
+        msg!("Position is healthy. Liquidation rejected.");
         return Err(error!(FluxError::VaultHealthy));
     }
 
     // 2. Calculate Liquidation Amounts
-    // Max liquidation amount is usually 50% of debt to prevent cascading failures
-    let max_repay_amount = vault.total_liabilities / 2;
-    msg!("Max Repay Allowed: {}", max_repay_amount);
+    // The liquidator's chosen collateral is seized worth the repaid debt plus the bonus
+    let (seize_price, available) = match (&ctx.accounts.collateral_reserve, &ctx.accounts.collateral_deposit) {
+        (Some(reserve), Some(deposit)) => {
+            require_keys_eq!(deposit.reserve, reserve.key(), FluxError::ReserveAccountsMismatch);
+            (reserve_price(reserve, ctx.remaining_accounts)?, reserve.normalize(deposit.amount)?)
+        }
+        (None, None) => (collateral_price, position.collateral_amount),
+        _ => return Err(error!(FluxError::ReserveAccountsMismatch)),
+    };
+    let (repay_amount, mut seize_amount) = liquidation_amounts(vault, position.debt_amount, debt_price, seize_price, available)?;
+    // Reserve collateral is priced in base units but held in the mint's own
+    if let Some(reserve) = &ctx.accounts.collateral_reserve {
+        seize_amount = reserve.denormalize(seize_amount)?;
+        require!(seize_amount > 0, FluxError::InvalidAmount);
+    }
+    msg!("Repaying {} debt for {} collateral", repay_amount, seize_amount);
+
+    // Seized base collateral leaves the vault; an insolvent vault is frozen rather than liquidated
+    let base_outflow = if ctx.accounts.collateral_reserve.is_none() { seize_amount } else { 0 };
+    if circuit_breaker::check(vault, base_outflow, Some(ctx.accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(());
+    }
 
     // 3. Prepare Jupiter Swap (CPI)
     // We need to swap User's Collateral -> Stablecoin to repay debt
@@ -72,10 +143,10 @@ pub fn handler(ctx: Context<LiquidatePosition>) -> Result<()> {
     
     // Mocking Route Data Construction
     // In production, this matches the Jupiter IDL layout
-    let _route_data = _build_jupiter_route_data(
+    let _route_data = swap::route_data(
         ctx.accounts.token_in.key(),
         ctx.accounts.token_out.key(),
-        max_repay_amount,
+        seize_amount,
         50 // 0.5% slippage
     );
     
@@ -98,64 +169,73 @@ pub fn handler(ctx: Context<LiquidatePosition>) -> Result<()> {
     // jupiter::swap(cpi_ctx, route_data)?;
     */
     // Simulation:
-    msg!("CPI Success: Swapped {} Collateral for Debt Asset", max_repay_amount);
+    msg!("CPI Success: Swapped {} Collateral for Debt Asset", seize_amount);
 
-    // 5. Settle Debt
-    // Assuming swap returned enough to cover 'max_repay_amount' worth of debt
-    vault.total_liabilities = vault.total_liabilities.checked_sub(max_repay_amount).unwrap_or(0);
-    vault.total_assets = vault.total_assets.checked_sub(max_repay_amount).unwrap_or(0); // Rough approximation of collateral reduction
+    // 5. Settle Debt, counting the liquidation against the owner's reputation
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    settle_debt(
+        vault,
+        position,
+        ctx.accounts.risk_category.as_deref_mut(),
+        &mut profile,
+        repay_amount,
+        clock.unix_timestamp,
+    )?;
+    profile.record_action(ActionType::Liquidated, repay_amount, &clock);
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
 
-    // 6. Pay Liquidation Bonus to Caller
-    let bonus_rate = vault.liquidation_bonus as u64; // e.g. 500 = 5%
-    let bonus_amount = max_repay_amount.checked_mul(bonus_rate).unwrap().checked_div(10000).unwrap();
-    
-    msg!("Distributing Liquidation Bonus: {} tokens", bonus_amount);
+    // 6. Release the seized collateral, bonus included, to the liquidator
+    match (ctx.accounts.collateral_reserve.as_mut(), ctx.accounts.collateral_deposit.as_mut()) {
+        (Some(reserve), Some(deposit)) => {
+            deposit.amount -= seize_amount;
+            reserve.total_deposits = reserve.total_deposits.saturating_sub(seize_amount);
+        }
+        _ => {
+            position.collateral_amount -= seize_amount;
+            vault.total_collateral = vault.total_collateral.saturating_sub(seize_amount);
+        }
+    }
     // Token transfer logic to 'liquidator_token_account' would go here
     
-    // 7. Apply Penalties & Update Risk Factor
-    vault.apply_risk_adjustment(800); // Assume high volatility caused this
-    
-    msg!("Liquidation Complete. Vault Risk Factor updated to: {}", vault.risk_factor);
+    // Risk parameters are maintained separately by the `refresh_risk` crank
+    msg!("Liquidation Complete. Position debt: {}", position.debt_amount);
+    emit!(PositionLiquidatedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        liquidator: ctx.accounts.authority.key(),
+        collateral_reserve: ctx.accounts.collateral_reserve.as_ref().map(|reserve| reserve.key()),
+        repaid: repay_amount,
+        seized: seize_amount,
+        health_factor,
+        timestamp: clock.unix_timestamp,
+    });
     
     Ok(())
 }
 
-fn _calculate_complex_health_factor(vault: &Vault, col_price: u64, debt_price: u64) -> Result<u64> {
-    if vault.total_liabilities == 0 {
-        return Ok(u64::MAX);
-    }
-    
-    // (CollateralAmt * CollateralPrice * LTV) / (DebtAmt * DebtPrice)
-    let col_val = (vault.total_assets as u128)
-        .checked_mul(col_price as u128).unwrap();
-        
-    // LTV is derived from risk factor, e.g., 80% base - risk factor
-    let base_ltv = 8000u128; // 80%
-    let adjusted_ltv = base_ltv.saturating_sub(vault.risk_factor as u128);
-    
-    let weighted_collateral = col_val.checked_mul(adjusted_ltv).unwrap().checked_div(10000).unwrap();
-    
-    let debt_val = (vault.total_liabilities as u128)
-        .checked_mul(debt_price as u128).unwrap();
-        
-    let hf = weighted_collateral.checked_mul(10000).unwrap().checked_div(debt_val).unwrap(); // Scaled by 10000
-    
-    Ok(hf as u64)
-}
+/// Debt repaid and collateral seized by one liquidation: half the debt, paid for with
+/// collateral worth the repayment plus the vault's bonus. With less than that `available`,
+/// the repayment shrinks in proportion.
+pub(crate) fn liquidation_amounts(
+    vault: &Vault,
+    debt_amount: u64,
+    debt_price: OraclePrice,
+    seize_price: OraclePrice,
+    available: u64,
+) -> Result<(u64, u64)> {
+    // Max liquidation amount is usually 50% of debt to prevent cascading failures
+    let mut repay_amount = debt_amount / 2;
+    require!(repay_amount > 0, FluxError::InvalidAmount);
 
-fn _build_jupiter_route_data(
-    input_mint: Pubkey,
-    output_mint: Pubkey,
-    amount_in: u64,
-    slippage_bps: u16
-) -> Vec<u8> {
-    // Mock binary layout for Jupiter Swap instruction
-    let mut data = Vec::with_capacity(256);
-    data.extend_from_slice(&[0xe5, 0x12, 0x3a]); // Discriminator
-    data.extend_from_slice(&amount_in.to_le_bytes());
-    data.extend_from_slice(&slippage_bps.to_le_bytes());
-    data.extend_from_slice(input_mint.as_ref());
-    data.extend_from_slice(output_mint.as_ref());
-    // ... padding ...
-    data
+    let bonus_factor = 10000 + vault.liquidation_bonus as u128;
+    let seize_value = risk::value(repay_amount, debt_price)?
+        .checked_mul(bonus_factor).ok_or(error!(FluxError::ArithmeticError))? / 10000;
+    let mut seize_amount = risk::amount_for_value(seize_value, seize_price)?;
+    if seize_amount > available {
+        // Not enough of this collateral: repay proportionally less
+        repay_amount = (repay_amount as u128 * available as u128 / seize_amount as u128) as u64;
+        seize_amount = available;
+    }
+    require!(repay_amount > 0 && seize_amount > 0, FluxError::InvalidAmount);
+    Ok((repay_amount, seize_amount))
 }
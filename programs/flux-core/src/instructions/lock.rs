@@ -0,0 +1,282 @@
+use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::deposit_lock::{LockConfig, LockTerms, LockTerm, LockAccount, DepositLock};
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+use crate::events::{LockTermsUpdatedEvent, DepositLockedEvent, DepositUnlockedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitLockConfig<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = authority,
+        space = LockConfig::LEN,
+        seeds = [b"lock_config", vault.key().as_ref()],
+        bump
+    )]
+    pub lock_config: Account<'info, LockConfig>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateLockConfig<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"lock_config", vault.key().as_ref()],
+        bump = lock_config.bump,
+    )]
+    pub lock_config: Account<'info, LockConfig>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLockAccount<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = LockAccount::LEN,
+        seeds = [b"lock_account", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub lock_account: Account<'info, LockAccount>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct LockDeposit<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"lock_config", vault.key().as_ref()],
+        bump = lock_config.bump,
+    )]
+    pub lock_config: Account<'info, LockConfig>,
+    #[account(
+        mut,
+        seeds = [b"lock_account", vault.key().as_ref(), owner.key().as_ref()],
+        bump = lock_account.bump,
+    )]
+    pub lock_account: Account<'info, LockAccount>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (source)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UnlockDeposit<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"lock_config", vault.key().as_ref()],
+        bump = lock_config.bump,
+    )]
+    pub lock_config: Account<'info, LockConfig>,
+    #[account(
+        mut,
+        seeds = [b"lock_account", vault.key().as_ref(), owner.key().as_ref()],
+        bump = lock_account.bump,
+    )]
+    pub lock_account: Account<'info, LockAccount>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (destination)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+    /// CHECK: Incinerator program
+    pub incinerator_program: UncheckedAccount<'info>,
+}
+
+pub fn init_config_handler(ctx: Context<InitLockConfig>, terms: LockTerms) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_fee_manager(&authority), FluxError::UnauthorizedAccess);
+    terms.validate()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    let lock_config = &mut ctx.accounts.lock_config;
+    lock_config.vault = ctx.accounts.vault.key();
+    lock_config.apply_terms(&terms);
+    lock_config.bump = ctx.bumps.lock_config;
+    // Starts the management fee clock for vaults that never collected it
+    lock_config.collect_management_fee(&mut ctx.accounts.vault, now)?;
+
+    msg!("Deposit locks enabled for vault {}", lock_config.vault);
+    emit!(LockTermsUpdatedEvent {
+        vault: lock_config.vault,
+        previous: None,
+        current: terms,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+pub fn update_config_handler(ctx: Context<UpdateLockConfig>, terms: LockTerms) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_fee_manager(&authority), FluxError::UnauthorizedAccess);
+    terms.validate()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Open locks keep the boost they were promised
+    let lock_config = &mut ctx.accounts.lock_config;
+    lock_config.collect_management_fee(&mut ctx.accounts.vault, now)?;
+    let previous = lock_config.terms();
+    lock_config.apply_terms(&terms);
+
+    msg!("Lock terms updated for vault {}", lock_config.vault);
+    emit!(LockTermsUpdatedEvent {
+        vault: lock_config.vault,
+        previous: Some(previous),
+        current: terms,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+pub fn open_account_handler(ctx: Context<OpenLockAccount>) -> Result<()> {
+    let lock_account = &mut ctx.accounts.lock_account;
+    lock_account.vault = ctx.accounts.vault.key();
+    lock_account.owner = ctx.accounts.owner.key();
+    lock_account.bump = ctx.bumps.lock_account;
+
+    msg!("Lock account opened for {}", lock_account.owner);
+    Ok(())
+}
+
+pub fn lock_handler(ctx: Context<LockDeposit>, amount: u64, term: LockTerm) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &mut ctx.accounts.vault;
+    let lock_config = &mut ctx.accounts.lock_config;
+    let lock_account = &mut ctx.accounts.lock_account;
+    let clock = Clock::get()?;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::DEPOSITS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    require!(lock_account.locks.len() < LockAccount::MAX_LOCKS, FluxError::TooManyLocks);
+    vault.accrue_interest(clock.unix_timestamp)?;
+    if circuit_breaker::check(vault, 0, None, clock.unix_timestamp)? {
+        return Ok(());
+    }
+    vault.require_supply_cap(amount)?;
+
+    // 1. Fees accrued so far fund the boost, which is promised in full up front
+    lock_config.collect_management_fee(vault, clock.unix_timestamp)?;
+    let (boost_bps, boost) = lock_config.reserve_boost(amount, term)?;
+
+    // 2. Deposit
+    // Token transfer from 'owner_token_account' into the vault would go here
+    circuit_breaker::record_inflow(vault, amount, clock.unix_timestamp);
+    vault.total_assets = vault.total_assets.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+    lock_config.total_locked = lock_config.total_locked.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+
+    let unlocks_at = clock.unix_timestamp + term.duration();
+    lock_account.locks.push(DepositLock {
+        amount,
+        term,
+        boost_bps,
+        boost,
+        locked_at: clock.unix_timestamp,
+        unlocks_at,
+    });
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    msg!("Locked {} for {:?} with {} boost, unlocking at {}", amount, term, boost, unlocks_at);
+    emit!(DepositLockedEvent {
+        vault: vault.key(),
+        owner: lock_account.owner,
+        amount,
+        term,
+        boost_bps,
+        boost,
+        unlocks_at,
+    });
+    Ok(())
+}
+
+pub fn withdraw_handler(ctx: Context<UnlockDeposit>, index: u8) -> Result<()> {
+    unlock(ctx, index, false)
+}
+
+pub fn exit_early_handler(ctx: Context<UnlockDeposit>, index: u8) -> Result<()> {
+    unlock(ctx, index, true)
+}
+
+fn unlock(ctx: Context<UnlockDeposit>, index: u8, early: bool) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let lock_config = &mut ctx.accounts.lock_config;
+    let lock_account = &mut ctx.accounts.lock_account;
+    let clock = Clock::get()?;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::WITHDRAWALS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+
+    let lock = lock_account.lock(index)?;
+    if early {
+        require!(!lock.is_matured(clock.unix_timestamp), FluxError::LockMatured);
+    } else {
+        require!(lock.is_matured(clock.unix_timestamp), FluxError::LockNotMatured);
+    }
+
+    let available = vault.total_assets.saturating_sub(vault.total_liabilities);
+    require!(lock.amount <= available, FluxError::InsufficientLiquidity);
+    if circuit_breaker::check(vault, lock.amount, None, clock.unix_timestamp)? {
+        return Ok(());
+    }
+
+    lock_account.locks.remove(index as usize);
+    // An early exit forfeits the boost back to the budget and pays the penalty
+    lock_config.boost_reserved -= lock.boost;
+    let (boost, penalty) = if early {
+        lock_config.boost_budget += lock.boost;
+        (0, lock_config.early_exit_penalty(lock.amount))
+    } else {
+        (lock.boost, 0)
+    };
+    vault.total_assets -= lock.amount;
+    lock_config.total_locked -= lock.amount;
+
+    // Token transfer of principal - penalty + boost to 'owner_token_account' would go here
+    // ... CPI to Flux Incinerator to burn 'penalty' ...
+
+    msg!("Unlocked {} (boost {}, penalty {})", lock.amount, boost, penalty);
+    emit!(DepositUnlockedEvent {
+        vault: vault.key(),
+        owner: lock_account.owner,
+        amount: lock.amount,
+        boost,
+        penalty,
+        early,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
@@ -3,3 +3,19 @@ pub mod liquidate;
 pub mod unload;
 pub mod xfer;
 pub mod admin;
+pub mod compliance;
+pub mod profile;
+pub mod position;
+pub mod reputation;
+pub mod authority;
+pub mod multisig;
+pub mod risk;
+pub mod views;
+pub mod flash;
+pub mod reserve;
+pub mod withdrawal;
+pub mod lock;
+pub mod deleverage;
+pub mod order;
+pub mod leverage;
+pub mod rebalance;
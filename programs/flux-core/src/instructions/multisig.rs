@@ -0,0 +1,214 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program::invoke_signed;
+use crate::state::config::ProtocolConfig;
+use crate::state::multisig::{AdminMultisig, AdminProposal, ProposedInstruction};
+use crate::events::{
+    AdminProposalCreatedEvent, AdminProposalApprovedEvent, AdminProposalExecutedEvent,
+    MultisigMembersUpdatedEvent,
+};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitAdminMultisig<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = AdminMultisig::LEN,
+        seeds = [b"admin_multisig"],
+        bump
+    )]
+    pub multisig: Account<'info, AdminMultisig>,
+    #[account(
+        seeds = [b"protocol_config"],
+        bump = protocol_config.bump,
+        has_one = admin @ FluxError::UnauthorizedAccess,
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Membership changes must themselves go through a proposal.
+#[derive(Accounts)]
+pub struct SetMultisigMembers<'info> {
+    #[account(mut, seeds = [b"admin_multisig"], bump = multisig.bump)]
+    pub multisig: Account<'info, AdminMultisig>,
+    #[account(seeds = [b"multisig_signer"], bump = multisig.signer_bump)]
+    pub multisig_signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(instruction: ProposedInstruction)]
+pub struct CreateAdminProposal<'info> {
+    #[account(mut, seeds = [b"admin_multisig"], bump = multisig.bump)]
+    pub multisig: Account<'info, AdminMultisig>,
+    #[account(
+        init,
+        payer = proposer,
+        space = AdminProposal::space(&instruction),
+        seeds = [b"admin_proposal", multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveAdminProposal<'info> {
+    #[account(seeds = [b"admin_multisig"], bump = multisig.bump)]
+    pub multisig: Account<'info, AdminMultisig>,
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", proposal.nonce.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+    pub member: Signer<'info>,
+}
+
+/// The proposed instruction's accounts are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct ExecuteAdminProposal<'info> {
+    // Read-only here: the proposed instruction may itself update the multisig
+    #[account(seeds = [b"admin_multisig"], bump = multisig.bump)]
+    pub multisig: Account<'info, AdminMultisig>,
+    #[account(
+        mut,
+        seeds = [b"admin_proposal", proposal.nonce.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, AdminProposal>,
+    /// CHECK: System-owned PDA holding the delegated protocol roles
+    #[account(mut, seeds = [b"multisig_signer"], bump = multisig.signer_bump)]
+    pub multisig_signer: UncheckedAccount<'info>,
+    /// CHECK: This program, target of the proposed instruction
+    #[account(address = crate::ID)]
+    pub flux_program: UncheckedAccount<'info>,
+    pub executor: Signer<'info>,
+}
+
+pub fn init_handler(ctx: Context<InitAdminMultisig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    AdminMultisig::validate_members(&signers, threshold)?;
+
+    let multisig = &mut ctx.accounts.multisig;
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.bump = ctx.bumps.multisig;
+    multisig.signer_bump = Pubkey::find_program_address(&[b"multisig_signer"], ctx.program_id).1;
+
+    msg!("Admin multisig initialized: {}-of-{}", threshold, multisig.signers.len());
+    Ok(())
+}
+
+pub fn set_members_handler(ctx: Context<SetMultisigMembers>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+    AdminMultisig::validate_members(&signers, threshold)?;
+
+    let multisig = &mut ctx.accounts.multisig;
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.config_version = multisig.config_version.wrapping_add(1);
+
+    msg!("Admin multisig updated: {}-of-{}", threshold, multisig.signers.len());
+    emit!(MultisigMembersUpdatedEvent {
+        signers: multisig.signers.clone(),
+        threshold,
+        config_version: multisig.config_version,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn create_proposal_handler(
+    ctx: Context<CreateAdminProposal>,
+    instruction: ProposedInstruction,
+    expires_in: i64,
+) -> Result<()> {
+    let multisig = &mut ctx.accounts.multisig;
+    let proposer = ctx.accounts.proposer.key();
+    let index = multisig.signer_index(&proposer).ok_or(error!(FluxError::NotMultisigMember))?;
+    require!(
+        (AdminMultisig::MIN_EXPIRY..=AdminMultisig::MAX_EXPIRY).contains(&expires_in),
+        FluxError::InvalidMultisigConfig
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.nonce = multisig.proposal_count;
+    proposal.proposer = proposer;
+    proposal.instruction = instruction;
+    proposal.approvals = 1 << index; // proposing counts as approval
+    proposal.config_version = multisig.config_version;
+    proposal.created_at = now;
+    proposal.expires_at = now + expires_in;
+    proposal.bump = ctx.bumps.proposal;
+
+    multisig.proposal_count = multisig.proposal_count.checked_add(1).ok_or(error!(FluxError::ArithmeticError))?;
+
+    msg!("Admin proposal #{} created by {}", proposal.nonce, proposer);
+    emit!(AdminProposalCreatedEvent {
+        nonce: proposal.nonce,
+        proposer,
+        expires_at: proposal.expires_at,
+    });
+    Ok(())
+}
+
+pub fn approve_proposal_handler(ctx: Context<ApproveAdminProposal>) -> Result<()> {
+    let multisig = &ctx.accounts.multisig;
+    let proposal = &mut ctx.accounts.proposal;
+    let member = ctx.accounts.member.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    let index = multisig.signer_index(&member).ok_or(error!(FluxError::NotMultisigMember))?;
+    require!(!proposal.executed, FluxError::ProposalAlreadyExecuted);
+    require!(now < proposal.expires_at, FluxError::ProposalExpired);
+    require!(proposal.config_version == multisig.config_version, FluxError::StaleProposal);
+    require!(proposal.approvals & (1 << index) == 0, FluxError::AlreadyApproved);
+
+    proposal.approvals |= 1 << index;
+
+    msg!("Admin proposal #{} approved by {} ({}/{})",
+        proposal.nonce, member, proposal.approval_count(), multisig.threshold);
+    emit!(AdminProposalApprovedEvent {
+        nonce: proposal.nonce,
+        member,
+        approvals: proposal.approval_count() as u8,
+        threshold: multisig.threshold,
+    });
+    Ok(())
+}
+
+pub fn execute_proposal_handler<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteAdminProposal<'info>>) -> Result<()> {
+    let multisig = &ctx.accounts.multisig;
+    let proposal = &mut ctx.accounts.proposal;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!proposal.executed, FluxError::ProposalAlreadyExecuted);
+    require!(now < proposal.expires_at, FluxError::ProposalExpired);
+    require!(proposal.config_version == multisig.config_version, FluxError::StaleProposal);
+    require!(proposal.approval_count() >= multisig.threshold as u32, FluxError::InsufficientApprovals);
+
+    // Mark first so the proposal cannot be replayed from within the call
+    proposal.executed = true;
+    proposal.exit(ctx.program_id)?;
+
+    let signer_key = ctx.accounts.multisig_signer.key();
+    let ix = proposal.to_instruction(&signer_key);
+
+    let mut account_infos = ctx.remaining_accounts.to_vec();
+    account_infos.push(ctx.accounts.multisig_signer.to_account_info());
+    account_infos.push(ctx.accounts.flux_program.to_account_info());
+
+    invoke_signed(&ix, &account_infos, &[&[b"multisig_signer", &[multisig.signer_bump]]])?;
+
+    msg!("Admin proposal #{} executed", proposal.nonce);
+    emit!(AdminProposalExecutedEvent {
+        nonce: proposal.nonce,
+        executor: ctx.accounts.executor.key(),
+        timestamp: now,
+    });
+    Ok(())
+}
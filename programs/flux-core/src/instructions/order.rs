@@ -0,0 +1,232 @@
+use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::RiskCategory;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::user_profile::UserProfile;
+use crate::state::order::{PositionOrder, OrderParams, OrderAction};
+use crate::instructions::reserve::position_category;
+use crate::instructions::deleverage::{sell_collateral_for_debt, position_portfolio};
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::circuit_breaker;
+use crate::oracle;
+use crate::risk;
+use crate::swap::SwapAccounts;
+use crate::events::{OrderPlacedEvent, OrderCancelledEvent, OrderExecutedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+#[instruction(id: u8)]
+pub struct PlaceOrder<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = PositionOrder::LEN,
+        seeds = [b"position_order", position.key().as_ref(), &[id]],
+        bump
+    )]
+    pub order: Account<'info, PositionOrder>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"position_order", order.position.as_ref(), &[order.id]],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, PositionOrder>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteOrder<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        has_one = position,
+        has_one = owner,
+        seeds = [b"position_order", position.key().as_ref(), &[order.id]],
+        bump = order.bump,
+    )]
+    pub order: Account<'info, PositionOrder>,
+    /// CHECK: Position owner; receives the order's rent and swap output beyond the debt
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    /// The owner's profile, whose loan closes if the order repays the debt in full
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Required once the position has opted into a risk category
+    #[account(mut)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub executor: Signer<'info>, // Keeper
+    /// CHECK: Executor token account (receives the keeper tip)
+    #[account(mut)]
+    pub executor_token_account: UncheckedAccount<'info>,
+
+    /// CHECK: Jupiter aggregator program V6
+    pub jupiter_program: UncheckedAccount<'info>,
+    /// CHECK: Token account source (Collateral)
+    #[account(mut)]
+    pub token_in: UncheckedAccount<'info>,
+    /// CHECK: Token account destination (Debt Asset)
+    #[account(mut)]
+    pub token_out: UncheckedAccount<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's oracle the trigger is quoted in
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+pub fn place_handler(ctx: Context<PlaceOrder>, id: u8, params: OrderParams) -> Result<()> {
+    params.validate()?;
+    let clock = Clock::get()?;
+
+    let order = &mut ctx.accounts.order;
+    order.position = ctx.accounts.position.key();
+    order.owner = ctx.accounts.owner.key();
+    order.id = id;
+    order.kind = params.kind;
+    order.trigger_price = params.trigger_price;
+    order.action = params.action;
+    order.max_slippage_bps = params.max_slippage_bps;
+    order.keeper_tip = params.keeper_tip;
+    order.created_at = clock.unix_timestamp;
+    order.bump = ctx.bumps.order;
+
+    msg!("{:?} order {} placed at trigger {}", params.kind, id, params.trigger_price);
+    emit!(OrderPlacedEvent {
+        position: order.position,
+        order: order.key(),
+        params,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn cancel_handler(ctx: Context<CancelOrder>) -> Result<()> {
+    let order = &ctx.accounts.order;
+    msg!("Order {} cancelled", order.id);
+    emit!(OrderCancelledEvent {
+        position: order.position,
+        order: order.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn execute_handler(ctx: Context<ExecuteOrder>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let order = &ctx.accounts.order;
+    let clock = Clock::get()?;
+
+    // 0. Security Checks
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::LIQUIDATIONS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+
+    // 1. Trigger
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+    require!(order.is_triggered(collateral_price.price), FluxError::OrderNotTriggered);
+
+    let target = order.repay_target(position.debt_amount);
+    require!(target > 0, FluxError::InvalidAmount);
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let health_before = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?
+        .health_factor()?;
+
+    // 2. Size the sale: the oracle quote for `target`, plus room for slippage
+    let slippage = order.max_slippage_bps as u128;
+    let quote = risk::amount_for_value(risk::value(target, debt_price)?, collateral_price)?;
+    let available = position.collateral_amount.saturating_sub(order.keeper_tip);
+    let sell = ((quote as u128 * (10000 + slippage) / 10000) as u64).min(available);
+    let min_amount_out = match order.action {
+        OrderAction::Repay { .. } => (target as u128 * (10000 - slippage) / 10000) as u64,
+        OrderAction::Close => target,
+    };
+    let outflow = sell.checked_add(order.keeper_tip).ok_or(error!(FluxError::ArithmeticError))?;
+    if circuit_breaker::check(vault, outflow, Some(ctx.accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(());
+    }
+
+    // 3. Swap and repay
+    let repaid = sell_collateral_for_debt(
+        vault,
+        position,
+        ctx.accounts.risk_category.as_mut(),
+        &mut *ctx.accounts.user_profile.load_mut()?,
+        &SwapAccounts {
+            jupiter_program: &ctx.accounts.jupiter_program.to_account_info(),
+            token_in: &ctx.accounts.token_in.to_account_info(),
+            token_out: &ctx.accounts.token_out.to_account_info(),
+        },
+        sell,
+        order.keeper_tip,
+        (collateral_price, debt_price),
+        min_amount_out,
+        clock.unix_timestamp,
+    )?;
+    // Token transfer of 'keeper_tip' to 'executor_token_account' would go here
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+    let health_after = position_portfolio(vault, position, category, collateral_price, debt_price, ctx.remaining_accounts)?
+        .health_factor()?;
+    require!(health_after >= health_before, FluxError::DeleverageWorsensHealth);
+
+    msg!("Order {} executed at price {}: sold {}, repaid {}", order.id, collateral_price.price, sell, repaid);
+    emit!(OrderExecutedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        order: order.key(),
+        executor: ctx.accounts.executor.key(),
+        price: collateral_price.price,
+        collateral_sold: sell,
+        repaid,
+        keeper_tip: order.keeper_tip,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
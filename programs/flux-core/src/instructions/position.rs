@@ -0,0 +1,284 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::RiskCategory;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::state::reputation::ReputationConfig;
+use crate::state::user_profile::{UserProfile, UserRole, ActionType, ActionHistory};
+use crate::instructions::profile::append_history;
+use crate::oracle::{self, OraclePrice};
+use crate::risk::{Portfolio, RiskParams};
+use crate::instructions::reserve::{add_position_collateral, position_category};
+use crate::circuit_breaker;
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = owner,
+        space = Position::LEN,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositCollateral<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageDebt<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Required once the profile has opened an action history
+    #[account(
+        mut,
+        seeds = [b"history", user_profile.key().as_ref()],
+        bump = action_history.load()?.bump,
+    )]
+    pub action_history: Option<AccountLoader<'info, ActionHistory>>,
+    /// Required once the position has opted into a risk category
+    #[account(mut)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"reputation_config"], bump = reputation_config.bump)]
+    pub reputation_config: Account<'info, ReputationConfig>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+
+    /// CHECK: Instructions sysvar, used to identify this instruction in the history log
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    // Remaining accounts (borrow): one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+pub fn open_handler(ctx: Context<OpenPosition>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.owner = ctx.accounts.owner.key();
+    position.vault = ctx.accounts.vault.key();
+    position.last_update = Clock::get()?.unix_timestamp;
+    position.bump = ctx.bumps.position;
+
+    msg!("Position opened in vault {} for {}", position.vault, position.owner);
+    Ok(())
+}
+
+pub fn deposit_collateral_handler(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let clock = Clock::get()?;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::DEPOSITS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+    if circuit_breaker::check(vault, 0, None, clock.unix_timestamp)? {
+        return Ok(());
+    }
+    circuit_breaker::record_inflow(vault, amount, clock.unix_timestamp);
+
+    // Token transfer into the vault would go here. Collateral backs the position only; it is
+    // never lent out, so it stays out of `total_assets` and the supply cap.
+    position.collateral_amount = position.collateral_amount.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+    position.last_update = clock.unix_timestamp;
+    vault.total_collateral = vault.total_collateral.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+
+    msg!("Collateral deposited: {}. Position collateral: {}", amount, position.collateral_amount);
+    Ok(())
+}
+
+pub fn borrow_handler(ctx: Context<ManageDebt>, amount: u64) -> Result<()> {
+    borrow(ctx.accounts, ctx.remaining_accounts, amount, |_, _| Ok(0))?;
+    Ok(())
+}
+
+/// Borrow `amount` against the position. `buy_collateral` runs once the loan is approved
+/// and returns base collateral acquired with it (0 for a plain borrow), which is deposited
+/// before the collateral check. Returns false if a circuit breaker froze the vault instead.
+pub(crate) fn borrow(
+    accounts: &mut ManageDebt,
+    remaining_accounts: &[AccountInfo],
+    amount: u64,
+    buy_collateral: impl FnOnce(OraclePrice, OraclePrice) -> Result<u64>,
+) -> Result<bool> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &mut accounts.vault;
+    let position = &mut accounts.position;
+    let params = &accounts.reputation_config.params;
+    let mut profile = accounts.user_profile.load_mut()?;
+    let clock = Clock::get()?;
+
+    // 1. Eligibility
+    accounts.protocol_config.require_not_paused(pause_flags::BORROWS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    require!(!profile.is_aml_flagged(), FluxError::AccountFlagged);
+    require!(profile.role() != UserRole::Blacklisted, FluxError::UserBlacklisted);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+
+    // 2. Reputation tier sets the borrow limit and collateral requirement
+    profile.reputation_score = params.compute_score(&profile, clock.unix_timestamp);
+    let tier = params.tier_for(profile.reputation_score).ok_or(error!(FluxError::LowReputation))?;
+
+    let new_debt = position.debt_amount.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+    require!(new_debt <= tier.borrow_limit, FluxError::BorrowLimitExceeded);
+    vault.require_borrow_cap(amount, new_debt)?;
+
+    // Queued withdrawals take idle liquidity ahead of new borrows
+    fill_withdrawals(vault, accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+    let available = vault.total_assets.saturating_sub(vault.total_liabilities);
+    require!(amount <= available, FluxError::InsufficientLiquidity);
+
+    // 3. Collateral check against both oracle prices
+    let collateral_price = oracle::read_price(&accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&accounts.price_feed_debt)?;
+    if circuit_breaker::check(vault, amount, Some(accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(false);
+    }
+    let bought = buy_collateral(collateral_price, debt_price)?;
+    if bought > 0 {
+        circuit_breaker::record_inflow(vault, bought, clock.unix_timestamp);
+        position.collateral_amount = position.collateral_amount.checked_add(bought).ok_or(error!(FluxError::ArithmeticError))?;
+        vault.total_collateral = vault.total_collateral.checked_add(bought).ok_or(error!(FluxError::ArithmeticError))?;
+    }
+    let opened_loan = position.debt_amount == 0;
+    position.debt_amount = new_debt;
+
+    // The reputation discount applies to the base collateral; reserves use their own or their category's limits
+    let category = position_category(position, accounts.risk_category.as_ref())?;
+    let base = RiskParams::with_collateral_ratio(vault, tier.required_collateral_ratio(vault.collateral_ratio))?;
+    let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+    add_position_collateral(&mut portfolio, vault, position, category, collateral_price, &base, remaining_accounts)?;
+    msg!("LTV after borrow: {} bps", portfolio.ltv_bps()?);
+    require!(portfolio.within_borrow_limit()?, FluxError::InsufficientCollateral);
+
+    // 4. Settle
+    if let Some(category) = accounts.risk_category.as_mut() {
+        category.add_debt(amount)?;
+    }
+    // Token transfer to the borrower would go here
+    vault.total_liabilities = vault.total_liabilities.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+    position.last_update = clock.unix_timestamp;
+
+    if opened_loan {
+        profile.active_loans = profile.active_loans.saturating_add(1);
+    }
+    profile.record_action(ActionType::Borrow, amount, &clock);
+    append_history(
+        &profile,
+        accounts.action_history.as_ref(),
+        &accounts.instructions_sysvar.to_account_info(),
+        ActionType::Borrow,
+        amount,
+        &clock,
+    )?;
+
+    msg!("Borrowed {}. Position debt: {}", amount, position.debt_amount);
+    Ok(true)
+}
+
+pub fn repay_handler(ctx: Context<ManageDebt>, amount: u64) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &mut ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let params = &ctx.accounts.reputation_config.params;
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    let clock = Clock::get()?;
+
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+
+    let repaid = amount.min(position.debt_amount);
+    require!(repaid > 0, FluxError::InvalidAmount);
+    position_category(position, ctx.accounts.risk_category.as_ref())?;
+
+    // Token transfer from the borrower would go here
+    circuit_breaker::record_inflow(vault, repaid, clock.unix_timestamp);
+    settle_debt(vault, position, ctx.accounts.risk_category.as_deref_mut(), &mut profile, repaid, clock.unix_timestamp)?;
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    profile.record_action(ActionType::Repay, repaid, &clock);
+    profile.reputation_score = params.compute_score(&profile, clock.unix_timestamp);
+    append_history(
+        &profile,
+        ctx.accounts.action_history.as_ref(),
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        ActionType::Repay,
+        repaid,
+        &clock,
+    )?;
+
+    msg!("Repaid {}. Position debt: {}. Reputation Score: {}", repaid, position.debt_amount, profile.reputation_score);
+    Ok(())
+}
+
+/// Take `repaid` off the position's debt, the vault's liabilities and the position's risk
+/// category. Every path that reduces debt settles through here, so the owner's open loan
+/// is closed the moment the debt reaches zero.
+pub(crate) fn settle_debt(
+    vault: &mut Vault,
+    position: &mut Position,
+    risk_category: Option<&mut RiskCategory>,
+    profile: &mut UserProfile,
+    repaid: u64,
+    now: i64,
+) -> Result<()> {
+    position.debt_amount = position.debt_amount.checked_sub(repaid).ok_or(error!(FluxError::ArithmeticError))?;
+    position.last_update = now;
+    vault.total_liabilities = vault.total_liabilities.saturating_sub(repaid);
+    if let Some(category) = risk_category {
+        category.remove_debt(repaid);
+    }
+    if repaid > 0 && position.debt_amount == 0 {
+        profile.active_loans = profile.active_loans.saturating_sub(1);
+    }
+    Ok(())
+}
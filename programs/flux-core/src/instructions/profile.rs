@@ -0,0 +1,281 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::sysvar::instructions::load_current_index_checked;
+use crate::state::user_profile::{UserProfile, UserRole, ActionType, ActionHistory, UserAction, LegacyUserProfile};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitUserProfile<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = UserProfile::LEN,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUserProfile<'info> {
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Closed with the profile; required once the profile has opened an action history
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"history", user_profile.key().as_ref()],
+        bump = action_history.load()?.bump,
+    )]
+    pub action_history: Option<AccountLoader<'info, ActionHistory>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateUserProfile<'info> {
+    /// CHECK: Deserialized manually, since older profiles use the Borsh layout
+    #[account(mut, owner = crate::ID)]
+    pub user_profile: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitActionHistory<'info> {
+    #[account(
+        mut,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    #[account(
+        init,
+        payer = owner,
+        space = ActionHistory::space(ActionHistory::INITIAL_CAPACITY),
+        seeds = [b"history", user_profile.key().as_ref()],
+        bump
+    )]
+    pub action_history: AccountLoader<'info, ActionHistory>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResizeActionHistory<'info> {
+    #[account(
+        seeds = [b"profile", owner.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    #[account(
+        mut,
+        seeds = [b"history", user_profile.key().as_ref()],
+        bump = action_history.load()?.bump,
+    )]
+    pub action_history: AccountLoader<'info, ActionHistory>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn init_handler(ctx: Context<InitUserProfile>) -> Result<()> {
+    let mut profile = ctx.accounts.user_profile.load_init()?;
+    let clock = Clock::get()?;
+
+    profile.owner = ctx.accounts.owner.key();
+    profile.role = UserRole::Standard as u8;
+    profile.last_active_timestamp = clock.unix_timestamp;
+    profile.created_at = clock.unix_timestamp;
+    profile.bump = ctx.bumps.user_profile;
+    profile.version = UserProfile::CURRENT_VERSION;
+
+    msg!("User profile created for {}", profile.owner);
+    Ok(())
+}
+
+pub fn close_handler(ctx: Context<CloseUserProfile>) -> Result<()> {
+    let profile = ctx.accounts.user_profile.load()?;
+
+    require!(profile.active_loans == 0, FluxError::ActiveLoansOutstanding);
+    // Flagged profiles and their case log must be retained for review
+    require!(!profile.is_aml_flagged(), FluxError::AccountFlagged);
+    require!(profile.case_count == 0, FluxError::CaseHistoryRetained);
+    // Otherwise the history PDA would be stranded with its rent
+    require!(
+        !profile.has_history() || ctx.accounts.action_history.is_some(),
+        FluxError::MissingActionHistory
+    );
+
+    msg!("User profile for {} closed. Rent refunded.", profile.owner);
+    Ok(())
+}
+
+pub fn migrate_handler(ctx: Context<MigrateUserProfile>) -> Result<()> {
+    let info = ctx.accounts.user_profile.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+
+    // 1. Verify this is the signer's profile at its canonical address
+    {
+        let data = info.try_borrow_data()?;
+        require!(data.len() >= 8 + 32, FluxError::InvalidProfileLayout);
+        require!(data[..8] == UserProfile::DISCRIMINATOR, FluxError::InvalidProfileLayout);
+        require!(data[8..40] == owner.key().to_bytes(), FluxError::UnauthorizedAccess);
+    }
+    let (expected, _) = Pubkey::find_program_address(&[b"profile", owner.key.as_ref()], ctx.program_id);
+    require_keys_eq!(info.key(), expected, FluxError::InvalidProfileLayout);
+
+    let clock = Clock::get()?;
+    let old_len = info.data_len();
+    if old_len == UserProfile::LEN {
+        let mut data = info.try_borrow_mut_data()?;
+        let profile: &mut UserProfile = bytemuck::from_bytes_mut(&mut data[8..]);
+        let from_version = profile.version;
+        upgrade_in_place(profile, &clock);
+        msg!("Profile {} upgraded in place: v{} -> v{}", info.key(), from_version, profile.version);
+        return Ok(());
+    }
+
+    // 2. Decode the Borsh layout. Version 0 predates `case_count`/`version`,
+    //    so the tail is zero-padded before decoding.
+    let legacy = {
+        let data = info.try_borrow_data()?;
+        let mut buf = vec![0u8; LegacyUserProfile::LEN.max(data.len())];
+        buf[..data.len()].copy_from_slice(&data);
+        LegacyUserProfile::deserialize(&mut &buf[8..])
+            .map_err(|_| error!(FluxError::InvalidProfileLayout))?
+    };
+
+    // 3. Resize to the zero-copy layout, settling rent with the owner
+    let rent_exempt = Rent::get()?.minimum_balance(UserProfile::LEN);
+    let current = info.lamports();
+    if current < rent_exempt {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer { from: owner.clone(), to: info.clone() },
+            ),
+            rent_exempt - current,
+        )?;
+    }
+    info.realloc(UserProfile::LEN, true)?;
+    let excess = info.lamports().saturating_sub(rent_exempt);
+    if excess > 0 {
+        **info.try_borrow_mut_lamports()? -= excess;
+        **owner.try_borrow_mut_lamports()? += excess;
+    }
+
+    // 4. Rewrite under the current layout. Legacy history entries carried no
+    //    transaction identifiers and are not carried over.
+    let mut data = info.try_borrow_mut_data()?;
+    data.fill(0);
+    data[..8].copy_from_slice(&UserProfile::DISCRIMINATOR);
+    let profile: &mut UserProfile = bytemuck::from_bytes_mut(&mut data[8..]);
+    profile.owner = legacy.owner;
+    profile.total_borrowed_lifetime = legacy.total_borrowed_lifetime;
+    profile.total_repaid_lifetime = legacy.total_repaid_lifetime;
+    profile.last_active_timestamp = legacy.last_active_timestamp;
+    profile.active_loans = legacy.active_loans;
+    profile.case_count = legacy.case_count;
+    profile.liquidation_count = legacy.liquidation_count;
+    profile.country_code = legacy.country_code;
+    profile.reputation_score = legacy.reputation_score;
+    profile.role = legacy.role as u8;
+    profile.kyc_verified = legacy.kyc_verified as u8;
+    profile.aml_flagged = legacy.aml_flagged as u8;
+    profile.bump = legacy.bump;
+    profile.version = 2;
+    upgrade_in_place(profile, &clock);
+
+    msg!("Profile {} migrated: {} -> {} bytes, v{} -> v{}",
+        info.key(), old_len, UserProfile::LEN, legacy.version, profile.version);
+    Ok(())
+}
+
+/// Fill fields added to the zero-copy layout after v2. They occupy what was
+/// `reserved`, so older accounts read them as zero.
+fn upgrade_in_place(profile: &mut UserProfile, clock: &Clock) {
+    if profile.version < 3 {
+        // Creation time was not tracked; age is counted from the migration
+        profile.created_at = clock.unix_timestamp;
+    }
+    profile.version = UserProfile::CURRENT_VERSION;
+}
+
+/// Append `action_type` to the profile's history log, if it has opened one.
+/// The entry is keyed by slot and the index of the current instruction.
+pub(crate) fn append_history(
+    profile: &UserProfile,
+    action_history: Option<&AccountLoader<ActionHistory>>,
+    instructions_sysvar: &AccountInfo,
+    action_type: ActionType,
+    amount: u64,
+    clock: &Clock,
+) -> Result<()> {
+    if !profile.has_history() {
+        return Ok(());
+    }
+    let history = action_history.ok_or(error!(FluxError::MissingActionHistory))?;
+    let ix_index = load_current_index_checked(instructions_sysvar)?;
+    ActionHistory::append(
+        &history.to_account_info(),
+        UserAction::new(action_type, amount, clock, ix_index),
+    )
+}
+
+pub fn init_history_handler(ctx: Context<InitActionHistory>) -> Result<()> {
+    let mut history = ctx.accounts.action_history.load_init()?;
+    history.user_profile = ctx.accounts.user_profile.key();
+    history.capacity = ActionHistory::INITIAL_CAPACITY;
+    history.bump = ctx.bumps.action_history;
+
+    ctx.accounts.user_profile.load_mut()?.has_history = 1;
+
+    msg!("Action history opened with capacity {}", history.capacity);
+    Ok(())
+}
+
+pub fn resize_history_handler(ctx: Context<ResizeActionHistory>, new_capacity: u32) -> Result<()> {
+    let old_capacity = ctx.accounts.action_history.load()?.capacity;
+    require!(
+        new_capacity > old_capacity && new_capacity <= ActionHistory::MAX_CAPACITY,
+        FluxError::InvalidHistoryCapacity
+    );
+
+    let info = ctx.accounts.action_history.to_account_info();
+    let new_len = ActionHistory::space(new_capacity);
+    // The runtime caps per-instruction growth; larger buffers take several calls
+    require!(
+        new_len - info.data_len() <= anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE,
+        FluxError::InvalidHistoryCapacity
+    );
+
+    ActionHistory::linearize(&info)?;
+
+    let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(info.lamports());
+    if rent_due > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer { from: ctx.accounts.owner.to_account_info(), to: info.clone() },
+            ),
+            rent_due,
+        )?;
+    }
+    info.realloc(new_len, true)?;
+    ctx.accounts.action_history.load_mut()?.capacity = new_capacity;
+
+    msg!("Action history resized: {} -> {} entries", old_capacity, new_capacity);
+    Ok(())
+}
@@ -0,0 +1,487 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::RiskCategory;
+use crate::state::withdrawal_queue::WithdrawalQueue;
+use crate::state::pipeline::{PipelineStep, XferVenue};
+use crate::state::user_profile::{UserProfile, ActionType, ActionHistory};
+use crate::state::compliance::{ComplianceConfig, TransferRecord};
+use crate::instructions::withdrawal::fill_withdrawals;
+use crate::instructions::xfer::{check_compliance, write_transfer_record};
+use crate::instructions::position::settle_debt;
+use crate::instructions::profile::append_history;
+use crate::instructions::reserve::position_category;
+use crate::instructions::deleverage::position_portfolio;
+use crate::instructions::liquidate::liquidation_amounts;
+use crate::circuit_breaker;
+use crate::oracle::{self, OraclePrice};
+use crate::risk;
+use crate::swap;
+use crate::events::{PositionLiquidatedEvent, RebalancedEvent, XferVenueUpdatedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct Rebalance<'info> {
+    #[account(mut, has_one = authority @ FluxError::UnauthorizedAccess)]
+    pub vault: Account<'info, Vault>,
+    /// Required once the vault has opened a withdrawal queue
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Option<Account<'info, WithdrawalQueue>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>, // Vault authority
+    /// CHECK: Authority token account (funds `Fetch`, receives seized collateral)
+    #[account(mut)]
+    pub authority_token_account: UncheckedAccount<'info>,
+    /// CHECK: Destination venue for `Xfer`; must be on the venue allow-list
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    /// Required for `Xfer`; exists only while the admin allows the recipient
+    #[account(
+        seeds = [b"xfer_venue", recipient.key().as_ref()],
+        bump = xfer_venue.bump,
+    )]
+    pub xfer_venue: Option<Account<'info, XferVenue>>,
+
+    // Compliance Accounts, checked for `Xfer` as `xfer_funds` checks them
+    /// Required for `Xfer`: the authority's profile
+    #[account(
+        mut,
+        seeds = [b"profile", authority.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: Option<AccountLoader<'info, UserProfile>>,
+    /// Required once the authority's profile has opened an action history
+    #[account(
+        mut,
+        seeds = [b"history", user_profile.as_ref().map(|profile| profile.key()).unwrap_or_default().as_ref()],
+        bump = action_history.load()?.bump,
+    )]
+    pub action_history: Option<AccountLoader<'info, ActionHistory>>,
+    /// Required for `Xfer`
+    #[account(seeds = [b"compliance_config"], bump = compliance_config.bump)]
+    pub compliance_config: Option<Account<'info, ComplianceConfig>>,
+    /// Required when the `Xfer` step carries travel-rule data
+    #[account(
+        init,
+        payer = authority,
+        space = TransferRecord::LEN,
+        seeds = [
+            b"transfer_record",
+            user_profile.as_ref().map(|profile| profile.key()).unwrap_or_default().as_ref(),
+            TransferRecord::next_nonce(user_profile.as_ref())?.as_ref(),
+        ],
+        bump
+    )]
+    pub transfer_record: Option<Account<'info, TransferRecord>>,
+    /// CHECK: Instructions sysvar, used to identify this instruction in the history log
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Jupiter aggregator program V6
+    pub jupiter_program: UncheckedAccount<'info>,
+    /// CHECK: Token account source (Collateral)
+    #[account(mut)]
+    pub token_in: UncheckedAccount<'info>,
+    /// CHECK: Token account destination (Debt Asset)
+    #[account(mut)]
+    pub token_out: UncheckedAccount<'info>,
+    /// CHECK: Incinerator program
+    pub incinerator_program: UncheckedAccount<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; the vault's own, since vault health is checked against it
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: for each `Liquidate` step in order, the position, then its owner's
+    // profile, then its risk category if it has one, then one [reserve, collateral_deposit,
+    // price_feed] triple per reserve deposit
+}
+
+#[derive(Accounts)]
+pub struct AddXferVenue<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = XferVenue::LEN,
+        seeds = [b"xfer_venue", venue.key().as_ref()],
+        bump
+    )]
+    pub xfer_venue: Account<'info, XferVenue>,
+    /// CHECK: Venue allowed to receive `Xfer` transfers
+    pub venue: UncheckedAccount<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveXferVenue<'info> {
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"xfer_venue", xfer_venue.venue.as_ref()],
+        bump = xfer_venue.bump,
+    )]
+    pub xfer_venue: Account<'info, XferVenue>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+}
+
+/// Funds each step moved, checked against the vault's balances once the pipeline ends.
+#[derive(Default)]
+struct Ledger {
+    fetched: u64,
+    repaid: u64,
+    seized: u64,
+    transferred: u64,
+    liquidations: Vec<PositionLiquidatedEvent>,
+}
+
+impl Ledger {
+    fn add(total: &mut u64, amount: u64) -> Result<()> {
+        *total = total.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+        Ok(())
+    }
+
+    /// Funds that left the vault: seized collateral and transfers.
+    fn outflow(&self) -> Result<u64> {
+        self.seized.checked_add(self.transferred).ok_or(error!(FluxError::ArithmeticError))
+    }
+
+    /// Every change to the vault's assets, collateral and liabilities must be one the steps recorded.
+    fn reconcile(&self, before: &Vault, after: &Vault) -> Result<()> {
+        let assets = (before.total_assets as u128 + self.fetched as u128)
+            .checked_sub(self.transferred as u128);
+        require!(assets == Some(after.total_assets as u128), FluxError::PipelineInvariantViolated);
+        let collateral = before.total_collateral.checked_sub(self.seized);
+        require!(collateral == Some(after.total_collateral), FluxError::PipelineInvariantViolated);
+        let liabilities = before.total_liabilities.checked_sub(self.repaid);
+        require!(liabilities == Some(after.total_liabilities), FluxError::PipelineInvariantViolated);
+        Ok(())
+    }
+}
+
+/// Positions, owner profiles and categories loaded from the remaining accounts. Written
+/// back only once the whole pipeline has passed; each account is loaded once so no update
+/// is lost.
+#[derive(Default)]
+struct Loaded<'info> {
+    positions: Vec<Account<'info, Position>>,
+    profiles: Vec<(AccountLoader<'info, UserProfile>, UserProfile)>,
+    categories: Vec<Account<'info, RiskCategory>>,
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, Rebalance<'info>>, steps: Vec<PipelineStep>) -> Result<()> {
+    PipelineStep::validate(&steps)?;
+    let vault = &mut ctx.accounts.vault;
+    let protocol_config = &ctx.accounts.protocol_config;
+    let authority = ctx.accounts.authority.key();
+    let clock = Clock::get()?;
+
+    // 0. Security Checks
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+    if circuit_breaker::check(vault, 0, Some(ctx.accounts.price_feed_collateral.as_ref()), clock.unix_timestamp)? {
+        return Ok(());
+    }
+    // Queued withdrawals take idle liquidity ahead of anything the pipeline moves out
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    let before = (**vault).clone();
+    let health_before = risk::vault_portfolio(vault, collateral_price, debt_price)?.health_factor()?;
+
+    // 1. Run the steps in order. Positions stay in memory until every invariant holds.
+    let mut ledger = Ledger::default();
+    let mut loaded = Loaded::default();
+    let mut accounts = ctx.remaining_accounts;
+    for step in &steps {
+        match *step {
+            PipelineStep::Fetch { amount } => {
+                protocol_config.require_not_paused(pause_flags::DEPOSITS)?;
+                vault.require_supply_cap(amount)?;
+                // Token transfer from 'authority_token_account' into the vault would go here
+                vault.total_assets = vault.total_assets.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+                Ledger::add(&mut ledger.fetched, amount)?;
+            }
+            PipelineStep::Liquidate => {
+                protocol_config.require_not_paused(pause_flags::LIQUIDATIONS)?;
+                let event = liquidate(
+                    vault,
+                    &mut accounts,
+                    &mut loaded,
+                    authority,
+                    (ctx.accounts.token_in.key(), ctx.accounts.token_out.key()),
+                    (collateral_price, debt_price),
+                    &clock,
+                )?;
+                Ledger::add(&mut ledger.repaid, event.repaid)?;
+                Ledger::add(&mut ledger.seized, event.seized)?;
+                ledger.liquidations.push(event);
+            }
+            PipelineStep::Unload => {
+                protocol_config.require_not_paused(pause_flags::UNLOADS)?;
+                // The management fee belongs to the lock boost budget, so this burns nothing
+                // the vault accounts for, the same as `unload_vault`
+                // ... CPI to Flux Incinerator to burn assets ...
+            }
+            PipelineStep::Xfer { amount, travel_rule } => {
+                protocol_config.require_not_paused(pause_flags::TRANSFERS)?;
+                // Depositors' liquidity only leaves for an allowed venue, under the `xfer_funds` checks
+                require!(ctx.accounts.xfer_venue.is_some(), FluxError::VenueNotAllowed);
+                let (Some(user_profile), Some(compliance_config)) =
+                    (ctx.accounts.user_profile.as_ref(), ctx.accounts.compliance_config.as_ref())
+                else {
+                    return Err(error!(FluxError::MissingComplianceAccounts));
+                };
+                check_compliance(
+                    &*user_profile.load()?,
+                    compliance_config,
+                    amount,
+                    travel_rule.as_ref(),
+                    ctx.accounts.transfer_record.is_some(),
+                    clock.unix_timestamp,
+                )?;
+                let available = vault.total_assets.saturating_sub(vault.total_liabilities);
+                require!(amount <= available, FluxError::InsufficientLiquidity);
+                // Token transfer from the vault to 'recipient' would go here
+                vault.total_assets -= amount;
+                Ledger::add(&mut ledger.transferred, amount)?;
+            }
+        }
+    }
+    require!(accounts.is_empty(), FluxError::PipelineAccountsMismatch);
+
+    // 2. Shared invariants: fee accounting, solvency, and vault health at or above 1.0
+    //    (or no worse than before, so an unhealthy vault can still be liquidated back up)
+    ledger.reconcile(&before, vault)?;
+    require!(vault.is_solvent(), FluxError::PipelineInvariantViolated);
+    let health_after = risk::vault_portfolio(vault, collateral_price, debt_price)?.health_factor()?;
+    require!(
+        health_after >= risk::HEALTH_FACTOR_ONE || health_after >= health_before,
+        FluxError::PipelineInvariantViolated
+    );
+
+    // 3. Breakers see the pipeline's net flow. A trip rolls every step back and keeps only the freeze.
+    circuit_breaker::record_inflow(vault, ledger.fetched, clock.unix_timestamp);
+    if circuit_breaker::check(vault, ledger.outflow()?, None, clock.unix_timestamp)? {
+        let freeze_reason = vault.freeze_reason;
+        vault.set_inner(before);
+        vault.is_frozen = true;
+        vault.freeze_reason = freeze_reason;
+        if let Some(record) = ctx.accounts.transfer_record.as_ref() {
+            record.close(ctx.accounts.authority.to_account_info())?;
+        }
+        return Ok(());
+    }
+
+    // 4. Commit
+    for position in &loaded.positions {
+        position.exit(&crate::ID)?;
+    }
+    for (loader, profile) in &loaded.profiles {
+        *loader.load_mut()? = *profile;
+    }
+    for category in &loaded.categories {
+        category.exit(&crate::ID)?;
+    }
+    if ledger.transferred > 0 {
+        record_transfer(ctx.accounts, &steps, ledger.transferred, &clock, ctx.bumps.transfer_record)?;
+    }
+    let vault = &mut ctx.accounts.vault;
+    fill_withdrawals(vault, ctx.accounts.withdrawal_queue.as_mut(), clock.unix_timestamp)?;
+
+    for event in ledger.liquidations {
+        emit!(event);
+    }
+    msg!("Rebalanced in {} steps. Vault health: {} -> {}", steps.len(), health_before, health_after);
+    emit!(RebalancedEvent {
+        vault: vault.key(),
+        authority,
+        steps: steps.len() as u8,
+        fetched: ledger.fetched,
+        repaid: ledger.repaid,
+        seized: ledger.seized,
+        transferred: ledger.transferred,
+        health_factor_before: health_before,
+        health_factor_after: health_after,
+        timestamp: clock.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn add_venue_handler(ctx: Context<AddXferVenue>) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    require!(ctx.accounts.protocol_config.is_admin(&admin), FluxError::UnauthorizedAccess);
+    let now = Clock::get()?.unix_timestamp;
+
+    let xfer_venue = &mut ctx.accounts.xfer_venue;
+    xfer_venue.venue = ctx.accounts.venue.key();
+    xfer_venue.added_by = admin;
+    xfer_venue.added_at = now;
+    xfer_venue.bump = ctx.bumps.xfer_venue;
+
+    msg!("Xfer venue allowed: {}", xfer_venue.venue);
+    emit!(XferVenueUpdatedEvent {
+        venue: xfer_venue.venue,
+        allowed: true,
+        admin,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+pub fn remove_venue_handler(ctx: Context<RemoveXferVenue>) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    require!(ctx.accounts.protocol_config.is_admin(&admin), FluxError::UnauthorizedAccess);
+
+    msg!("Xfer venue removed: {}", ctx.accounts.xfer_venue.venue);
+    emit!(XferVenueUpdatedEvent {
+        venue: ctx.accounts.xfer_venue.venue,
+        allowed: false,
+        admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Log the pipeline's transfer on the authority's profile, as `xfer_funds` does, once it is final.
+fn record_transfer(accounts: &mut Rebalance, steps: &[PipelineStep], amount: u64, clock: &Clock, bump: u8) -> Result<()> {
+    let user_profile = accounts.user_profile.as_ref().ok_or(error!(FluxError::MissingComplianceAccounts))?;
+    let mut profile = user_profile.load_mut()?;
+    profile.record_action(ActionType::Withdraw, amount, clock);
+    append_history(
+        &profile,
+        accounts.action_history.as_ref(),
+        &accounts.instructions_sysvar.to_account_info(),
+        ActionType::Withdraw,
+        amount,
+        clock,
+    )?;
+    if let (Some(info), Some(record)) = (PipelineStep::travel_rule(steps), accounts.transfer_record.as_mut()) {
+        write_transfer_record(
+            record,
+            info,
+            user_profile.key(),
+            &mut profile,
+            accounts.authority.key(),
+            accounts.recipient.key(),
+            amount,
+            clock,
+            bump,
+        )?;
+    }
+    Ok(())
+}
+
+/// Liquidate the position at the front of `accounts`, seizing its base collateral and
+/// recording the liquidation on its owner's profile, and advance `accounts` past the
+/// position's group.
+fn liquidate<'info>(
+    vault: &mut Account<'info, Vault>,
+    accounts: &mut &'info [AccountInfo<'info>],
+    loaded: &mut Loaded<'info>,
+    liquidator: Pubkey,
+    (token_in, token_out): (Pubkey, Pubkey),
+    (collateral_price, debt_price): (OraclePrice, OraclePrice),
+    clock: &Clock,
+) -> Result<PositionLiquidatedEvent> {
+    let now = clock.unix_timestamp;
+    let (position_info, mut rest) = accounts.split_first().ok_or(error!(FluxError::PipelineAccountsMismatch))?;
+    require!(
+        position_info.is_writable && !loaded.positions.iter().any(|position| position.key() == position_info.key()),
+        FluxError::PipelineAccountsMismatch
+    );
+    let mut position = Account::<Position>::try_from(position_info)?;
+    require_keys_eq!(position.vault, vault.key(), FluxError::PipelineAccountsMismatch);
+
+    // The owner's canonical profile; positions are distinct, so each profile loads once
+    let (profile_info, tail) = rest.split_first().ok_or(error!(FluxError::PipelineAccountsMismatch))?;
+    require!(profile_info.is_writable, FluxError::PipelineAccountsMismatch);
+    rest = tail;
+    let profile_loader = AccountLoader::<UserProfile>::try_from(profile_info)?;
+    let mut profile = *profile_loader.load()?;
+    let profile_key = Pubkey::create_program_address(
+        &[b"profile", position.owner.as_ref(), &[profile.bump]],
+        &crate::ID,
+    ).map_err(|_| error!(FluxError::PipelineAccountsMismatch))?;
+    require_keys_eq!(profile_info.key(), profile_key, FluxError::PipelineAccountsMismatch);
+
+    let category_index = if position.risk_category != 0 {
+        let (category_info, tail) = rest.split_first().ok_or(error!(FluxError::PipelineAccountsMismatch))?;
+        require!(category_info.is_writable, FluxError::PipelineAccountsMismatch);
+        rest = tail;
+        match loaded.categories.iter().position(|category| category.key() == category_info.key()) {
+            Some(index) => Some(index),
+            None => {
+                loaded.categories.push(Account::<RiskCategory>::try_from(category_info)?);
+                Some(loaded.categories.len() - 1)
+            }
+        }
+    } else {
+        None
+    };
+    let reserve_accounts_len = 3 * position.reserve_count as usize;
+    require!(rest.len() >= reserve_accounts_len, FluxError::PipelineAccountsMismatch);
+    let (reserve_accounts, tail) = rest.split_at(reserve_accounts_len);
+    *accounts = tail;
+
+    // 1. Health across every collateral asset, as `liquidate_position` sees it
+    let category = position_category(&position, category_index.map(|index| &loaded.categories[index]))?;
+    let health_factor = position_portfolio(vault, &position, category, collateral_price, debt_price, reserve_accounts)?
+        .health_factor()?;
+    require!(health_factor < risk::HEALTH_FACTOR_ONE, FluxError::VaultHealthy);
+
+    // 2. Swap the seized collateral for the debt asset and settle
+    let (repaid, seized) = liquidation_amounts(
+        vault, position.debt_amount, debt_price, collateral_price, position.collateral_amount,
+    )?;
+    let _route_data = swap::route_data(token_in, token_out, seized, 50);
+    // ... CPI to Jupiter V6 swapping 'seized' collateral for the debt asset ...
+
+    position.collateral_amount -= seized;
+    vault.total_collateral = vault.total_collateral.checked_sub(seized).ok_or(error!(FluxError::ArithmeticError))?;
+    settle_debt(
+        vault,
+        &mut position,
+        category_index.map(|index| &mut *loaded.categories[index]),
+        &mut profile,
+        repaid,
+        now,
+    )?;
+    profile.record_action(ActionType::Liquidated, repaid, clock);
+    // Token transfer of the bonus to 'authority_token_account' would go here
+
+    msg!("Pipeline liquidated {}: repaid {}, seized {}", position.key(), repaid, seized);
+    let event = PositionLiquidatedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        liquidator,
+        collateral_reserve: None,
+        repaid,
+        seized,
+        health_factor,
+        timestamp: now,
+    };
+    loaded.positions.push(position);
+    loaded.profiles.push((profile_loader, profile));
+    Ok(event)
+}
@@ -0,0 +1,112 @@
+use anchor_lang::prelude::*;
+use crate::state::reputation::{ReputationConfig, ReputationParams};
+use crate::state::user_profile::{UserProfile, UserRole};
+use crate::state::config::ProtocolConfig;
+use crate::events::{ReputationConfigUpdatedEvent, RolePromotedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitReputationConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = ReputationConfig::LEN,
+        seeds = [b"reputation_config"],
+        bump
+    )]
+    pub reputation_config: Account<'info, ReputationConfig>,
+    /// Only the upgrade authority may create the singleton, so it cannot be front-run after deploy
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ FluxError::UnauthorizedAccess)]
+    pub program: Program<'info, crate::program::FluxCore>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ FluxError::UnauthorizedAccess)]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReputationConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"reputation_config"],
+        bump = reputation_config.bump,
+    )]
+    pub reputation_config: Account<'info, ReputationConfig>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+/// Permissionless: anyone may refresh a score or promote an eligible profile.
+#[derive(Accounts)]
+pub struct RefreshReputation<'info> {
+    #[account(mut)]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    #[account(seeds = [b"reputation_config"], bump = reputation_config.bump)]
+    pub reputation_config: Account<'info, ReputationConfig>,
+}
+
+pub fn init_config_handler(ctx: Context<InitReputationConfig>, params: ReputationParams) -> Result<()> {
+    params.validate()?;
+
+    let config = &mut ctx.accounts.reputation_config;
+    config.params = params;
+    config.last_update = Clock::get()?.unix_timestamp;
+    config.bump = ctx.bumps.reputation_config;
+
+    msg!("Reputation config initialized");
+    Ok(())
+}
+
+pub fn update_config_handler(ctx: Context<UpdateReputationConfig>, params: ReputationParams) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    params.validate()?;
+
+    let config = &mut ctx.accounts.reputation_config;
+    config.params = params;
+    config.last_update = Clock::get()?.unix_timestamp;
+
+    msg!("Reputation config updated");
+    emit!(ReputationConfigUpdatedEvent {
+        authority,
+        params,
+        timestamp: config.last_update,
+    });
+    Ok(())
+}
+
+pub fn refresh_handler(ctx: Context<RefreshReputation>) -> Result<()> {
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    profile.reputation_score = ctx.accounts.reputation_config.params.compute_score(&profile, now);
+
+    msg!("Reputation Score for {}: {}", profile.owner, profile.reputation_score);
+    Ok(())
+}
+
+pub fn promote_role_handler(ctx: Context<RefreshReputation>) -> Result<()> {
+    let params = &ctx.accounts.reputation_config.params;
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(profile.role() == UserRole::Standard, FluxError::UnauthorizedAccess);
+    require!(!profile.is_aml_flagged(), FluxError::AccountFlagged);
+
+    profile.reputation_score = params.compute_score(&profile, now);
+    require!(params.is_eligible_for_premium(&profile), FluxError::LowReputation);
+
+    profile.role = UserRole::Premium as u8;
+
+    msg!("Profile {} promoted to Premium", profile.owner);
+    emit!(RolePromotedEvent {
+        user_profile: ctx.accounts.user_profile.key(),
+        owner: profile.owner,
+        reputation_score: profile.reputation_score,
+        role: UserRole::Premium,
+        timestamp: now,
+    });
+    Ok(())
+}
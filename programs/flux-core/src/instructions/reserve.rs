@@ -0,0 +1,529 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::{
+    Reserve, ReserveConfig, CollateralDeposit, RiskCategory, RiskCategoryKind, RiskCategoryConfig,
+};
+use crate::risk::{Portfolio, RiskParams};
+use crate::oracle::{self, OraclePrice};
+use crate::events::{ReserveConfiguredEvent, RiskCategoryConfiguredEvent, PositionCategoryChangedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct AddReserve<'info> {
+    pub vault: Account<'info, Vault>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        space = Reserve::LEN,
+        seeds = [b"reserve", vault.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub reserve: Account<'info, Reserve>,
+    /// Required when `config.category` is set
+    #[account(has_one = vault)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateReserve<'info> {
+    #[account(
+        mut,
+        seeds = [b"reserve", reserve.vault.as_ref(), reserve.mint.as_ref()],
+        bump = reserve.bump,
+    )]
+    pub reserve: Account<'info, Reserve>,
+    /// Required when `config.category` is set
+    #[account(constraint = risk_category.vault == reserve.vault @ FluxError::RiskCategoryMismatch)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: u8)]
+pub struct AddRiskCategory<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = authority,
+        space = RiskCategory::LEN,
+        seeds = [b"risk_category", vault.key().as_ref(), &[id]],
+        bump
+    )]
+    pub risk_category: Account<'info, RiskCategory>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRiskCategory<'info> {
+    #[account(
+        mut,
+        seeds = [b"risk_category", risk_category.vault.as_ref(), &[risk_category.id]],
+        bump = risk_category.bump,
+    )]
+    pub risk_category: Account<'info, RiskCategory>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPositionCategory<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    /// The position's current category; required unless it has none
+    #[account(has_one = vault)]
+    pub current_category: Option<Account<'info, RiskCategory>>,
+    /// The category to enter; omitted to leave categories
+    #[account(has_one = vault)]
+    pub new_category: Option<Account<'info, RiskCategory>>,
+    pub owner: Signer<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+#[derive(Accounts)]
+pub struct OpenCollateralDeposit<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(has_one = vault)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        init,
+        payer = owner,
+        space = CollateralDeposit::LEN,
+        seeds = [b"collateral", position.key().as_ref(), reserve.key().as_ref()],
+        bump
+    )]
+    pub collateral_deposit: Account<'info, CollateralDeposit>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositReserveCollateral<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = vault)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"collateral", position.key().as_ref(), reserve.key().as_ref()],
+        bump = collateral_deposit.bump,
+    )]
+    pub collateral_deposit: Account<'info, CollateralDeposit>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (source)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawReserveCollateral<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(mut, has_one = vault)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        seeds = [b"collateral", position.key().as_ref(), reserve.key().as_ref()],
+        bump = collateral_deposit.bump,
+    )]
+    pub collateral_deposit: Account<'info, CollateralDeposit>,
+    /// The position's category; required unless it has none
+    #[account(has_one = vault)]
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (destination)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+
+    // Oracle Accounts for Price Feeds
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+#[derive(Accounts)]
+pub struct CloseCollateralDeposit<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(has_one = vault)]
+    pub reserve: Account<'info, Reserve>,
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"collateral", position.key().as_ref(), reserve.key().as_ref()],
+        bump = collateral_deposit.bump,
+        constraint = collateral_deposit.amount == 0 @ FluxError::CollateralDepositNotEmpty,
+    )]
+    pub collateral_deposit: Account<'info, CollateralDeposit>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+}
+
+pub fn add_handler(ctx: Context<AddReserve>, config: ReserveConfig) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    config.validate()?;
+
+    let reserve = &mut ctx.accounts.reserve;
+    reserve.vault = ctx.accounts.vault.key();
+    reserve.mint = ctx.accounts.mint.key();
+    reserve.decimals = ctx.accounts.mint.decimals;
+    reserve.apply_config(&config);
+    reserve.isolated = category_isolation(&config, ctx.accounts.risk_category.as_deref())? as u8;
+    reserve.bump = ctx.bumps.reserve;
+
+    msg!("Reserve {} added to vault {}", reserve.mint, reserve.vault);
+    emit!(ReserveConfiguredEvent {
+        vault: reserve.vault,
+        reserve: reserve.key(),
+        mint: reserve.mint,
+        previous: None,
+        current: config,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn update_handler(ctx: Context<UpdateReserve>, config: ReserveConfig) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    config.validate()?;
+
+    let reserve = &mut ctx.accounts.reserve;
+    let previous = reserve.config();
+    reserve.apply_config(&config);
+    reserve.isolated = category_isolation(&config, ctx.accounts.risk_category.as_deref())? as u8;
+
+    msg!("Reserve {} updated", reserve.key());
+    emit!(ReserveConfiguredEvent {
+        vault: reserve.vault,
+        reserve: reserve.key(),
+        mint: reserve.mint,
+        previous: Some(previous),
+        current: config,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Whether the category `config` assigns is isolated. The category account must be
+/// supplied so a reserve cannot name a category that does not exist.
+fn category_isolation(config: &ReserveConfig, category: Option<&RiskCategory>) -> Result<bool> {
+    match (config.category, category) {
+        (0, _) => Ok(false),
+        (id, Some(category)) if category.id == id => Ok(category.is_isolated()),
+        _ => Err(error!(FluxError::RiskCategoryMismatch)),
+    }
+}
+
+pub fn add_category_handler(
+    ctx: Context<AddRiskCategory>,
+    id: u8,
+    kind: RiskCategoryKind,
+    config: RiskCategoryConfig,
+) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+    require!(id != 0, FluxError::InvalidRiskCategory); // 0 means "no category"
+    config.validate(kind)?;
+
+    let category = &mut ctx.accounts.risk_category;
+    category.vault = ctx.accounts.vault.key();
+    category.id = id;
+    category.kind = kind;
+    category.apply_config(&config);
+    category.bump = ctx.bumps.risk_category;
+
+    msg!("Risk category {} ({:?}) added to vault {}", id, kind, category.vault);
+    emit!(RiskCategoryConfiguredEvent {
+        vault: category.vault,
+        category: category.key(),
+        id,
+        kind,
+        previous: None,
+        current: config,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn update_category_handler(ctx: Context<UpdateRiskCategory>, config: RiskCategoryConfig) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+
+    let category = &mut ctx.accounts.risk_category;
+    config.validate(category.kind)?;
+    // Lowering the ceiling below current debt only blocks new borrows
+    let previous = category.config();
+    category.apply_config(&config);
+
+    msg!("Risk category {} updated", category.id);
+    emit!(RiskCategoryConfiguredEvent {
+        vault: category.vault,
+        category: category.key(),
+        id: category.id,
+        kind: category.kind,
+        previous: Some(previous),
+        current: config,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn set_position_category_handler(ctx: Context<SetPositionCategory>) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+    let position = &mut ctx.accounts.position;
+    let current = position_category(position, ctx.accounts.current_category.as_ref())?;
+    let new = ctx.accounts.new_category.as_deref();
+    let new_id = new.map_or(0, |category| category.id);
+    require!(new_id != position.risk_category, FluxError::InvalidRiskCategory);
+
+    // Isolated debt is tracked per category, so it cannot move with the position
+    let isolated = current.is_some_and(RiskCategory::is_isolated) || new.is_some_and(RiskCategory::is_isolated);
+    require!(!isolated || position.debt_amount == 0, FluxError::CategoryChangeWithDebt);
+
+    // Leaving e-mode lowers the position's limits; it must still cover its debt
+    if position.debt_amount > 0 {
+        let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+        let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+        let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+        add_position_collateral(
+            &mut portfolio,
+            vault,
+            position,
+            new,
+            collateral_price,
+            &RiskParams::for_vault(vault)?,
+            ctx.remaining_accounts,
+        )?;
+        require!(portfolio.within_borrow_limit()?, FluxError::InsufficientCollateral);
+    }
+
+    let previous = position.risk_category;
+    position.risk_category = new_id;
+
+    msg!("Position {} risk category: {} -> {}", position.key(), previous, new_id);
+    emit!(PositionCategoryChangedEvent {
+        vault: vault.key(),
+        position: position.key(),
+        previous,
+        current: new_id,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+pub fn open_deposit_handler(ctx: Context<OpenCollateralDeposit>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    require!(position.reserve_count < Position::MAX_RESERVES, FluxError::TooManyReserves);
+    position.reserve_count += 1;
+
+    let deposit = &mut ctx.accounts.collateral_deposit;
+    deposit.position = position.key();
+    deposit.reserve = ctx.accounts.reserve.key();
+    deposit.bump = ctx.bumps.collateral_deposit;
+
+    msg!("Collateral deposit opened for reserve {}", deposit.reserve);
+    Ok(())
+}
+
+pub fn deposit_handler(ctx: Context<DepositReserveCollateral>, amount: u64) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &ctx.accounts.vault;
+    let reserve = &mut ctx.accounts.reserve;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::DEPOSITS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+
+    let total = reserve.total_deposits.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+    require!(reserve.deposit_cap == 0 || total <= reserve.deposit_cap, FluxError::ReserveDepositCapExceeded);
+
+    // Token transfer from 'owner_token_account' into the reserve would go here
+    reserve.total_deposits = total;
+    let deposit = &mut ctx.accounts.collateral_deposit;
+    deposit.amount = deposit.amount.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+
+    msg!("Reserve collateral deposited: {}. Balance: {}", amount, deposit.amount);
+    Ok(())
+}
+
+pub fn withdraw_handler(ctx: Context<WithdrawReserveCollateral>, amount: u64) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &ctx.accounts.vault;
+    let position = &ctx.accounts.position;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::WITHDRAWALS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+
+    let deposit = &mut ctx.accounts.collateral_deposit;
+    require!(amount <= deposit.amount, FluxError::InsufficientCollateral);
+    deposit.amount -= amount;
+    let reserve = &mut ctx.accounts.reserve;
+    reserve.total_deposits = reserve.total_deposits.saturating_sub(amount);
+
+    // The remaining collateral must still cover the position's debt
+    if position.debt_amount > 0 {
+        // The health check reads the deposit through the remaining accounts
+        ctx.accounts.collateral_deposit.exit(&crate::ID)?;
+        let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+        let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+        let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+        let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+        add_position_collateral(
+            &mut portfolio,
+            vault,
+            position,
+            category,
+            collateral_price,
+            &RiskParams::for_vault(vault)?,
+            ctx.remaining_accounts,
+        )?;
+        require!(portfolio.within_borrow_limit()?, FluxError::InsufficientCollateral);
+    }
+
+    // Token transfer from the reserve to 'owner_token_account' would go here
+    msg!("Reserve collateral withdrawn: {}. Balance: {}", amount, ctx.accounts.collateral_deposit.amount);
+    Ok(())
+}
+
+pub fn close_deposit_handler(ctx: Context<CloseCollateralDeposit>) -> Result<()> {
+    let position = &mut ctx.accounts.position;
+    position.reserve_count = position.reserve_count.saturating_sub(1);
+
+    msg!("Collateral deposit closed for reserve {}", ctx.accounts.reserve.key());
+    Ok(())
+}
+
+/// The category `position` has opted into, checked against the supplied account.
+pub(crate) fn position_category<'a>(
+    position: &Position,
+    category: Option<&'a Account<RiskCategory>>,
+) -> Result<Option<&'a RiskCategory>> {
+    match (position.risk_category, category) {
+        (0, None) => Ok(None),
+        (id, Some(category)) if id != 0 && category.id == id && category.vault == position.vault => {
+            Ok(Some(category))
+        }
+        _ => Err(error!(FluxError::RiskCategoryMismatch)),
+    }
+}
+
+/// Value all collateral of `position` into `portfolio` and return the base collateral's value.
+///
+/// Reserves in the position's `category` use the category's limits. An isolated position
+/// counts only its category's reserves, while other positions never count isolated reserves.
+/// `accounts` must hold one `[reserve, collateral_deposit, price_feed]` triple for each
+/// deposit the position has opened, so a caller cannot leave collateral out of a health check.
+pub(crate) fn add_position_collateral(
+    portfolio: &mut Portfolio,
+    vault: &Account<Vault>,
+    position: &Account<Position>,
+    category: Option<&RiskCategory>,
+    base_price: OraclePrice,
+    base_params: &RiskParams,
+    accounts: &[AccountInfo],
+) -> Result<u128> {
+    let isolated = category.is_some_and(RiskCategory::is_isolated);
+    let base_value = if isolated {
+        0
+    } else {
+        portfolio.add_collateral(position.collateral_amount, base_price, base_params)?
+    };
+
+    require!(
+        accounts.len() == 3 * position.reserve_count as usize,
+        FluxError::ReserveAccountsMismatch
+    );
+    let mut seen: Vec<Pubkey> = Vec::with_capacity(position.reserve_count as usize);
+    for triple in accounts.chunks(3) {
+        let reserve: Reserve = load_owned(&triple[0])?;
+        let deposit: CollateralDeposit = load_owned(&triple[1])?;
+        require_keys_eq!(reserve.vault, vault.key(), FluxError::ReserveAccountsMismatch);
+        require_keys_eq!(deposit.position, position.key(), FluxError::ReserveAccountsMismatch);
+        require_keys_eq!(deposit.reserve, triple[0].key(), FluxError::ReserveAccountsMismatch);
+        require_keys_eq!(triple[2].key(), reserve.oracle, FluxError::OracleMismatch);
+        require!(!seen.contains(triple[1].key), FluxError::ReserveAccountsMismatch);
+        seen.push(triple[1].key());
+
+        let params = match category {
+            Some(category) if category.id == reserve.category => RiskParams::for_category(vault, category),
+            _ if isolated || reserve.is_isolated() => continue,
+            _ => RiskParams::for_reserve(vault, &reserve),
+        };
+        let price = oracle::read_price(&triple[2])?;
+        portfolio.add_collateral(reserve.normalize(deposit.amount)?, price, &params)?;
+    }
+    Ok(base_value)
+}
+
+fn load_owned<T: AccountDeserialize>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(*info.owner, crate::ID, FluxError::ReserveAccountsMismatch);
+    T::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+/// Price of `reserve`'s collateral from its feed among the `add_position_collateral` accounts.
+pub(crate) fn reserve_price(reserve: &Reserve, accounts: &[AccountInfo]) -> Result<OraclePrice> {
+    let feed = accounts
+        .iter()
+        .find(|info| info.key() == reserve.oracle)
+        .ok_or(error!(FluxError::OracleMismatch))?;
+    oracle::read_price(feed)
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::state::price_history::PriceHistory;
+use crate::circuit_breaker;
+use crate::oracle;
+use crate::events::RiskRefreshedEvent;
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitPriceHistory<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = payer,
+        space = PriceHistory::LEN,
+        seeds = [b"price_history", vault.key().as_ref()],
+        bump
+    )]
+    pub price_history: AccountLoader<'info, PriceHistory>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless crank.
+#[derive(Accounts)]
+pub struct RefreshRisk<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"price_history", vault.key().as_ref()],
+        bump = price_history.load()?.bump,
+    )]
+    pub price_history: AccountLoader<'info, PriceHistory>,
+    /// CHECK: Collateral price feed; must match the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed: UncheckedAccount<'info>,
+}
+
+pub fn init_price_history_handler(ctx: Context<InitPriceHistory>) -> Result<()> {
+    let mut history = ctx.accounts.price_history.load_init()?;
+    history.vault = ctx.accounts.vault.key();
+    history.oracle = ctx.accounts.vault.oracle_config;
+    history.bump = ctx.bumps.price_history;
+
+    msg!("Price history opened for vault {}", history.vault);
+    Ok(())
+}
+
+pub fn refresh_handler(ctx: Context<RefreshRisk>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let mut history = ctx.accounts.price_history.load_mut()?;
+    let now = Clock::get()?.unix_timestamp;
+
+    // Spacing samples out keeps a single caller from flattening the volatility estimate
+    if let Some(last) = history.last() {
+        require!(now - last.timestamp >= Vault::RISK_REFRESH_INTERVAL, FluxError::RateLimitExceeded);
+    }
+
+    // A change of oracle restarts the series
+    if history.oracle != vault.oracle_config {
+        history.oracle = vault.oracle_config;
+        history.len = 0;
+        history.head = 0;
+    }
+
+    let price = oracle::read_price(&ctx.accounts.price_feed)?;
+    history.push(price.price, now);
+
+    if !vault.is_frozen && circuit_breaker::check(vault, 0, Some(ctx.accounts.price_feed.as_ref()), now)? {
+        return Ok(());
+    }
+
+    let Some(volatility) = history.realized_volatility() else {
+        msg!("Not enough price samples yet ({})", history.len);
+        return Ok(());
+    };
+    vault.apply_risk_adjustment(volatility);
+    vault.last_risk_refresh = now;
+
+    emit!(RiskRefreshedEvent {
+        vault: vault.key(),
+        price: price.price,
+        volatility_bps: volatility,
+        risk_factor: vault.risk_factor,
+        liquidation_penalty: vault.liquidation_penalty,
+        timestamp: now,
+    });
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
 use crate::state::vault::Vault;
 
 #[derive(Accounts)]
@@ -8,9 +9,15 @@ pub struct UnloadVault<'info> {
     /// CHECK: Incinerator program
     pub incinerator_program: UncheckedAccount<'info>,
     pub authority: Signer<'info>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
 }
 
-pub fn handler(_ctx: Context<UnloadVault>) -> Result<()> {
+pub fn handler(ctx: Context<UnloadVault>) -> Result<()> {
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::UNLOADS)?;
+    require!(!ctx.accounts.vault.is_frozen, crate::errors::FluxError::VaultFrozen);
+    ctx.accounts.vault.require_no_flash_loan()?;
+
     msg!("Connecting to Flux Incinerator...");
     
     // ... CPI to Flux Incinerator to burn assets ...
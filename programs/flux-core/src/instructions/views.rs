@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::state::position::Position;
+use crate::state::reserve::RiskCategory;
+use crate::oracle;
+use crate::risk::{self, Portfolio, RiskParams};
+use crate::instructions::reserve::{add_position_collateral, position_category};
+use crate::errors::FluxError;
+
+// Read-only instructions. Values are computed after simulating interest accrual up to
+// the current clock and returned through `set_return_data`; call them with
+// `simulateTransaction` to get the program's own numbers.
+
+#[derive(Accounts)]
+pub struct GetVaultHealth<'info> {
+    pub vault: Account<'info, Vault>,
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetPositionHealth<'info> {
+    pub vault: Account<'info, Vault>,
+    #[account(
+        seeds = [b"position", vault.key().as_ref(), position.owner.as_ref()],
+        bump = position.bump,
+    )]
+    pub position: Account<'info, Position>,
+    /// Required once the position has opted into a risk category
+    pub risk_category: Option<Account<'info, RiskCategory>>,
+    /// CHECK: Collateral Price Feed; must be the vault's configured oracle
+    #[account(address = vault.oracle_config @ FluxError::OracleMismatch)]
+    pub price_feed_collateral: UncheckedAccount<'info>,
+    /// CHECK: Debt Price Feed; must be the vault's configured debt oracle
+    #[account(address = vault.debt_oracle @ FluxError::OracleMismatch)]
+    pub price_feed_debt: UncheckedAccount<'info>,
+    // Remaining accounts: one [reserve, collateral_deposit, price_feed] triple per reserve deposit
+}
+
+#[derive(Accounts)]
+pub struct GetRates<'info> {
+    pub vault: Account<'info, Vault>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct VaultHealthView {
+    pub total_assets: u64,
+    pub total_liabilities: u64,
+    pub total_collateral: u64,
+    pub utilization_bps: u64,
+    pub ltv_bps: u64,
+    pub health_factor: u64, // `HEALTH_FACTOR_ONE` = 1.0
+    pub max_ltv_bps: u64,
+    pub liquidation_threshold_bps: u64,
+    pub is_solvent: bool,
+    pub is_frozen: bool,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct PositionHealthView {
+    pub collateral_amount: u64, // base collateral
+    pub debt_amount: u64,
+    // Values use `risk::VALUE_DECIMALS` and include reserve collateral
+    pub base_collateral_value: u128,
+    pub collateral_value: u128,
+    pub debt_value: u128,
+    pub ltv_bps: u64,
+    pub health_factor: u64,
+    pub liquidatable: bool,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct RatesView {
+    pub utilization_bps: u64,
+    pub borrow_rate_bps: u64,
+    pub supply_rate_bps: u64,
+    pub flash_loan_fee_rate: u16,
+    pub timestamp: i64,
+}
+
+/// Copy of the vault with interest accrued to `now`; the account itself is not written.
+fn accrued(vault: &Vault, now: i64) -> Result<Vault> {
+    let mut vault = vault.clone();
+    vault.accrue_interest(now)?;
+    Ok(vault)
+}
+
+pub fn vault_health_handler(ctx: Context<GetVaultHealth>) -> Result<VaultHealthView> {
+    let now = Clock::get()?.unix_timestamp;
+    let vault = accrued(&ctx.accounts.vault, now)?;
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+
+    let params = RiskParams::for_vault(&vault)?;
+    let portfolio = risk::vault_portfolio(&vault, collateral_price, debt_price)?;
+
+    Ok(VaultHealthView {
+        total_assets: vault.total_assets,
+        total_liabilities: vault.total_liabilities,
+        total_collateral: vault.total_collateral,
+        utilization_bps: vault.utilization_bps(),
+        ltv_bps: portfolio.ltv_bps()?,
+        health_factor: portfolio.health_factor()?,
+        max_ltv_bps: params.max_ltv_bps,
+        liquidation_threshold_bps: params.liquidation_threshold_bps,
+        is_solvent: vault.is_solvent(),
+        is_frozen: vault.is_frozen,
+        timestamp: now,
+    })
+}
+
+pub fn position_health_handler(ctx: Context<GetPositionHealth>) -> Result<PositionHealthView> {
+    let now = Clock::get()?.unix_timestamp;
+    let position = &ctx.accounts.position;
+    let collateral_price = oracle::read_price(&ctx.accounts.price_feed_collateral)?;
+    let debt_price = oracle::read_price(&ctx.accounts.price_feed_debt)?;
+
+    let category = position_category(position, ctx.accounts.risk_category.as_ref())?;
+
+    let mut portfolio = Portfolio::new(position.debt_amount, debt_price)?;
+    let base_value = add_position_collateral(
+        &mut portfolio,
+        &ctx.accounts.vault,
+        position,
+        category,
+        collateral_price,
+        &RiskParams::for_vault(&accrued(&ctx.accounts.vault, now)?)?,
+        ctx.remaining_accounts,
+    )?;
+
+    Ok(PositionHealthView {
+        collateral_amount: position.collateral_amount,
+        debt_amount: position.debt_amount,
+        base_collateral_value: base_value,
+        collateral_value: portfolio.collateral_value,
+        debt_value: portfolio.debt_value,
+        ltv_bps: portfolio.ltv_bps()?,
+        health_factor: portfolio.health_factor()?,
+        liquidatable: portfolio.is_liquidatable()?,
+        timestamp: now,
+    })
+}
+
+pub fn rates_handler(ctx: Context<GetRates>) -> Result<RatesView> {
+    let now = Clock::get()?.unix_timestamp;
+    let vault = accrued(&ctx.accounts.vault, now)?;
+
+    Ok(RatesView {
+        utilization_bps: vault.utilization_bps(),
+        borrow_rate_bps: vault.borrow_rate_bps(),
+        supply_rate_bps: vault.supply_rate_bps(),
+        flash_loan_fee_rate: vault.flash_loan_fee_rate,
+        timestamp: now,
+    })
+}
@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::vault::Vault;
+use crate::state::supply::SupplyPosition;
+use crate::state::withdrawal_queue::{WithdrawalQueue, WithdrawalTicket};
+use crate::circuit_breaker;
+use crate::events::{WithdrawalRequestedEvent, WithdrawalsFilledEvent, WithdrawalClaimedEvent};
+use crate::errors::FluxError;
+
+#[derive(Accounts)]
+pub struct InitWithdrawalQueue<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        init,
+        payer = authority,
+        space = WithdrawalQueue::LEN,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdrawal<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+    #[account(
+        mut,
+        seeds = [b"supply_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump = supply_position.bump,
+    )]
+    pub supply_position: Account<'info, SupplyPosition>,
+    #[account(
+        init,
+        payer = owner,
+        space = WithdrawalTicket::LEN,
+        seeds = [
+            b"withdrawal_ticket",
+            withdrawal_queue.key().as_ref(),
+            withdrawal_queue.ticket_count.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdrawal<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        has_one = vault,
+        seeds = [b"withdrawal_queue", vault.key().as_ref()],
+        bump = withdrawal_queue.bump,
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+    #[account(
+        mut,
+        has_one = owner,
+        constraint = ticket.queue == withdrawal_queue.key() @ FluxError::UnauthorizedAccess,
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK: Owner token account (destination)
+    #[account(mut)]
+    pub owner_token_account: UncheckedAccount<'info>,
+}
+
+pub fn init_handler(ctx: Context<InitWithdrawalQueue>) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    require!(ctx.accounts.protocol_config.is_risk_manager(&authority), FluxError::UnauthorizedAccess);
+
+    let queue = &mut ctx.accounts.withdrawal_queue;
+    queue.vault = ctx.accounts.vault.key();
+    queue.bump = ctx.bumps.withdrawal_queue;
+    ctx.accounts.vault.has_withdrawal_queue = 1;
+
+    msg!("Withdrawal queue opened for vault {}", queue.vault);
+    Ok(())
+}
+
+pub fn request_handler(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
+    require!(amount > 0, FluxError::InvalidAmount);
+    let vault = &mut ctx.accounts.vault;
+    let supply_position = &mut ctx.accounts.supply_position;
+    let queue = &mut ctx.accounts.withdrawal_queue;
+    let clock = Clock::get()?;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::WITHDRAWALS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+    vault.accrue_interest(clock.unix_timestamp)?;
+
+    // 1. Lock the shares in the ticket; they stay in `total_assets` until filled
+    supply_position.shares = supply_position.shares
+        .checked_sub(amount)
+        .ok_or(error!(FluxError::InsufficientShares))?;
+
+    // 2. Issue the ticket at the back of the queue
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.queue = queue.key();
+    ticket.supply_position = supply_position.key();
+    ticket.owner = ctx.accounts.owner.key();
+    ticket.id = queue.ticket_count;
+    ticket.amount = amount;
+    ticket.start = queue.total_requested;
+    ticket.created_at = clock.unix_timestamp;
+    ticket.bump = ctx.bumps.ticket;
+
+    let queued_ahead = queue.pending();
+    queue.ticket_count += 1;
+    queue.total_requested = queue.total_requested.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+
+    msg!("Withdrawal ticket {} queued for {} behind {}", ticket.id, amount, queued_ahead);
+    emit!(WithdrawalRequestedEvent {
+        vault: vault.key(),
+        ticket: ticket.key(),
+        owner: ticket.owner,
+        id: ticket.id,
+        amount,
+        queued_ahead,
+        timestamp: clock.unix_timestamp,
+    });
+
+    // 3. Fill immediately from any idle liquidity
+    fill_withdrawals(vault, Some(queue), clock.unix_timestamp)
+}
+
+pub fn claim_handler(ctx: Context<ClaimWithdrawal>) -> Result<()> {
+    let vault = &mut ctx.accounts.vault;
+    let queue = &mut ctx.accounts.withdrawal_queue;
+    let ticket = &mut ctx.accounts.ticket;
+    let clock = Clock::get()?;
+
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::WITHDRAWALS)?;
+    require!(!vault.is_frozen, FluxError::VaultFrozen);
+    vault.require_no_flash_loan()?;
+
+    let amount = queue.claimable(ticket);
+    require!(amount > 0, FluxError::NothingToClaim);
+    if circuit_breaker::check(vault, amount, None, clock.unix_timestamp)? {
+        return Ok(());
+    }
+
+    // Token transfer from the vault to 'owner_token_account' would go here
+    ticket.claimed += amount;
+    queue.escrowed -= amount;
+
+    msg!("Withdrawal ticket {} claimed {}", ticket.id, amount);
+    emit!(WithdrawalClaimedEvent {
+        vault: vault.key(),
+        ticket: ticket.key(),
+        owner: ticket.owner,
+        amount,
+        remaining: ticket.amount - ticket.claimed,
+        timestamp: clock.unix_timestamp,
+    });
+
+    if ticket.is_settled() {
+        ticket.close(ctx.accounts.owner.to_account_info())?;
+    }
+    Ok(())
+}
+
+/// Fill pending withdrawals from the vault's free liquidity. Runs on every inflow, including
+/// debt repaid by selling collateral, and before borrows, so a vault with a queue must
+/// always be passed it.
+pub(crate) fn fill_withdrawals(
+    vault: &mut Account<Vault>,
+    queue: Option<&mut Account<WithdrawalQueue>>,
+    now: i64,
+) -> Result<()> {
+    let Some(queue) = queue else {
+        require!(!vault.has_withdrawal_queue(), FluxError::MissingWithdrawalQueue);
+        return Ok(());
+    };
+
+    let filled = queue.fill(vault);
+    if filled > 0 {
+        msg!("Withdrawal queue filled {}. Pending: {}", filled, queue.pending());
+        emit!(WithdrawalsFilledEvent {
+            vault: vault.key(),
+            filled,
+            total_filled: queue.total_filled,
+            pending: queue.pending(),
+            timestamp: now,
+        });
+    }
+    Ok(())
+}
@@ -1,11 +1,27 @@
 use anchor_lang::prelude::*;
-use crate::state::user_profile::{UserProfile, ActionType};
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use crate::state::config::{ProtocolConfig, pause_flags};
+use crate::state::user_profile::{UserProfile, ActionType, ActionHistory};
+use crate::instructions::profile::append_history;
+use crate::state::compliance::{ComplianceConfig, TransferRecord, TravelRuleInfo};
+use crate::events::TravelRuleTransferEvent;
 use crate::errors::FluxError;
 
 #[derive(Accounts)]
 pub struct XferFunds<'info> {
-    #[account(mut)]
-    pub user_profile: Account<'info, UserProfile>,
+    #[account(
+        mut,
+        seeds = [b"profile", authority.key().as_ref()],
+        bump = user_profile.load()?.bump,
+    )]
+    pub user_profile: AccountLoader<'info, UserProfile>,
+    /// Required once the profile has opened an action history
+    #[account(
+        mut,
+        seeds = [b"history", user_profile.key().as_ref()],
+        bump = action_history.load()?.bump,
+    )]
+    pub action_history: Option<AccountLoader<'info, ActionHistory>>,
     #[account(mut)]
     pub authority: Signer<'info>,
     
@@ -14,39 +30,52 @@ pub struct XferFunds<'info> {
     pub recipient: UncheckedAccount<'info>,
     
     pub system_program: Program<'info, System>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, ProtocolConfig>,
     
     // Compliance Accounts
     /// CHECK: Global blacklist
     pub blacklist_registry: UncheckedAccount<'info>,
+    #[account(seeds = [b"compliance_config"], bump = compliance_config.bump)]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+    /// Required when `travel_rule` data is supplied
+    #[account(
+        init,
+        payer = authority,
+        space = TransferRecord::LEN,
+        seeds = [b"transfer_record", user_profile.key().as_ref(), TransferRecord::next_nonce(Some(&user_profile))?.as_ref()],
+        bump
+    )]
+    pub transfer_record: Option<Account<'info, TransferRecord>>,
+    
+    /// CHECK: Instructions sysvar, used to identify this instruction in the history log
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
 }
 
-pub fn handler(ctx: Context<XferFunds>, amount: u64) -> Result<()> {
-    let profile = &mut ctx.accounts.user_profile;
+pub fn handler(ctx: Context<XferFunds>, amount: u64, travel_rule: Option<TravelRuleInfo>) -> Result<()> {
+    let profile_key = ctx.accounts.user_profile.key();
+    let mut profile = ctx.accounts.user_profile.load_mut()?;
     let clock = Clock::get()?;
 
-    // 1. Compliance Checks (KYC/AML)
-    msg!("Performing Compliance Checks...");
-    if profile.aml_flagged {
-        return Err(error!(FluxError::AccountFlagged));
-    }
-    
-    if !profile.kyc_verified && amount > 10_000_000_000 { // > 10 SOL requires KYC
-        msg!("Transfer exceeds limit for unverified user.");
-        return Err(error!(FluxError::TransferLimitExceeded));
-    }
+    ctx.accounts.protocol_config.require_not_paused(pause_flags::TRANSFERS)?;
+
+    // 1. Compliance Checks (KYC/AML, travel rule, rate limit)
+    check_compliance(
+        &profile,
+        &ctx.accounts.compliance_config,
+        amount,
+        travel_rule.as_ref(),
+        ctx.accounts.transfer_record.is_some(),
+        clock.unix_timestamp,
+    )?;
 
     // 2. Check Blacklist
     // Mock check: if recipient starts with "Bad", block it (conceptually)
     // In reality, we'd check a PDA or Bitmask in 'blacklist_registry'
     msg!("Verifying recipient against Global Blocklist...");
-    
-    // 3. Rate Limiting / Time Locks
-    let time_since_last_tx = clock.unix_timestamp - profile.last_active_timestamp;
-    if time_since_last_tx < 30 { // 30 seconds cooldown
-        return Err(error!(FluxError::RateLimitExceeded));
-    }
 
-    // 4. Execution
+    // 3. Execution
     msg!("Executing Secure Transfer of {} lamports...", amount);
     
     let ix = anchor_lang::solana_program::system_instruction::transfer(
@@ -64,15 +93,111 @@ pub fn handler(ctx: Context<XferFunds>, amount: u64) -> Result<()> {
         ]
     )?;
 
-    // 5. Update History
+    if let (Some(info), Some(record)) = (travel_rule, ctx.accounts.transfer_record.as_mut()) {
+        write_transfer_record(
+            record,
+            info,
+            profile_key,
+            &mut profile,
+            ctx.accounts.authority.key(),
+            ctx.accounts.recipient.key(),
+            amount,
+            &clock,
+            ctx.bumps.transfer_record,
+        )?;
+    }
+
+    // 4. Update History
     msg!("Recording transaction in immutable log...");
     profile.record_action(ActionType::Withdraw, amount, &clock);
+    append_history(
+        &profile,
+        ctx.accounts.action_history.as_ref(),
+        &ctx.accounts.instructions_sysvar.to_account_info(),
+        ActionType::Withdraw,
+        amount,
+        &clock,
+    )?;
     
-    // 6. Analytics
+    // 5. Analytics
     if amount > 100_000_000_000 {
         msg!("Whale Alert: Large transfer detected!");
     }
 
-    msg!("Transfer complete. New Reputation Score: {}", profile.reputation_score);
+    msg!("Transfer complete. Reputation Score: {}", profile.reputation_score);
+    Ok(())
+}
+
+/// KYC/AML, travel-rule and rate-limit checks every outgoing transfer by `profile`'s owner passes.
+pub(crate) fn check_compliance(
+    profile: &UserProfile,
+    compliance_config: &ComplianceConfig,
+    amount: u64,
+    travel_rule: Option<&TravelRuleInfo>,
+    has_record: bool,
+    now: i64,
+) -> Result<()> {
+    msg!("Performing Compliance Checks...");
+    if profile.is_aml_flagged() {
+        return Err(error!(FluxError::AccountFlagged));
+    }
+
+    if !profile.is_kyc_verified() && amount > ComplianceConfig::UNVERIFIED_TRANSFER_LIMIT {
+        msg!("Transfer exceeds limit for unverified user.");
+        return Err(error!(FluxError::TransferLimitExceeded));
+    }
+
+    // Travel Rule (originator / beneficiary data for large transfers)
+    if compliance_config.requires_travel_rule(amount) {
+        require!(travel_rule.is_some(), FluxError::TravelRuleRequired);
+    }
+    require!(travel_rule.is_some() == has_record, FluxError::TravelRuleMismatch);
+    if let Some(info) = travel_rule {
+        require!(info.counterparty_vasp != [0; 32], FluxError::InvalidCounterpartyVasp);
+    }
+
+    // Rate Limiting / Time Locks
+    let time_since_last_tx = now - profile.last_active_timestamp;
+    if time_since_last_tx < ComplianceConfig::TRANSFER_COOLDOWN {
+        return Err(error!(FluxError::RateLimitExceeded));
+    }
+    Ok(())
+}
+
+/// Fill the travel-rule record of a completed transfer, taking the profile's next nonce.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_transfer_record(
+    record: &mut Account<TransferRecord>,
+    info: TravelRuleInfo,
+    user_profile: Pubkey,
+    profile: &mut UserProfile,
+    sender: Pubkey,
+    recipient: Pubkey,
+    amount: u64,
+    clock: &Clock,
+    bump: u8,
+) -> Result<()> {
+    record.user_profile = user_profile;
+    record.nonce = profile.transfer_records;
+    record.sender = sender;
+    record.recipient = recipient;
+    record.amount = amount;
+    record.payload_hash = info.payload_hash;
+    record.counterparty_vasp = info.counterparty_vasp;
+    record.timestamp = clock.unix_timestamp;
+    record.slot = clock.slot;
+    record.bump = bump;
+
+    msg!("Travel-rule record stored at {}", record.key());
+    emit!(TravelRuleTransferEvent {
+        transfer_record: record.key(),
+        sender,
+        recipient,
+        amount,
+        payload_hash: info.payload_hash,
+        counterparty_vasp: info.counterparty_vasp,
+        timestamp: clock.unix_timestamp,
+    });
+    profile.transfer_records = profile.transfer_records.checked_add(1).ok_or(error!(FluxError::ArithmeticError))?;
     Ok(())
 }
@@ -5,16 +5,49 @@ use anchor_lang::prelude::*;
 // This software is provided "as is", without warranty of any kind.
 // See the LICENSE file for more details.
 
+pub mod circuit_breaker;
 pub mod errors;
+pub mod events;
 pub mod instructions;
+pub mod oracle;
+pub mod risk;
 pub mod state;
+pub mod swap;
 
 // Re-export specific instructions to avoid namespace collisions
 use instructions::fetch::*;
 use instructions::liquidate::*;
 use instructions::unload::*;
 use instructions::xfer::*;
-use instructions::admin;
+use instructions::compliance::*;
+use instructions::profile::*;
+use instructions::position::*;
+use instructions::reputation::*;
+use instructions::admin::*;
+use instructions::authority::*;
+use instructions::multisig::*;
+use instructions::risk::*;
+use instructions::views::*;
+use instructions::flash::*;
+use instructions::reserve::*;
+use instructions::withdrawal::*;
+use instructions::lock::*;
+use instructions::deleverage::*;
+use instructions::order::*;
+use instructions::leverage::*;
+use instructions::rebalance::*;
+use state::compliance::TravelRuleInfo;
+use state::reputation::ReputationParams;
+use state::config::ProtocolRoles;
+use state::user_profile::UserRole;
+use state::vault::VaultConfigUpdate;
+use state::multisig::ProposedInstruction;
+use circuit_breaker::CircuitBreakerParams;
+use state::reserve::{ReserveConfig, RiskCategoryKind, RiskCategoryConfig};
+use state::deposit_lock::{LockTerms, LockTerm};
+use state::position::AutoDeleverageParams;
+use state::order::OrderParams;
+use state::pipeline::PipelineStep;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -24,12 +57,18 @@ pub mod flux_core {
 
     // --- Core User Instructions ---
 
-    /// Scan assets and deposit them into the protocol vault.
+    /// Open a depositor's share account in a vault (PDA: [b"supply_position", vault, owner]).
+    pub fn open_supply_position(ctx: Context<OpenSupplyPosition>) -> Result<()> {
+        instructions::fetch::open_supply_handler(ctx)
+    }
+
+    /// Scan assets and deposit them into the protocol vault, minting the depositor shares.
     pub fn fetch_assets(ctx: Context<FetchAssets>, amount: u64) -> Result<()> {
         instructions::fetch::handler(ctx, amount)
     }
 
-    /// Liquidate an insolvent position using Jupiter Aggregator (CPI).
+    /// Liquidate an unhealthy position using Jupiter Aggregator (CPI). The liquidator picks
+    /// the collateral to seize: a reserve, or the base collateral when none is given.
     pub fn liquidate_position(ctx: Context<LiquidatePosition>) -> Result<()> {
         instructions::liquidate::handler(ctx)
     }
@@ -40,34 +79,445 @@ pub mod flux_core {
     }
 
     /// Securely transfer funds between compliant accounts.
-    pub fn xfer_funds(ctx: Context<XferFunds>, amount: u64) -> Result<()> {
-        instructions::xfer::handler(ctx, amount)
+    /// Transfers above the travel-rule threshold must carry `travel_rule` data.
+    pub fn xfer_funds(ctx: Context<XferFunds>, amount: u64, travel_rule: Option<TravelRuleInfo>) -> Result<()> {
+        instructions::xfer::handler(ctx, amount, travel_rule)
+    }
+
+    /// Run Fetch, Liquidate, Unload and Xfer steps in order as one all-or-nothing rebalance.
+    /// Vault authority only. The pipeline must leave fees reconciled, the vault solvent and
+    /// its health factor at or above 1.0; a circuit breaker trip rolls back every step. Xfer
+    /// sends only to allowed venues and passes the same compliance checks as `xfer_funds`.
+    pub fn rebalance<'info>(ctx: Context<'_, '_, 'info, 'info, Rebalance<'info>>, steps: Vec<PipelineStep>) -> Result<()> {
+        instructions::rebalance::handler(ctx, steps)
+    }
+
+    /// Allow `Xfer` steps to send to a venue (PDA: [b"xfer_venue", venue]). Admin only.
+    pub fn add_xfer_venue(ctx: Context<AddXferVenue>) -> Result<()> {
+        instructions::rebalance::add_venue_handler(ctx)
+    }
+
+    /// Remove a venue from the `Xfer` allow-list. Admin only.
+    pub fn remove_xfer_venue(ctx: Context<RemoveXferVenue>) -> Result<()> {
+        instructions::rebalance::remove_venue_handler(ctx)
+    }
+
+    // --- Position Instructions ---
+
+    /// Open a borrowing position in a vault (PDA: [b"position", vault, owner]).
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        instructions::position::open_handler(ctx)
+    }
+
+    /// Add collateral to the caller's position.
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>, amount: u64) -> Result<()> {
+        instructions::position::deposit_collateral_handler(ctx, amount)
+    }
+
+    /// Borrow against position collateral, within the limits of the caller's reputation tier.
+    /// Reserve deposits are passed as remaining accounts.
+    pub fn borrow(ctx: Context<ManageDebt>, amount: u64) -> Result<()> {
+        instructions::position::borrow_handler(ctx, amount)
+    }
+
+    /// Repay position debt.
+    pub fn repay(ctx: Context<ManageDebt>, amount: u64) -> Result<()> {
+        instructions::position::repay_handler(ctx, amount)
+    }
+
+    /// Lever the caller's position up to `target_leverage_bps` (collateral over equity,
+    /// 10000 = 1x) in one step: the loan is swapped into collateral and deposited before
+    /// the collateral check. Capped by the vault's `max_leverage`.
+    pub fn leverage(ctx: Context<Leverage>, target_leverage_bps: u64, min_collateral_out: u64) -> Result<()> {
+        instructions::leverage::leverage_handler(ctx, target_leverage_bps, min_collateral_out)
+    }
+
+    /// Sell collateral to repay debt down to `target_leverage_bps`; 10000 closes out all debt.
+    pub fn unwind(ctx: Context<Unwind>, target_leverage_bps: u64, min_amount_out: u64) -> Result<()> {
+        instructions::leverage::unwind_handler(ctx, target_leverage_bps, min_amount_out)
+    }
+
+    /// Authorize a delegate to `deleverage` the caller's position, or clear it with `None`.
+    pub fn set_position_delegate(ctx: Context<SetPositionDelegate>, delegate: Option<Pubkey>) -> Result<()> {
+        instructions::deleverage::set_delegate_handler(ctx, delegate)
+    }
+
+    /// Sell position collateral through the swap adapter to repay debt, without the
+    /// liquidation penalty. Callable by the owner or delegate, or by any keeper once
+    /// health falls below the owner's auto-deleverage trigger; a keeper's swap must return
+    /// the oracle quote less the owner's slippage limit.
+    pub fn deleverage(ctx: Context<Deleverage>, collateral_amount: u64, min_amount_out: u64) -> Result<()> {
+        instructions::deleverage::handler(ctx, collateral_amount, min_amount_out)
+    }
+
+    /// Opt in to keeper deleveraging (PDA: [b"auto_deleverage", position]).
+    pub fn enable_auto_deleverage(ctx: Context<EnableAutoDeleverage>, params: AutoDeleverageParams) -> Result<()> {
+        instructions::deleverage::enable_auto_handler(ctx, params)
+    }
+
+    /// Opt out of keeper deleveraging and reclaim the account's rent.
+    pub fn disable_auto_deleverage(ctx: Context<DisableAutoDeleverage>) -> Result<()> {
+        instructions::deleverage::disable_auto_handler(ctx)
+    }
+
+    /// Place a stop-loss or take-profit order on the caller's position
+    /// (PDA: [b"position_order", position, id]).
+    pub fn place_order(ctx: Context<PlaceOrder>, id: u8, params: OrderParams) -> Result<()> {
+        instructions::order::place_handler(ctx, id, params)
+    }
+
+    /// Cancel an order and reclaim its rent.
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        instructions::order::cancel_handler(ctx)
+    }
+
+    /// Permissionless: run a triggered order, swapping collateral to repay debt within the
+    /// order's slippage limit. The executor earns the order's keeper tip.
+    pub fn execute_order(ctx: Context<ExecuteOrder>) -> Result<()> {
+        instructions::order::execute_handler(ctx)
+    }
+
+    // --- Reserves ---
+
+    /// Accept an additional collateral mint in a vault. Risk manager only.
+    pub fn add_reserve(ctx: Context<AddReserve>, config: ReserveConfig) -> Result<()> {
+        instructions::reserve::add_handler(ctx, config)
+    }
+
+    /// Update a reserve's oracle, limits and deposit cap. Risk manager only.
+    pub fn update_reserve(ctx: Context<UpdateReserve>, config: ReserveConfig) -> Result<()> {
+        instructions::reserve::update_handler(ctx, config)
+    }
+
+    /// Open a position's balance account for a reserve.
+    pub fn open_collateral_deposit(ctx: Context<OpenCollateralDeposit>) -> Result<()> {
+        instructions::reserve::open_deposit_handler(ctx)
+    }
+
+    /// Deposit reserve collateral into a position, within the reserve's deposit cap.
+    pub fn deposit_reserve_collateral(ctx: Context<DepositReserveCollateral>, amount: u64) -> Result<()> {
+        instructions::reserve::deposit_handler(ctx, amount)
+    }
+
+    /// Withdraw reserve collateral from a position. With debt outstanding, the remaining
+    /// collateral must stay within the position's borrow limit.
+    pub fn withdraw_reserve_collateral(ctx: Context<WithdrawReserveCollateral>, amount: u64) -> Result<()> {
+        instructions::reserve::withdraw_handler(ctx, amount)
+    }
+
+    /// Close an empty reserve balance account, freeing its slot on the position.
+    pub fn close_collateral_deposit(ctx: Context<CloseCollateralDeposit>) -> Result<()> {
+        instructions::reserve::close_deposit_handler(ctx)
+    }
+
+    /// Add an e-mode or isolated risk category (PDA: [b"risk_category", vault, id]). Risk manager only.
+    pub fn add_risk_category(
+        ctx: Context<AddRiskCategory>,
+        id: u8,
+        kind: RiskCategoryKind,
+        config: RiskCategoryConfig,
+    ) -> Result<()> {
+        instructions::reserve::add_category_handler(ctx, id, kind, config)
+    }
+
+    /// Update a risk category's limits; its kind is fixed. Risk manager only.
+    pub fn update_risk_category(ctx: Context<UpdateRiskCategory>, config: RiskCategoryConfig) -> Result<()> {
+        instructions::reserve::update_category_handler(ctx, config)
+    }
+
+    /// Opt a position into a risk category, or out of its current one.
+    pub fn set_position_category(ctx: Context<SetPositionCategory>) -> Result<()> {
+        instructions::reserve::set_position_category_handler(ctx)
+    }
+
+    // --- Withdrawal Queue ---
+
+    /// Open the vault's FIFO withdrawal queue (PDA: [b"withdrawal_queue", vault]). Risk manager only.
+    pub fn init_withdrawal_queue(ctx: Context<InitWithdrawalQueue>) -> Result<()> {
+        instructions::withdrawal::init_handler(ctx)
+    }
+
+    /// Lock supply shares for withdrawal and take a ticket at the back of the queue.
+    /// Repayments, deposits and collateral sales that repay debt fill tickets in order.
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, amount: u64) -> Result<()> {
+        instructions::withdrawal::request_handler(ctx, amount)
+    }
+
+    /// Pay out the filled part of a withdrawal ticket; the ticket closes once fully paid.
+    pub fn claim_withdrawal(ctx: Context<ClaimWithdrawal>) -> Result<()> {
+        instructions::withdrawal::claim_handler(ctx)
+    }
+
+    // --- Time-Locked Deposits ---
+
+    /// Enable time-locked deposits for a vault (PDA: [b"lock_config", vault]). Fee manager only.
+    pub fn init_lock_config(ctx: Context<InitLockConfig>, terms: LockTerms) -> Result<()> {
+        instructions::lock::init_config_handler(ctx, terms)
+    }
+
+    /// Update the boost and early-exit penalty for new locks. Fee manager only.
+    pub fn update_lock_config(ctx: Context<UpdateLockConfig>, terms: LockTerms) -> Result<()> {
+        instructions::lock::update_config_handler(ctx, terms)
+    }
+
+    /// Open the caller's lock account (PDA: [b"lock_account", vault, owner]).
+    pub fn open_lock_account(ctx: Context<OpenLockAccount>) -> Result<()> {
+        instructions::lock::open_account_handler(ctx)
+    }
+
+    /// Deposit for a 30, 90 or 180-day term, earning the term's boost on top of the supply rate.
+    pub fn lock_deposit(ctx: Context<LockDeposit>, amount: u64, term: LockTerm) -> Result<()> {
+        instructions::lock::lock_handler(ctx, amount, term)
+    }
+
+    /// Withdraw a matured lock with its boost.
+    pub fn withdraw_lock(ctx: Context<UnlockDeposit>, index: u8) -> Result<()> {
+        instructions::lock::withdraw_handler(ctx, index)
+    }
+
+    /// Exit a lock before maturity, forfeiting the boost and paying the early-exit penalty.
+    pub fn exit_lock_early(ctx: Context<UnlockDeposit>, index: u8) -> Result<()> {
+        instructions::lock::exit_early_handler(ctx, index)
+    }
+
+    // --- Profile Instructions ---
+
+    /// Create the caller's user profile (PDA: [b"profile", owner]).
+    pub fn init_user_profile(ctx: Context<InitUserProfile>) -> Result<()> {
+        instructions::profile::init_handler(ctx)
+    }
+
+    /// Close a profile with no active loans, along with its action history, and refund
+    /// their rent to the owner.
+    pub fn close_user_profile(ctx: Context<CloseUserProfile>) -> Result<()> {
+        instructions::profile::close_handler(ctx)
+    }
+
+    /// Realloc a profile created under an older layout to the current one.
+    pub fn migrate_user_profile(ctx: Context<MigrateUserProfile>) -> Result<()> {
+        instructions::profile::migrate_handler(ctx)
+    }
+
+    /// Open a ring-buffer action history for the caller's profile.
+    pub fn init_action_history(ctx: Context<InitActionHistory>) -> Result<()> {
+        instructions::profile::init_history_handler(ctx)
+    }
+
+    /// Grow the caller's action history to `new_capacity` entries.
+    pub fn resize_action_history(ctx: Context<ResizeActionHistory>, new_capacity: u32) -> Result<()> {
+        instructions::profile::resize_history_handler(ctx, new_capacity)
+    }
+
+    /// Create the singleton reputation model configuration. Upgrade authority only.
+    pub fn init_reputation_config(ctx: Context<InitReputationConfig>, params: ReputationParams) -> Result<()> {
+        instructions::reputation::init_config_handler(ctx, params)
+    }
+
+    /// Replace the reputation weights, decay and tier thresholds. Risk manager only.
+    pub fn update_reputation_config(ctx: Context<UpdateReputationConfig>, params: ReputationParams) -> Result<()> {
+        instructions::reputation::update_config_handler(ctx, params)
+    }
+
+    /// Recompute a profile's reputation score, applying time decay.
+    pub fn refresh_reputation(ctx: Context<RefreshReputation>) -> Result<()> {
+        instructions::reputation::refresh_handler(ctx)
+    }
+
+    /// Promote an eligible Standard profile to Premium.
+    pub fn promote_role(ctx: Context<RefreshReputation>) -> Result<()> {
+        instructions::reputation::promote_role_handler(ctx)
+    }
+
+    // --- Compliance Instructions ---
+
+    /// Create the singleton compliance configuration. Upgrade authority only.
+    pub fn init_compliance_config(ctx: Context<InitComplianceConfig>) -> Result<()> {
+        instructions::compliance::init_compliance_config_handler(ctx)
+    }
+
+    /// Set the transfer amount above which travel-rule data is mandatory. Admin only.
+    pub fn set_travel_rule_threshold(ctx: Context<UpdateComplianceConfig>, threshold: u64) -> Result<()> {
+        instructions::compliance::set_travel_rule_threshold_handler(ctx, threshold)
+    }
+
+    /// Flag a profile for AML review. Restricted to auditor and compliance roles.
+    pub fn flag_account(ctx: Context<UpdateAmlFlag>, case_id: u64, reason_code: u16) -> Result<()> {
+        instructions::compliance::flag_account_handler(ctx, case_id, reason_code)
+    }
+
+    /// Clear a profile's AML flag once the case is resolved.
+    pub fn clear_flag(ctx: Context<UpdateAmlFlag>, case_id: u64, reason_code: u16) -> Result<()> {
+        instructions::compliance::clear_flag_handler(ctx, case_id, reason_code)
     }
 
     // --- Admin & Configuration Instructions ---
 
-    /// Update global protocol configuration parameters.
-    pub fn update_config(ctx: Context<UpdateConfig>, new_risk_factor: u16) -> Result<()> {
-        instructions::admin::update_config_handler(ctx, new_risk_factor)
+    /// Create the protocol config singleton. Must be signed by the program's upgrade
+    /// authority, which becomes admin.
+    pub fn init_protocol_config(
+        ctx: Context<InitProtocolConfig>,
+        guardian: Pubkey,
+        risk_manager: Pubkey,
+        fee_manager: Pubkey,
+        insurance_fund: Pubkey,
+    ) -> Result<()> {
+        instructions::admin::init_protocol_config_handler(ctx, guardian, risk_manager, fee_manager, insurance_fund)
+    }
+
+    /// Reassign the admin, guardian, risk manager and fee manager keys.
+    pub fn update_protocol_roles(ctx: Context<UpdateProtocolRoles>, roles: ProtocolRoles) -> Result<()> {
+        instructions::admin::update_protocol_roles_handler(ctx, roles)
+    }
+
+    /// Point the insurance share of flash loan fees at a new account. Admin only.
+    pub fn set_insurance_fund(ctx: Context<UpdateProtocolRoles>, insurance_fund: Pubkey) -> Result<()> {
+        instructions::admin::set_insurance_fund_handler(ctx, insurance_fund)
+    }
+
+    /// Pause or resume individual operations protocol-wide (see `pause_flags`).
+    /// The guardian may only pause; resuming requires the admin.
+    pub fn set_pause_flags(ctx: Context<SetPauseFlags>, paused: u16) -> Result<()> {
+        instructions::admin::set_pause_flags_handler(ctx, paused)
+    }
+
+    /// Assign a role (e.g. Auditor, Compliance) to a user profile.
+    pub fn assign_user_role(ctx: Context<AssignUserRole>, role: UserRole) -> Result<()> {
+        instructions::admin::assign_user_role_handler(ctx, role)
+    }
+
+    /// Resize a vault created before `total_collateral` and `debt_oracle`, moving the
+    /// positions' `total_collateral` out of `total_assets` and pinning the debt feed.
+    pub fn migrate_vault(ctx: Context<MigrateVault>, total_collateral: u64, debt_oracle: Pubkey) -> Result<()> {
+        instructions::admin::migrate_vault_handler(ctx, total_collateral, debt_oracle)
+    }
+
+    /// Queue a vault parameter change behind the timelock. Fee fields require the
+    /// fee manager; all other fields require the risk manager.
+    pub fn propose_config_change(ctx: Context<ProposeConfigChange>, update: VaultConfigUpdate) -> Result<()> {
+        instructions::admin::propose_config_change_handler(ctx, update)
+    }
+
+    /// Apply a queued change once its ETA has passed and the cooldown since the last change has elapsed.
+    pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+        instructions::admin::execute_config_change_handler(ctx)
+    }
+
+    /// Drop a queued change. Risk manager, fee manager or admin.
+    pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+        instructions::admin::cancel_config_change_handler(ctx)
     }
     
-    /// Emergency freeze a vault in case of exploit detection.
+    /// Propose a new vault authority. Takes effect once the new key accepts.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::authority::propose_handler(ctx, new_authority)
+    }
+
+    /// Accept a pending vault authority transfer. Signed by the new authority.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::authority::accept_handler(ctx)
+    }
+
+    /// Withdraw a pending vault authority transfer. Signed by the current authority.
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+        instructions::authority::cancel_handler(ctx)
+    }
+
+    // --- Admin Multisig ---
+    // Hand the admin, risk and fee roles (and vault authorities) to the
+    // [b"multisig_signer"] PDA to require M-of-N approval for admin actions.
+
+    /// Create the admin multisig. Current protocol admin only.
+    pub fn init_admin_multisig(ctx: Context<InitAdminMultisig>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        instructions::multisig::init_handler(ctx, signers, threshold)
+    }
+
+    /// Replace multisig members and threshold. Only callable through an executed proposal.
+    pub fn set_multisig_members(ctx: Context<SetMultisigMembers>, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        instructions::multisig::set_members_handler(ctx, signers, threshold)
+    }
+
+    /// Propose a flux-core instruction to be signed by the multisig. Members only.
+    pub fn create_admin_proposal(
+        ctx: Context<CreateAdminProposal>,
+        instruction: ProposedInstruction,
+        expires_in: i64,
+    ) -> Result<()> {
+        instructions::multisig::create_proposal_handler(ctx, instruction, expires_in)
+    }
+
+    /// Approve an open proposal. Members only.
+    pub fn approve_admin_proposal(ctx: Context<ApproveAdminProposal>) -> Result<()> {
+        instructions::multisig::approve_proposal_handler(ctx)
+    }
+
+    /// Execute a proposal that has reached the threshold.
+    pub fn execute_admin_proposal<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteAdminProposal<'info>>) -> Result<()> {
+        instructions::multisig::execute_proposal_handler(ctx)
+    }
+
+    // --- Flash Loans ---
+
+    /// Borrow vault liquidity for the duration of the transaction. A `flash_repay`
+    /// for the same vault must follow as a later top-level instruction.
+    pub fn flash_borrow(ctx: Context<FlashBorrow>, amount: u64) -> Result<()> {
+        instructions::flash::borrow_handler(ctx, amount)
+    }
+
+    /// Repay the open flash loan plus `flash_loan_fee_rate`.
+    pub fn flash_repay(ctx: Context<FlashRepay>) -> Result<()> {
+        instructions::flash::repay_handler(ctx)
+    }
+
+    // --- Views ---
+    // Read-only; call through `simulateTransaction` and decode the return data.
+
+    /// Vault utilization, LTV and health factor after accruing interest to now.
+    pub fn get_vault_health(ctx: Context<GetVaultHealth>) -> Result<VaultHealthView> {
+        instructions::views::vault_health_handler(ctx)
+    }
+
+    /// A position's LTV and health factor across all of its collateral.
+    pub fn get_position_health(ctx: Context<GetPositionHealth>) -> Result<PositionHealthView> {
+        instructions::views::position_health_handler(ctx)
+    }
+
+    /// Current utilization, borrow and supply rates.
+    pub fn get_rates(ctx: Context<GetRates>) -> Result<RatesView> {
+        instructions::views::rates_handler(ctx)
+    }
+
+    // --- Risk Engine ---
+
+    /// Open the oracle price-history buffer for a vault. Permissionless.
+    pub fn init_price_history(ctx: Context<InitPriceHistory>) -> Result<()> {
+        instructions::risk::init_price_history_handler(ctx)
+    }
+
+    /// Sample the vault's oracle and move risk parameters toward the level implied by
+    /// realized volatility, within governance bounds. Permissionless crank.
+    pub fn refresh_risk(ctx: Context<RefreshRisk>) -> Result<()> {
+        instructions::risk::refresh_handler(ctx)
+    }
+
+    /// Emergency freeze a vault in case of exploit detection. Guardian or admin only.
     pub fn emergency_freeze(ctx: Context<UpdateConfig>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        // Basic check, in reality would check admin key from config
-        require!(ctx.accounts.authority.key() == vault.authority, crate::errors::FluxError::UnauthorizedAccess);
-        vault.is_frozen = true;
-        msg!("EMERGENCY: Vault {} has been FROZEN.", vault.key());
-        Ok(())
+        instructions::admin::emergency_freeze_handler(ctx)
     }
 
-    /// Unfreeze a vault after audit verification.
+    /// Unfreeze a vault after audit verification. Admin only.
+    /// Vaults frozen by a circuit breaker must be reset by the guardian instead.
     pub fn emergency_unfreeze(ctx: Context<UpdateConfig>) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        require!(ctx.accounts.authority.key() == vault.authority, crate::errors::FluxError::UnauthorizedAccess);
-        vault.is_frozen = false;
-        msg!("Vault {} has been restored to active status.", vault.key());
-        Ok(())
+        instructions::admin::emergency_unfreeze_handler(ctx)
+    }
+
+    /// Configure a vault's outflow and price-move circuit breakers. Risk manager only.
+    pub fn set_circuit_breaker(ctx: Context<UpdateConfig>, params: CircuitBreakerParams) -> Result<()> {
+        instructions::admin::set_circuit_breaker_handler(ctx, params)
+    }
+
+    /// Clear an automatic circuit-breaker freeze. Guardian only.
+    pub fn reset_circuit_breaker(ctx: Context<UpdateConfig>) -> Result<()> {
+        instructions::admin::reset_circuit_breaker_handler(ctx)
     }
 }
 
@@ -75,5 +525,7 @@ pub mod flux_core {
 pub struct UpdateConfig<'info> {
     #[account(mut)]
     pub vault: Account<'info, state::vault::Vault>,
+    #[account(seeds = [b"protocol_config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, state::config::ProtocolConfig>,
     pub authority: Signer<'info>,
 }
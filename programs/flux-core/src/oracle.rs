@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Simplified price feed layout: `price: u64` (LE) followed by an optional
+/// `decimals: u8`. Feeds without the decimals byte are treated as 6 decimals.
+pub const DEFAULT_PRICE_DECIMALS: u8 = 6;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OraclePrice {
+    pub price: u64,
+    pub decimals: u8,
+}
+
+pub fn read_price(feed: &AccountInfo) -> Result<OraclePrice> {
+    let data = feed.try_borrow_data()?;
+    require!(data.len() >= 8, FluxError::StaleOraclePrice);
+
+    let mut price_bytes = [0u8; 8];
+    price_bytes.copy_from_slice(&data[0..8]);
+    let price = u64::from_le_bytes(price_bytes);
+    require!(price > 0, FluxError::StaleOraclePrice);
+
+    let decimals = data.get(8).copied().unwrap_or(DEFAULT_PRICE_DECIMALS);
+    Ok(OraclePrice { price, decimals })
+}
@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::state::reserve::{Reserve, RiskCategory};
+use crate::oracle::OraclePrice;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Health factor of exactly 1.0. Below this a position can be liquidated.
+pub const HEALTH_FACTOR_ONE: u64 = 10000;
+
+/// Loan-to-value limits in basis points, derived from a vault's config or a reserve's.
+/// `risk_factor` is a haircut applied to both, so `refresh_risk` tightens them as volatility rises.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RiskParams {
+    pub max_ltv_bps: u64,               // borrowing limit, from `collateral_ratio`
+    pub liquidation_threshold_bps: u64, // from `min_collateral_ratio`
+}
+
+impl RiskParams {
+    pub fn for_vault(vault: &Vault) -> Result<Self> {
+        Self::with_collateral_ratio(vault, vault.collateral_ratio)
+    }
+
+    /// Limits for a reserve asset, after the vault's `risk_factor` haircut.
+    pub fn for_reserve(vault: &Vault, reserve: &Reserve) -> Self {
+        let haircut = vault.risk_factor as u64;
+        Self {
+            max_ltv_bps: (reserve.ltv_bps as u64).saturating_sub(haircut),
+            liquidation_threshold_bps: (reserve.liquidation_threshold_bps as u64).saturating_sub(haircut),
+        }
+    }
+
+    /// Limits for collateral in a position's risk category, after the same haircut.
+    pub fn for_category(vault: &Vault, category: &RiskCategory) -> Self {
+        let haircut = vault.risk_factor as u64;
+        Self {
+            max_ltv_bps: (category.ltv_bps as u64).saturating_sub(haircut),
+            liquidation_threshold_bps: (category.liquidation_threshold_bps as u64).saturating_sub(haircut),
+        }
+    }
+
+    /// Use `collateral_ratio` in place of the vault's, e.g. after a reputation tier discount.
+    /// The liquidation threshold is never tighter than the resulting borrow limit.
+    pub fn with_collateral_ratio(vault: &Vault, collateral_ratio: u16) -> Result<Self> {
+        let min_ratio = if vault.min_collateral_ratio > 0 { vault.min_collateral_ratio } else { vault.collateral_ratio };
+        let haircut = vault.risk_factor as u64;
+        let max_ltv_bps = ratio_to_ltv(collateral_ratio)?.saturating_sub(haircut);
+        let liquidation_threshold_bps = ratio_to_ltv(min_ratio.min(collateral_ratio))?.saturating_sub(haircut);
+        Ok(Self { max_ltv_bps, liquidation_threshold_bps })
+    }
+}
+
+fn ratio_to_ltv(collateral_ratio: u16) -> Result<u64> {
+    require!(collateral_ratio >= 10000, FluxError::HealthFactorCheckFailed);
+    Ok(100_000_000 / collateral_ratio as u64)
+}
+
+/// Decimals of values returned by `value`, independent of each feed's decimals.
+pub const VALUE_DECIMALS: u32 = 9;
+
+/// Decimals of base collateral and debt amounts. Reserve amounts are normalized to
+/// these before pricing, so every asset's feed quotes the same unit.
+pub const AMOUNT_DECIMALS: u8 = 9;
+
+/// `amount` with `from` decimals re-expressed with `to` decimals, rounded down.
+pub fn scale_amount(amount: u64, from: u8, to: u8) -> Result<u64> {
+    let scaled = if to >= from {
+        10u128.checked_pow((to - from) as u32).and_then(|scale| (amount as u128).checked_mul(scale))
+    } else {
+        10u128.checked_pow((from - to) as u32).map(|scale| amount as u128 / scale)
+    };
+    scaled
+        .and_then(|scaled| u64::try_from(scaled).ok())
+        .ok_or(error!(FluxError::ArithmeticError))
+}
+
+/// `amount` priced at `price`, scaled to `VALUE_DECIMALS`.
+pub fn value(amount: u64, price: OraclePrice) -> Result<u128> {
+    let raw = (amount as u128)
+        .checked_mul(price.price as u128)
+        .ok_or(error!(FluxError::HealthFactorCheckFailed))?;
+    let decimals = price.decimals as u32;
+    let scaled = if decimals <= VALUE_DECIMALS {
+        raw.checked_mul(10u128.pow(VALUE_DECIMALS - decimals))
+    } else {
+        10u128.checked_pow(decimals - VALUE_DECIMALS).map(|scale| raw / scale)
+    };
+    scaled.ok_or(error!(FluxError::HealthFactorCheckFailed))
+}
+
+/// Inverse of `value`: the amount of an asset priced at `price` worth `value`, rounded down.
+pub fn amount_for_value(value: u128, price: OraclePrice) -> Result<u64> {
+    let unit = self::value(1, price)?;
+    require!(unit > 0, FluxError::HealthFactorCheckFailed);
+    u64::try_from(value / unit).map_err(|_| error!(FluxError::HealthFactorCheckFailed))
+}
+
+/// Debt against any number of collateral assets, each weighted by its own limits.
+/// Capacities are value times bps, so comparisons against debt keep full precision.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Portfolio {
+    pub collateral_value: u128,
+    pub debt_value: u128,
+    pub borrow_capacity: u128,      // sum of value * max LTV bps
+    pub liquidation_capacity: u128, // sum of value * liquidation threshold bps
+}
+
+impl Portfolio {
+    pub fn new(debt_amount: u64, debt_price: OraclePrice) -> Result<Self> {
+        Ok(Self { debt_value: value(debt_amount, debt_price)?, ..Default::default() })
+    }
+
+    /// Add a collateral balance, returning its value.
+    pub fn add_collateral(&mut self, amount: u64, price: OraclePrice, params: &RiskParams) -> Result<u128> {
+        let collateral = value(amount, price)?;
+        let weighted = |bps: u64| collateral.checked_mul(bps as u128);
+        self.collateral_value = self.collateral_value.checked_add(collateral)
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?;
+        self.borrow_capacity = weighted(params.max_ltv_bps)
+            .and_then(|w| self.borrow_capacity.checked_add(w))
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?;
+        self.liquidation_capacity = weighted(params.liquidation_threshold_bps)
+            .and_then(|w| self.liquidation_capacity.checked_add(w))
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?;
+        Ok(collateral)
+    }
+
+    /// Debt over collateral in bps. `u64::MAX` with debt but no collateral.
+    pub fn ltv_bps(&self) -> Result<u64> {
+        if self.debt_value == 0 {
+            return Ok(0);
+        }
+        if self.collateral_value == 0 {
+            return Ok(u64::MAX);
+        }
+        let ltv = self.debt_value
+            .checked_mul(10000)
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?
+            / self.collateral_value;
+        Ok(ltv.min(u64::MAX as u128) as u64)
+    }
+
+    /// Threshold-weighted collateral over debt, scaled so `HEALTH_FACTOR_ONE` is 1.0.
+    /// `u64::MAX` without debt.
+    pub fn health_factor(&self) -> Result<u64> {
+        if self.debt_value == 0 {
+            return Ok(u64::MAX);
+        }
+        let hf = self.liquidation_capacity / self.debt_value;
+        Ok(hf.min(u64::MAX as u128) as u64)
+    }
+
+    /// Collateral over equity (collateral less debt) in bps, so 10000 is unlevered.
+    /// `u64::MAX` once debt reaches the collateral value.
+    pub fn leverage_bps(&self) -> Result<u64> {
+        let equity = self.collateral_value.saturating_sub(self.debt_value);
+        if equity == 0 {
+            return Ok(u64::MAX);
+        }
+        let leverage = self.collateral_value
+            .checked_mul(10000)
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?
+            / equity;
+        Ok(leverage.min(u64::MAX as u128) as u64)
+    }
+
+    pub fn within_borrow_limit(&self) -> Result<bool> {
+        let required = self.debt_value
+            .checked_mul(10000)
+            .ok_or(error!(FluxError::HealthFactorCheckFailed))?;
+        Ok(required <= self.borrow_capacity)
+    }
+
+    pub fn is_liquidatable(&self) -> Result<bool> {
+        Ok(self.health_factor()? < HEALTH_FACTOR_ONE)
+    }
+}
+
+/// Vault-wide view: `total_collateral` against `total_liabilities` as debt.
+pub fn vault_portfolio(vault: &Vault, collateral_price: OraclePrice, debt_price: OraclePrice) -> Result<Portfolio> {
+    let mut portfolio = Portfolio::new(vault.total_liabilities, debt_price)?;
+    portfolio.add_collateral(vault.total_collateral, collateral_price, &RiskParams::for_vault(vault)?)?;
+    Ok(portfolio)
+}
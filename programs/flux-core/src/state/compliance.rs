@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+use crate::state::user_profile::UserProfile;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// One entry of a profile's append-only compliance case log.
+/// Seeds: [b"case", user_profile, sequence]
+#[account]
+pub struct CaseRecord {
+    pub subject: Pubkey,   // flagged UserProfile
+    pub sequence: u32,     // position in the subject's case log
+    pub case_id: u64,      // external case reference from compliance tooling
+    pub action: CaseAction,
+    pub reason_code: u16,
+    pub officer: Pubkey,   // auditor / compliance signer
+    pub timestamp: i64,
+    pub slot: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum CaseAction {
+    Flag,
+    Clear,
+}
+
+/// Singleton holding protocol-wide compliance parameters. Updated by the protocol admin.
+/// Seeds: [b"compliance_config"]
+#[account]
+pub struct ComplianceConfig {
+    pub travel_rule_threshold: u64, // lamports; larger transfers need travel-rule data
+    pub bump: u8,
+}
+
+/// Reference to the off-chain travel-rule payload exchanged with the counterparty VASP.
+/// Seeds: [b"transfer_record", user_profile, nonce], the nonce counting the profile's records
+#[account]
+pub struct TransferRecord {
+    pub user_profile: Pubkey,
+    pub nonce: u32,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub payload_hash: [u8; 32],      // hash of the encrypted originator/beneficiary payload
+    pub counterparty_vasp: [u8; 32], // beneficiary VASP identifier
+    pub timestamp: i64,
+    pub slot: u64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TravelRuleInfo {
+    pub payload_hash: [u8; 32],
+    pub counterparty_vasp: [u8; 32],
+}
+
+impl CaseRecord {
+    pub const LEN: usize = 8 + 32 + 4 + 8 + 1 + 2 + 32 + 8 + 8 + 1;
+}
+
+impl ComplianceConfig {
+    pub const LEN: usize = 8 + 8 + 1;
+    
+    // 1000 SOL until governance sets a jurisdiction-specific value
+    pub const DEFAULT_TRAVEL_RULE_THRESHOLD: u64 = 1_000_000_000_000;
+    // Unverified profiles move at most 10 SOL per transfer
+    pub const UNVERIFIED_TRANSFER_LIMIT: u64 = 10_000_000_000;
+    // Seconds a profile must be idle before each transfer
+    pub const TRANSFER_COOLDOWN: i64 = 30;
+
+    pub fn requires_travel_rule(&self, amount: u64) -> bool {
+        amount > self.travel_rule_threshold
+    }
+}
+
+impl TransferRecord {
+    pub const LEN: usize = 8 + 32 + 4 + 32 + 32 + 8 + 32 + 32 + 8 + 8 + 1;
+
+    /// Nonce seed of the profile's next record. A missing profile never gets a record.
+    pub fn next_nonce(user_profile: Option<&AccountLoader<UserProfile>>) -> Result<[u8; 4]> {
+        Ok(match user_profile {
+            Some(profile) => profile.load()?.transfer_records.to_le_bytes(),
+            None => [0; 4],
+        })
+    }
+}
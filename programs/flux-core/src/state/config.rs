@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Singleton holding the protocol's privileged roles.
+/// Seeds: [b"protocol_config"]
+#[account]
+pub struct ProtocolConfig {
+    pub admin: Pubkey,        // role management, unfreeze
+    pub guardian: Pubkey,     // may only freeze / pause
+    pub risk_manager: Pubkey, // vault risk parameters
+    pub fee_manager: Pubkey,  // vault fee rates
+    pub bump: u8,
+    pub paused: u16, // bitmask of `pause_flags`
+    pub insurance_fund: Pubkey, // receives the insurance share of flash loan fees
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 94],
+}
+
+/// Operations the guardian can pause independently.
+pub mod pause_flags {
+    pub const DEPOSITS: u16 = 1 << 0;
+    pub const WITHDRAWALS: u16 = 1 << 1;
+    pub const BORROWS: u16 = 1 << 2;
+    pub const LIQUIDATIONS: u16 = 1 << 3;
+    pub const TRANSFERS: u16 = 1 << 4;
+    pub const UNLOADS: u16 = 1 << 5;
+
+    pub const ALL: u16 = DEPOSITS | WITHDRAWALS | BORROWS | LIQUIDATIONS | TRANSFERS | UNLOADS;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ProtocolRoles {
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
+    pub risk_manager: Pubkey,
+    pub fee_manager: Pubkey,
+}
+
+impl ProtocolConfig {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 1 + 2 + 32 + 94;
+
+    pub fn is_paused(&self, flag: u16) -> bool {
+        self.paused & flag != 0
+    }
+
+    pub fn require_not_paused(&self, flag: u16) -> Result<()> {
+        require!(!self.is_paused(flag), crate::errors::FluxError::ProtocolPaused);
+        Ok(())
+    }
+
+    pub fn is_admin(&self, key: &Pubkey) -> bool {
+        self.admin == *key
+    }
+
+    /// Freezing is an emergency action, so the admin may act if the guardian is unavailable.
+    pub fn can_freeze(&self, key: &Pubkey) -> bool {
+        self.guardian == *key || self.admin == *key
+    }
+
+    pub fn is_risk_manager(&self, key: &Pubkey) -> bool {
+        self.risk_manager == *key
+    }
+
+    pub fn is_fee_manager(&self, key: &Pubkey) -> bool {
+        self.fee_manager == *key
+    }
+
+    pub fn set_roles(&mut self, roles: &ProtocolRoles) {
+        self.admin = roles.admin;
+        self.guardian = roles.guardian;
+        self.risk_manager = roles.risk_manager;
+        self.fee_manager = roles.fee_manager;
+    }
+
+    pub fn roles(&self) -> ProtocolRoles {
+        ProtocolRoles {
+            admin: self.admin,
+            guardian: self.guardian,
+            risk_manager: self.risk_manager,
+            fee_manager: self.fee_manager,
+        }
+    }
+}
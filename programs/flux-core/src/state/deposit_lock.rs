@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Terms for a vault's time-locked deposits. Boosts are paid from `boost_budget`,
+/// which the vault's management fee fills as it accrues.
+/// Seeds: [b"lock_config", vault]
+#[account]
+pub struct LockConfig {
+    pub vault: Pubkey,
+    pub boost_bps: [u16; 3],        // added to the supply APR, indexed by `LockTerm`
+    pub early_exit_penalty_bps: u16, // of principal, sent to the incinerator
+    pub boost_budget: u64,          // management fees not yet promised to a lock
+    pub boost_reserved: u64,        // full-term boosts promised to open locks
+    pub total_locked: u64,
+    pub bump: u8,
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct LockTerms {
+    pub boost_bps: [u16; 3],
+    pub early_exit_penalty_bps: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum LockTerm {
+    Days30,
+    Days90,
+    Days180,
+}
+
+/// A user's time-locked deposits in one vault.
+/// Seeds: [b"lock_account", vault, owner]
+#[account]
+pub struct LockAccount {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub locks: Vec<DepositLock>,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DepositLock {
+    pub amount: u64,
+    pub term: LockTerm,
+    pub boost_bps: u16, // fixed when the lock opens
+    pub boost: u64,     // reserved from the budget, paid at maturity
+    pub locked_at: i64,
+    pub unlocks_at: i64,
+}
+
+impl LockTerm {
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn duration(self) -> i64 {
+        const DAY: i64 = 24 * 60 * 60;
+        match self {
+            LockTerm::Days30 => 30 * DAY,
+            LockTerm::Days90 => 90 * DAY,
+            LockTerm::Days180 => 180 * DAY,
+        }
+    }
+}
+
+impl LockTerms {
+    pub const MAX_BOOST_BPS: u16 = 2000;
+    pub const MAX_EARLY_EXIT_PENALTY_BPS: u16 = 2000;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.boost_bps.iter().all(|bps| *bps <= Self::MAX_BOOST_BPS),
+            FluxError::InvalidLockConfig
+        );
+        require!(
+            self.early_exit_penalty_bps <= Self::MAX_EARLY_EXIT_PENALTY_BPS,
+            FluxError::InvalidLockConfig
+        );
+        Ok(())
+    }
+}
+
+impl LockConfig {
+    pub const LEN: usize = 8 + 32 + 2 * 3 + 2 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn terms(&self) -> LockTerms {
+        LockTerms {
+            boost_bps: self.boost_bps,
+            early_exit_penalty_bps: self.early_exit_penalty_bps,
+        }
+    }
+
+    pub fn apply_terms(&mut self, terms: &LockTerms) {
+        self.boost_bps = terms.boost_bps;
+        self.early_exit_penalty_bps = terms.early_exit_penalty_bps;
+    }
+
+    /// Move the management fee accrued on `total_assets` since `last_fee_collection` into the
+    /// boost budget. Locks are the fee's only consumer; it is limited to idle liquidity so the
+    /// vault stays solvent.
+    pub fn collect_management_fee(&mut self, vault: &mut Vault, now: i64) -> Result<u64> {
+        let elapsed = now.saturating_sub(vault.last_fee_collection);
+        if vault.last_fee_collection == 0 || elapsed <= 0 {
+            vault.last_fee_collection = vault.last_fee_collection.max(now);
+            return Ok(0);
+        }
+
+        let fee = (vault.total_assets as u128)
+            .checked_mul(vault.management_fee_rate as u128).ok_or(error!(FluxError::ArithmeticError))?
+            .checked_mul(elapsed as u128).ok_or(error!(FluxError::ArithmeticError))?
+            / (10000 * Vault::SECONDS_PER_YEAR);
+        let fee = (fee as u64).min(vault.total_assets.saturating_sub(vault.total_liabilities));
+        vault.total_assets -= fee;
+        vault.last_fee_collection = now;
+        self.boost_budget = self.boost_budget.checked_add(fee).ok_or(error!(FluxError::ArithmeticError))?;
+        Ok(fee)
+    }
+
+    /// Boost earned on `amount` over the full term, set aside from the budget.
+    pub fn reserve_boost(&mut self, amount: u64, term: LockTerm) -> Result<(u16, u64)> {
+        let boost_bps = self.boost_bps[term.index()];
+        let boost = (amount as u128 * boost_bps as u128 * term.duration() as u128
+            / (10000 * Vault::SECONDS_PER_YEAR)) as u64;
+        require!(boost <= self.boost_budget, FluxError::InsufficientBoostBudget);
+        self.boost_budget -= boost;
+        self.boost_reserved += boost;
+        Ok((boost_bps, boost))
+    }
+
+    pub fn early_exit_penalty(&self, amount: u64) -> u64 {
+        (amount as u128 * self.early_exit_penalty_bps as u128 / 10000) as u64
+    }
+}
+
+impl LockAccount {
+    pub const MAX_LOCKS: usize = 8;
+    pub const LEN: usize = 8 + 32 + 32 + (4 + DepositLock::LEN * Self::MAX_LOCKS) + 1;
+
+    pub fn lock(&self, index: u8) -> Result<DepositLock> {
+        self.locks.get(index as usize).copied().ok_or(error!(FluxError::InvalidLockIndex))
+    }
+}
+
+impl DepositLock {
+    pub const LEN: usize = 8 + 1 + 2 + 8 + 8 + 8;
+
+    pub fn is_matured(&self, now: i64) -> bool {
+        now >= self.unlocks_at
+    }
+}
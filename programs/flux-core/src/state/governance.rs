@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::VaultConfigUpdate;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// A queued vault parameter change awaiting its timelock.
+/// Seeds: [b"pending_config", vault]
+#[account]
+pub struct PendingConfigChange {
+    pub vault: Pubkey,
+    pub proposer: Pubkey,
+    pub update: VaultConfigUpdate,
+    pub proposed_at: i64,
+    pub eta: i64, // earliest execution time
+    pub bump: u8,
+}
+
+impl PendingConfigChange {
+    pub const LEN: usize = 8 + 32 + 32 + VaultConfigUpdate::LEN + 8 + 8 + 1;
+}
+
+/// A proposed vault authority handover, completed when `new_authority` accepts.
+/// Seeds: [b"pending_authority", vault]
+#[account]
+pub struct PendingAuthority {
+    pub vault: Pubkey,
+    pub proposer: Pubkey, // vault authority at proposal time, receives the rent back
+    pub new_authority: Pubkey,
+    pub proposed_at: i64,
+    pub bump: u8,
+}
+
+impl PendingAuthority {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+}
@@ -1,3 +1,16 @@
 pub mod vault;
 pub mod user_profile;
 
+pub mod compliance;
+pub mod position;
+pub mod reputation;
+pub mod config;
+pub mod governance;
+pub mod multisig;
+pub mod price_history;
+pub mod reserve;
+pub mod withdrawal_queue;
+pub mod supply;
+pub mod deposit_lock;
+pub mod order;
+pub mod pipeline;
@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// M-of-N admin multisig. Protocol roles are handed to the system-owned signer PDA
+/// ([b"multisig_signer"]), which only signs flux-core instructions from approved proposals.
+/// Seeds: [b"admin_multisig"]
+#[account]
+pub struct AdminMultisig {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub proposal_count: u64, // nonce of the next proposal
+    pub config_version: u32, // bumped on membership change; invalidates open proposals
+    pub bump: u8,
+    pub signer_bump: u8,
+}
+
+/// A flux-core instruction awaiting approvals.
+/// Seeds: [b"admin_proposal", nonce]
+#[account]
+pub struct AdminProposal {
+    pub nonce: u64,
+    pub proposer: Pubkey,
+    pub instruction: ProposedInstruction,
+    pub approvals: u16, // bitmask over `AdminMultisig::signers`
+    pub config_version: u32,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub executed: bool,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct ProposedInstruction {
+    pub accounts: Vec<ProposedAccountMeta>,
+    pub data: Vec<u8>,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ProposedAccountMeta {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl AdminMultisig {
+    pub const MAX_SIGNERS: usize = 10;
+    pub const LEN: usize = 8 + (4 + 32 * Self::MAX_SIGNERS) + 1 + 8 + 4 + 1 + 1;
+    pub const MIN_EXPIRY: i64 = 60 * 60;
+    pub const MAX_EXPIRY: i64 = 14 * 24 * 60 * 60;
+
+    pub fn signer_index(&self, key: &Pubkey) -> Option<usize> {
+        self.signers.iter().position(|s| s == key)
+    }
+
+    pub fn validate_members(signers: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !signers.is_empty() && signers.len() <= Self::MAX_SIGNERS,
+            crate::errors::FluxError::InvalidMultisigConfig
+        );
+        require!(
+            threshold >= 1 && threshold as usize <= signers.len(),
+            crate::errors::FluxError::InvalidMultisigConfig
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(!signers[..i].contains(signer), crate::errors::FluxError::InvalidMultisigConfig);
+        }
+        Ok(())
+    }
+}
+
+impl AdminProposal {
+    pub fn space(instruction: &ProposedInstruction) -> usize {
+        8 + 8 + 32
+            + (4 + instruction.accounts.len() * (32 + 1 + 1)) + (4 + instruction.data.len())
+            + 2 + 4 + 8 + 8 + 1 + 1
+    }
+
+    pub fn approval_count(&self) -> u32 {
+        self.approvals.count_ones()
+    }
+
+    /// Build the flux-core instruction; only the multisig signer PDA is marked as signer.
+    pub fn to_instruction(&self, multisig_signer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: crate::ID,
+            accounts: self.instruction.accounts.iter().map(|meta| AccountMeta {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer && meta.pubkey == *multisig_signer,
+                is_writable: meta.is_writable,
+            }).collect(),
+            data: self.instruction.data.clone(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Conditional exit on a position, run by any keeper through `execute_order` once the
+/// vault's collateral oracle crosses `trigger_price`. Closed to the owner when executed.
+/// Seeds: [b"position_order", position, id]
+#[account]
+pub struct PositionOrder {
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub id: u8,
+    pub kind: OrderKind,
+    pub trigger_price: u64, // in the collateral feed's decimals
+    pub action: OrderAction,
+    pub max_slippage_bps: u16,
+    pub keeper_tip: u64, // base collateral paid to the executor
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OrderKind {
+    /// Fires when the collateral price falls to or below the trigger.
+    StopLoss,
+    /// Fires when the collateral price rises to or above the trigger.
+    TakeProfit,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OrderAction {
+    /// Sell collateral to repay up to `amount` of debt.
+    Repay { amount: u64 },
+    /// Sell collateral to repay all debt.
+    Close,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OrderParams {
+    pub kind: OrderKind,
+    pub trigger_price: u64,
+    pub action: OrderAction,
+    pub max_slippage_bps: u16,
+    pub keeper_tip: u64,
+}
+
+impl PositionOrder {
+    pub const LEN: usize = 8 + 32 + 32 + 1 + 1 + 8 + (1 + 8) + 2 + 8 + 8 + 1;
+
+    pub fn is_triggered(&self, price: u64) -> bool {
+        match self.kind {
+            OrderKind::StopLoss => price <= self.trigger_price,
+            OrderKind::TakeProfit => price >= self.trigger_price,
+        }
+    }
+
+    /// Debt the order repays out of `debt`.
+    pub fn repay_target(&self, debt: u64) -> u64 {
+        match self.action {
+            OrderAction::Repay { amount } => amount.min(debt),
+            OrderAction::Close => debt,
+        }
+    }
+}
+
+impl OrderParams {
+    pub const MAX_SLIPPAGE_BPS: u16 = 1000;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.trigger_price > 0, FluxError::InvalidOrder);
+        require!(self.max_slippage_bps <= Self::MAX_SLIPPAGE_BPS, FluxError::InvalidOrder);
+        if let OrderAction::Repay { amount } = self.action {
+            require!(amount > 0, FluxError::InvalidOrder);
+        }
+        Ok(())
+    }
+}
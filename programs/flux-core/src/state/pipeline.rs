@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use crate::state::compliance::TravelRuleInfo;
+use crate::errors::FluxError;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// One stage of a `rebalance` pipeline: the Fetch -> Liquidate -> Unload -> Xfer flow
+/// run inside a single instruction, in any order.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum PipelineStep {
+    /// Deposit `amount` from the authority into the vault.
+    Fetch { amount: u64 },
+    /// Liquidate the next position in the remaining accounts, seizing base collateral.
+    Liquidate,
+    /// Connect to the Flux Incinerator and burn unloaded assets, as `unload_vault` does.
+    Unload,
+    /// Move `amount` of idle liquidity to an allowed venue, under the `xfer_funds` checks.
+    Xfer { amount: u64, travel_rule: Option<TravelRuleInfo> },
+}
+
+/// A recipient the admin allows `Xfer` steps to send vault liquidity to.
+/// Seeds: [b"xfer_venue", venue]
+#[account]
+pub struct XferVenue {
+    pub venue: Pubkey,
+    pub added_by: Pubkey,
+    pub added_at: i64,
+    pub bump: u8,
+}
+
+impl PipelineStep {
+    pub const MAX_STEPS: usize = 8;
+
+    /// Steps are bounded, amounts non-zero, and at most one `Xfer` runs per pipeline, since
+    /// transfers are rate limited per sender.
+    pub fn validate(steps: &[PipelineStep]) -> Result<()> {
+        require!(!steps.is_empty() && steps.len() <= Self::MAX_STEPS, FluxError::InvalidPipeline);
+        for step in steps {
+            if let PipelineStep::Fetch { amount } | PipelineStep::Xfer { amount, .. } = step {
+                require!(*amount > 0, FluxError::InvalidPipeline);
+            }
+        }
+        let transfers = steps.iter().filter(|step| matches!(step, PipelineStep::Xfer { .. })).count();
+        require!(transfers <= 1, FluxError::InvalidPipeline);
+        Ok(())
+    }
+
+    /// Travel-rule data of the pipeline's `Xfer` step, if it carries any.
+    pub fn travel_rule(steps: &[PipelineStep]) -> Option<TravelRuleInfo> {
+        steps.iter().find_map(|step| match step {
+            PipelineStep::Xfer { travel_rule, .. } => *travel_rule,
+            _ => None,
+        })
+    }
+}
+
+impl XferVenue {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// A borrower's collateral and debt within one vault.
+/// Seeds: [b"position", vault, owner]
+#[account]
+pub struct Position {
+    pub owner: Pubkey,
+    pub vault: Pubkey,
+    pub collateral_amount: u64, // base collateral; reserve assets are held in `CollateralDeposit`
+    pub debt_amount: u64,
+    pub last_update: i64,
+    pub bump: u8,
+    pub reserve_count: u8, // open `CollateralDeposit` accounts
+    pub risk_category: u8, // opted-in `RiskCategory` id, 0 = none
+    pub delegate: Pubkey,  // may `deleverage` for the owner; default = none
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 30],
+}
+
+/// Owner opt-in letting any keeper `deleverage` the position once its health factor
+/// falls below `trigger_health_factor`, for `keeper_fee` of base collateral per call. Keeper
+/// swaps must return the oracle quote less `max_slippage_bps`.
+/// Seeds: [b"auto_deleverage", position]
+#[account]
+pub struct AutoDeleverage {
+    pub position: Pubkey,
+    pub trigger_health_factor: u64, // `HEALTH_FACTOR_ONE` = 1.0
+    pub max_repay_bps: u16,         // of debt, per call
+    pub keeper_fee: u64,
+    pub max_slippage_bps: u16,      // of the oracle quote, per keeper swap
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AutoDeleverageParams {
+    pub trigger_health_factor: u64,
+    pub max_repay_bps: u16,
+    pub keeper_fee: u64,
+    pub max_slippage_bps: u16,
+}
+
+impl Position {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 1 + 1 + 1 + 32 + 30;
+    pub const MAX_RESERVES: u8 = 8;
+
+    /// The owner or their delegate.
+    pub fn can_manage(&self, key: &Pubkey) -> bool {
+        self.owner == *key || (self.delegate != Pubkey::default() && self.delegate == *key)
+    }
+}
+
+impl AutoDeleverage {
+    pub const LEN: usize = 8 + 32 + 8 + 2 + 8 + 2 + 1;
+    /// Keepers may act at most this far above the liquidation line (3.0).
+    pub const MAX_TRIGGER_HEALTH_FACTOR: u64 = 3 * crate::risk::HEALTH_FACTOR_ONE;
+    pub const MAX_SLIPPAGE_BPS: u16 = 1000;
+}
+
+impl AutoDeleverageParams {
+    pub fn validate(&self) -> Result<()> {
+        // Below 1.0 liquidators act first, so the trigger would never fire
+        require!(
+            self.trigger_health_factor > crate::risk::HEALTH_FACTOR_ONE
+                && self.trigger_health_factor <= AutoDeleverage::MAX_TRIGGER_HEALTH_FACTOR,
+            crate::errors::FluxError::InvalidAutoDeleverage
+        );
+        require!(
+            self.max_repay_bps > 0 && self.max_repay_bps <= 10000,
+            crate::errors::FluxError::InvalidAutoDeleverage
+        );
+        require!(
+            self.max_slippage_bps <= AutoDeleverage::MAX_SLIPPAGE_BPS,
+            crate::errors::FluxError::InvalidAutoDeleverage
+        );
+        Ok(())
+    }
+}
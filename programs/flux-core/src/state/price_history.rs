@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use std::mem::size_of;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Ring buffer of oracle samples for a vault's collateral feed, appended by `refresh_risk`.
+/// Seeds: [b"price_history", vault]
+#[account(zero_copy)]
+pub struct PriceHistory {
+    pub vault: Pubkey,
+    pub oracle: Pubkey,
+    pub samples: [PriceSample; 64],
+    pub head: u32, // Next write slot
+    pub len: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+}
+
+#[zero_copy]
+#[derive(Debug, PartialEq, Default)]
+pub struct PriceSample {
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+impl PriceHistory {
+    pub const LEN: usize = 8 + size_of::<PriceHistory>();
+    pub const CAPACITY: u32 = 64;
+    /// Period realized volatility is quoted over.
+    pub const VOLATILITY_PERIOD: i64 = 60 * 60;
+
+    pub fn push(&mut self, price: u64, timestamp: i64) {
+        self.samples[self.head as usize] = PriceSample { price, timestamp };
+        self.head = (self.head + 1) % Self::CAPACITY;
+        self.len = self.len.saturating_add(1).min(Self::CAPACITY);
+    }
+
+    pub fn last(&self) -> Option<PriceSample> {
+        if self.len == 0 {
+            return None;
+        }
+        Some(self.samples[((self.head + Self::CAPACITY - 1) % Self::CAPACITY) as usize])
+    }
+
+    /// Realized volatility in bps per `VOLATILITY_PERIOD`: squared returns are summed and
+    /// divided by the time they span, so the estimate does not depend on how often the
+    /// crank runs. `None` until two samples exist.
+    pub fn realized_volatility(&self) -> Option<u16> {
+        if self.len < 2 {
+            return None;
+        }
+        let oldest = (self.head + Self::CAPACITY - self.len) % Self::CAPACITY;
+        let first = self.samples[oldest as usize];
+        let mut sum_sq: u128 = 0;
+        let mut prev = first;
+        for i in 1..self.len {
+            let sample = self.samples[((oldest + i) % Self::CAPACITY) as usize];
+            let ret_bps = (sample.price.abs_diff(prev.price) as u128 * 10000 / prev.price.max(1) as u128)
+                .min(u16::MAX as u128);
+            sum_sq += ret_bps * ret_bps;
+            prev = sample;
+        }
+        let elapsed = prev.timestamp.saturating_sub(first.timestamp).max(1) as u128;
+        let variance = sum_sq * Self::VOLATILITY_PERIOD as u128 / elapsed;
+        Some(isqrt(variance).min(u16::MAX as u128) as u16)
+    }
+}
+
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(samples: &[(u64, i64)]) -> PriceHistory {
+        let mut history = PriceHistory {
+            vault: Pubkey::default(),
+            oracle: Pubkey::default(),
+            samples: [PriceSample::default(); 64],
+            head: 0,
+            len: 0,
+            bump: 0,
+            _padding: [0; 7],
+        };
+        for &(price, timestamp) in samples {
+            history.push(price, timestamp);
+        }
+        history
+    }
+
+    /// `count` samples `interval` seconds apart, alternating between 100 and 101.
+    fn alternating(count: usize, interval: i64) -> Vec<(u64, i64)> {
+        (0..count).map(|i| (100 + (i % 2) as u64, i as i64 * interval)).collect()
+    }
+
+    #[test]
+    fn isqrt_floors() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(3), 1);
+        assert_eq!(isqrt(4), 2);
+        assert_eq!(isqrt(99), 9);
+        assert_eq!(isqrt(10000), 100);
+        assert_eq!(isqrt(u64::MAX as u128 * u64::MAX as u128), u64::MAX as u128);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn volatility_needs_two_samples() {
+        assert_eq!(history(&[]).realized_volatility(), None);
+        assert_eq!(history(&[(100, 0)]).realized_volatility(), None);
+        assert_eq!(history(&[(100, 0), (100, 60)]).realized_volatility(), Some(0));
+    }
+
+    #[test]
+    fn volatility_is_scaled_by_elapsed_time() {
+        // 1% moves once per period read as 100 bps
+        assert_eq!(history(&alternating(10, 3600)).realized_volatility(), Some(99));
+        // The same moves every minute are sqrt(60) times as volatile per period
+        assert_eq!(history(&alternating(10, 60)).realized_volatility(), Some(771));
+        // Sampling the same path more sparsely does not make it look calmer
+        let minutes = history(&[(100, 0), (110, 60)]).realized_volatility().unwrap();
+        let hour = history(&[(100, 0), (110, 60), (110, 3600)]).realized_volatility().unwrap();
+        assert_eq!((minutes, hour), (7745, 1000));
+    }
+
+    #[test]
+    fn volatility_reads_only_the_retained_window() {
+        // A crash that has rotated out of the ring no longer counts
+        let mut samples = vec![(1000, 0), (100, 60)];
+        samples.extend((2..66).map(|i| (100, i * 60)));
+        let history = history(&samples);
+        assert_eq!(history.len, PriceHistory::CAPACITY);
+        assert_eq!(history.realized_volatility(), Some(0));
+        assert_eq!(history.last(), Some(PriceSample { price: 100, timestamp: 65 * 60 }));
+    }
+}
@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+use crate::state::user_profile::UserProfile;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// Singleton holding the reputation model parameters. Updated by the risk manager.
+/// Seeds: [b"reputation_config"]
+#[account]
+pub struct ReputationConfig {
+    pub params: ReputationParams,
+    pub last_update: i64,
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReputationParams {
+    pub weights: ReputationWeights,
+    pub baseline_score: u8,      // score of a new profile, and the floor time decay converges to
+    pub decay_bps_per_day: u16,  // share of the score above baseline lost per idle day
+    pub premium_min_score: u8,
+    pub premium_min_volume: u64, // lifetime borrowed, lamports
+    pub tiers: [ReputationTier; 3], // ascending by min_score
+}
+
+/// Weights are in hundredths of a score point.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReputationWeights {
+    pub repayment: u16,   // per completed repayment
+    pub liquidation: u16, // deducted per liquidation
+    pub account_age: u16, // per 30 days since profile creation
+    pub volume: u16,      // per 1000 SOL repaid
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReputationTier {
+    pub min_score: u8,
+    pub borrow_limit: u64,                  // max outstanding debt per position
+    pub collateral_ratio_discount_bps: u16, // subtracted from the vault's collateral_ratio
+}
+
+impl ReputationConfig {
+    pub const LEN: usize = 8 + ReputationParams::LEN + 8 + 1;
+}
+
+impl ReputationParams {
+    pub const LEN: usize = (2 * 4) + 1 + 2 + 1 + 8 + (3 * ReputationTier::LEN);
+    pub const MAX_SCORE: u64 = 100;
+    pub const SECONDS_PER_DAY: i64 = 86_400;
+    pub const AGE_PERIOD: i64 = 30 * Self::SECONDS_PER_DAY;
+    pub const VOLUME_UNIT: u64 = 1_000_000_000_000; // 1000 SOL
+
+    // Counters are capped so no single factor can dominate the score
+    pub const MAX_COUNTED_REPAYMENTS: u64 = 100;
+    pub const MAX_COUNTED_AGE_PERIODS: u64 = 24;
+    pub const MAX_COUNTED_VOLUME_UNITS: u64 = 100;
+
+    pub fn validate(&self) -> Result<()> {
+        require!((self.baseline_score as u64) <= Self::MAX_SCORE, FluxError::InvalidReputationConfig);
+        require!((self.premium_min_score as u64) <= Self::MAX_SCORE, FluxError::InvalidReputationConfig);
+        require!(self.decay_bps_per_day <= 10000, FluxError::InvalidReputationConfig);
+        for pair in self.tiers.windows(2) {
+            require!(pair[0].min_score < pair[1].min_score, FluxError::InvalidReputationConfig);
+            require!(pair[0].borrow_limit <= pair[1].borrow_limit, FluxError::InvalidReputationConfig);
+        }
+        for tier in self.tiers.iter() {
+            require!(tier.collateral_ratio_discount_bps <= 5000, FluxError::InvalidReputationConfig);
+        }
+        Ok(())
+    }
+
+    /// Score (0-100) for `profile` at `now`, before it is written back.
+    pub fn compute_score(&self, profile: &UserProfile, now: i64) -> u8 {
+        let w = &self.weights;
+        let age_periods = if profile.created_at > 0 {
+            (now.saturating_sub(profile.created_at) / Self::AGE_PERIOD) as u64
+        } else {
+            0
+        };
+        let volume_units = profile.total_repaid_lifetime / Self::VOLUME_UNIT;
+
+        let positive = (self.baseline_score as u64 * 100)
+            .saturating_add(w.repayment as u64 * (profile.repayment_count as u64).min(Self::MAX_COUNTED_REPAYMENTS))
+            .saturating_add(w.account_age as u64 * age_periods.min(Self::MAX_COUNTED_AGE_PERIODS))
+            .saturating_add(w.volume as u64 * volume_units.min(Self::MAX_COUNTED_VOLUME_UNITS));
+        let negative = w.liquidation as u64 * profile.liquidation_count as u64;
+        let raw = positive.saturating_sub(negative).min(Self::MAX_SCORE * 100);
+
+        // Time decay: idle days erode the part of the score above baseline
+        let baseline = (self.baseline_score as u64 * 100).min(raw);
+        let idle_days = (now.saturating_sub(profile.last_active_timestamp) / Self::SECONDS_PER_DAY).max(0) as u64;
+        let decay_bps = (self.decay_bps_per_day as u64).saturating_mul(idle_days).min(10000);
+        let decayed = baseline + (raw - baseline) * (10000 - decay_bps) / 10000;
+
+        (decayed / 100) as u8
+    }
+
+    /// Highest tier unlocked by `score`, if any.
+    pub fn tier_for(&self, score: u8) -> Option<&ReputationTier> {
+        self.tiers.iter().rev().find(|tier| score >= tier.min_score)
+    }
+
+    pub fn is_eligible_for_premium(&self, profile: &UserProfile) -> bool {
+        profile.reputation_score >= self.premium_min_score
+            && profile.total_borrowed_lifetime >= self.premium_min_volume
+    }
+}
+
+impl ReputationTier {
+    pub const LEN: usize = 1 + 8 + 2;
+
+    /// Collateral ratio required from a borrower in this tier, never below 100%.
+    pub fn required_collateral_ratio(&self, vault_collateral_ratio: u16) -> u16 {
+        vault_collateral_ratio
+            .saturating_sub(self.collateral_ratio_discount_bps)
+            .max(10000)
+    }
+}
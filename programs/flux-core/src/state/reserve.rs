@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+use crate::risk;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// An additional collateral asset accepted by a vault, alongside its base collateral.
+/// Seeds: [b"reserve", vault, mint]
+#[account]
+pub struct Reserve {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub oracle: Pubkey,
+    pub ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub deposit_cap: u64, // 0 = uncapped
+    pub total_deposits: u64,
+    pub bump: u8,
+    pub category: u8, // `RiskCategory` id, 0 = none
+    pub isolated: u8, // category is isolated; copied from the category when assigned
+    pub decimals: u8, // the mint's decimals
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 61],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ReserveConfig {
+    pub oracle: Pubkey,
+    pub ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub deposit_cap: u64,
+    pub category: u8,
+}
+
+/// A position's balance of one reserve's collateral.
+/// Seeds: [b"collateral", position, reserve]
+#[account]
+pub struct CollateralDeposit {
+    pub position: Pubkey,
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+impl Reserve {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 2 + 2 + 8 + 8 + 1 + 1 + 1 + 1 + 61;
+
+    pub fn is_isolated(&self) -> bool {
+        self.isolated != 0
+    }
+
+    /// `amount` of this reserve's mint in `risk::AMOUNT_DECIMALS`.
+    pub fn normalize(&self, amount: u64) -> Result<u64> {
+        risk::scale_amount(amount, self.decimals, risk::AMOUNT_DECIMALS)
+    }
+
+    /// Inverse of `normalize`, rounded down.
+    pub fn denormalize(&self, amount: u64) -> Result<u64> {
+        risk::scale_amount(amount, risk::AMOUNT_DECIMALS, self.decimals)
+    }
+
+    pub fn config(&self) -> ReserveConfig {
+        ReserveConfig {
+            oracle: self.oracle,
+            ltv_bps: self.ltv_bps,
+            liquidation_threshold_bps: self.liquidation_threshold_bps,
+            deposit_cap: self.deposit_cap,
+            category: self.category,
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &ReserveConfig) {
+        self.oracle = config.oracle;
+        self.ltv_bps = config.ltv_bps;
+        self.liquidation_threshold_bps = config.liquidation_threshold_bps;
+        self.deposit_cap = config.deposit_cap;
+        self.category = config.category;
+    }
+}
+
+impl ReserveConfig {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.oracle != Pubkey::default(), FluxError::InvalidReserveConfig);
+        require!(
+            self.ltv_bps <= self.liquidation_threshold_bps && self.liquidation_threshold_bps < 10000,
+            FluxError::InvalidReserveConfig
+        );
+        Ok(())
+    }
+}
+
+/// Risk category shared by correlated or isolated reserves. Positions opt in with
+/// `set_position_category`; in-category collateral then uses these limits.
+/// Seeds: [b"risk_category", vault, id]
+#[account]
+pub struct RiskCategory {
+    pub vault: Pubkey,
+    pub id: u8,
+    pub kind: RiskCategoryKind,
+    pub ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub debt_ceiling: u64, // Isolated only: total debt its positions may carry
+    pub total_debt: u64,
+    pub bump: u8,
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RiskCategoryKind {
+    /// Correlated assets (e.g. stablecoins, LST/SOL) borrowed against at a higher LTV.
+    Efficiency,
+    /// Riskier assets that only back positions in this category, up to `debt_ceiling`.
+    Isolated,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct RiskCategoryConfig {
+    pub ltv_bps: u16,
+    pub liquidation_threshold_bps: u16,
+    pub debt_ceiling: u64,
+}
+
+impl RiskCategory {
+    pub const LEN: usize = 8 + 32 + 1 + 1 + 2 + 2 + 8 + 8 + 1 + 32;
+
+    pub fn is_isolated(&self) -> bool {
+        self.kind == RiskCategoryKind::Isolated
+    }
+
+    pub fn config(&self) -> RiskCategoryConfig {
+        RiskCategoryConfig {
+            ltv_bps: self.ltv_bps,
+            liquidation_threshold_bps: self.liquidation_threshold_bps,
+            debt_ceiling: self.debt_ceiling,
+        }
+    }
+
+    pub fn apply_config(&mut self, config: &RiskCategoryConfig) {
+        self.ltv_bps = config.ltv_bps;
+        self.liquidation_threshold_bps = config.liquidation_threshold_bps;
+        self.debt_ceiling = config.debt_ceiling;
+    }
+
+    /// Track debt carried by an isolated category's positions; a no-op for e-mode.
+    pub fn add_debt(&mut self, amount: u64) -> Result<()> {
+        if !self.is_isolated() {
+            return Ok(());
+        }
+        let total = self.total_debt.checked_add(amount).ok_or(error!(FluxError::ArithmeticError))?;
+        require!(total <= self.debt_ceiling, FluxError::DebtCeilingExceeded);
+        self.total_debt = total;
+        Ok(())
+    }
+
+    pub fn remove_debt(&mut self, amount: u64) {
+        if self.is_isolated() {
+            self.total_debt = self.total_debt.saturating_sub(amount);
+        }
+    }
+}
+
+impl RiskCategoryConfig {
+    pub fn validate(&self, kind: RiskCategoryKind) -> Result<()> {
+        require!(
+            self.ltv_bps <= self.liquidation_threshold_bps && self.liquidation_threshold_bps < 10000,
+            FluxError::InvalidRiskCategory
+        );
+        require!(
+            kind == RiskCategoryKind::Efficiency || self.debt_ceiling > 0,
+            FluxError::InvalidRiskCategory
+        );
+        Ok(())
+    }
+}
+
+impl CollateralDeposit {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
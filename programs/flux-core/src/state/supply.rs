@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// A depositor's shares of a vault's supplied liquidity.
+///
+/// Shares are minted one-for-one with supplied assets: interest accrues to liabilities,
+/// not `total_assets`, so a share always redeems for one unit of the vault's asset.
+/// Seeds: [b"supply_position", vault, owner]
+#[account]
+pub struct SupplyPosition {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub shares: u64, // not locked in withdrawal tickets
+    pub bump: u8,
+}
+
+impl SupplyPosition {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 1;
+}
@@ -1,49 +1,82 @@
 use anchor_lang::prelude::*;
+use std::mem::size_of;
 
 // Copyright (c) 2025 FLUX Protocol. All rights reserved.
 
-#[account]
+/// Zero-copy user profile. Fields are ordered largest-first so the `repr(C)`
+/// layout has no implicit padding; flags are stored as `u8` since `bool` is not `Pod`.
+/// Seeds: [b"profile", owner]
+#[account(zero_copy)]
 pub struct UserProfile {
     pub owner: Pubkey,
-    pub reputation_score: u8,
-    pub active_loans: u32,
     pub total_borrowed_lifetime: u64,
     pub total_repaid_lifetime: u64,
-    pub liquidation_count: u16,
     pub last_active_timestamp: i64,
-    pub role: UserRole,
-    
-    // History Tracking
-    pub action_history: [UserAction; 50],
-    pub history_idx: u8, // Circular buffer index
-    
-    // KYC/AML Flags
-    pub kyc_verified: bool,
-    pub aml_flagged: bool,
+    pub active_loans: u32,
+    pub case_count: u32, // Compliance case log (see `CaseRecord`)
+    pub liquidation_count: u16,
     pub country_code: [u8; 2],
-    
+    pub reputation_score: u8,
+    pub role: u8, // UserRole
+
+    // KYC/AML Flags
+    pub kyc_verified: u8,
+    pub aml_flagged: u8,
+
+    pub has_history: u8, // ActionHistory account opened
     pub bump: u8,
+    pub version: u8,
+    pub _padding: [u8; 5],
+
+    // Reputation inputs (v3)
+    pub created_at: i64,
+    pub repayment_count: u32,
+
+    pub transfer_records: u32, // `TransferRecord`s opened; the next one's nonce
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 48],
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
 pub enum UserRole {
     Standard,
     Premium,
     Institutional,
     Auditor,
     Blacklisted,
+    Compliance,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+impl From<u8> for UserRole {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => UserRole::Premium,
+            2 => UserRole::Institutional,
+            3 => UserRole::Auditor,
+            4 => UserRole::Blacklisted,
+            5 => UserRole::Compliance,
+            _ => UserRole::Standard,
+        }
+    }
+}
+
+#[zero_copy]
+#[derive(Debug, PartialEq, Default)]
 pub struct UserAction {
-    pub action_type: ActionType,
     pub amount: u64,
     pub timestamp: i64,
-    pub tx_signature_hash: [u8; 8], // Partial hash for storage efficiency
+    pub slot: u64,              // slot + instruction index identify the originating instruction
+    pub instruction_index: u16,
+    pub action_type: u8,        // ActionType
+    pub _padding: [u8; 5],
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[repr(u8)]
 pub enum ActionType {
+    #[default]
     None,
     Deposit,
     Withdraw,
@@ -52,65 +85,147 @@ pub enum ActionType {
     Liquidated,
 }
 
-impl Default for ActionType {
-    fn default() -> Self {
-        ActionType::None
+/// Per-profile action log stored as a ring buffer. This header is followed in
+/// the account data by `capacity` packed `UserAction` entries.
+/// Seeds: [b"history", user_profile]
+#[account(zero_copy)]
+pub struct ActionHistory {
+    pub user_profile: Pubkey,
+    pub capacity: u32,
+    pub len: u32,
+    pub head: u32, // Next write slot
+    pub bump: u8,
+    pub _padding: [u8; 3],
+}
+
+impl UserAction {
+    pub const LEN: usize = size_of::<UserAction>();
+
+    pub fn new(action_type: ActionType, amount: u64, clock: &Clock, instruction_index: u16) -> Self {
+        Self {
+            amount,
+            timestamp: clock.unix_timestamp,
+            slot: clock.slot,
+            instruction_index,
+            action_type: action_type as u8,
+            _padding: [0u8; 5],
+        }
     }
 }
 
 impl UserProfile {
-    pub const LEN: usize = 8 + 32 + 1 + 4 + 8 + 8 + 2 + 8 + 1 + (50 * (1 + 8 + 8 + 8)) + 1 + 1 + 1 + 2 + 1;
+    pub const LEN: usize = 8 + size_of::<UserProfile>();
+    pub const CURRENT_VERSION: u8 = 3;
 
+    pub fn role(&self) -> UserRole {
+        UserRole::from(self.role)
+    }
+
+    pub fn is_kyc_verified(&self) -> bool {
+        self.kyc_verified != 0
+    }
+
+    pub fn is_aml_flagged(&self) -> bool {
+        self.aml_flagged != 0
+    }
+
+    pub fn set_aml_flagged(&mut self, flagged: bool) {
+        self.aml_flagged = flagged as u8;
+    }
+
+    pub fn has_history(&self) -> bool {
+        self.has_history != 0
+    }
+
+    /// Update aggregate statistics. The detailed entry is appended to the
+    /// profile's `ActionHistory`, if one is open. `reputation_score` is
+    /// recomputed separately against the `ReputationConfig`.
     pub fn record_action(&mut self, action_type: ActionType, amount: u64, clock: &Clock) {
-        let idx = self.history_idx as usize;
-        
-        self.action_history[idx] = UserAction {
-            action_type,
-            amount,
-            timestamp: clock.unix_timestamp,
-            tx_signature_hash: [0u8; 8], // In real app, hash the sig
-        };
-        
-        self.history_idx = ((idx + 1) % 50) as u8;
         self.last_active_timestamp = clock.unix_timestamp;
-        
+
         // Update aggregate stats
         match action_type {
             ActionType::Borrow => {
                 self.total_borrowed_lifetime = self.total_borrowed_lifetime.saturating_add(amount);
-                self.active_loans = self.active_loans.saturating_add(1);
             },
             ActionType::Repay => {
                 self.total_repaid_lifetime = self.total_repaid_lifetime.saturating_add(amount);
-                if self.active_loans > 0 {
-                    self.active_loans -= 1;
-                }
+                self.repayment_count = self.repayment_count.saturating_add(1);
             },
             ActionType::Liquidated => {
                 self.liquidation_count = self.liquidation_count.saturating_add(1);
-                // Penalty on reputation
-                self.reputation_score = self.reputation_score.saturating_sub(10);
             },
             _ => {}
         }
-        
-        // Dynamic reputation adjustment
-        self.update_reputation();
     }
-    
-    fn update_reputation(&mut self) {
-        // If user repays more than borrows, increase rep
-        if self.total_repaid_lifetime > self.total_borrowed_lifetime {
-            self.reputation_score = self.reputation_score.saturating_add(1);
-        }
-        
-        // Cap at 100
-        if self.reputation_score > 100 {
-            self.reputation_score = 100;
-        }
+
+    pub fn is_compliance_officer(&self) -> bool {
+        matches!(self.role(), UserRole::Auditor | UserRole::Compliance) && !self.is_aml_flagged()
     }
-    
-    pub fn is_eligible_for_premium(&self) -> bool {
-        self.reputation_score > 80 && self.total_borrowed_lifetime > 1_000_000_000_000 // 1000 SOL
+}
+
+impl ActionHistory {
+    pub const HEADER_LEN: usize = 8 + size_of::<ActionHistory>();
+    pub const INITIAL_CAPACITY: u32 = 64;
+    pub const MAX_CAPACITY: u32 = 8192;
+
+    pub fn space(capacity: u32) -> usize {
+        Self::HEADER_LEN + capacity as usize * UserAction::LEN
+    }
+
+    /// Append an entry, overwriting the oldest once the buffer is full.
+    pub fn append(info: &AccountInfo, action: UserAction) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        let (header, entries) = data.split_at_mut(Self::HEADER_LEN);
+        let history: &mut ActionHistory = bytemuck::from_bytes_mut(&mut header[8..]);
+        require!(history.capacity > 0, crate::errors::FluxError::InvalidHistoryCapacity);
+
+        let offset = history.head as usize * UserAction::LEN;
+        entries[offset..offset + UserAction::LEN].copy_from_slice(bytemuck::bytes_of(&action));
+
+        history.head = (history.head + 1) % history.capacity;
+        history.len = history.len.saturating_add(1).min(history.capacity);
+        Ok(())
+    }
+
+    /// Rotate the entries so the oldest sits at index 0. Must run before the
+    /// buffer grows, otherwise wrapped entries would be split around the new slots.
+    pub fn linearize(info: &AccountInfo) -> Result<()> {
+        let mut data = info.try_borrow_mut_data()?;
+        let (header, entries) = data.split_at_mut(Self::HEADER_LEN);
+        let history: &mut ActionHistory = bytemuck::from_bytes_mut(&mut header[8..]);
+
+        if history.len == history.capacity && history.head != 0 {
+            let used = history.capacity as usize * UserAction::LEN;
+            entries[..used].rotate_left(history.head as usize * UserAction::LEN);
+        }
+        history.head = history.len;
+        Ok(())
     }
 }
+
+/// Borsh layout used by profiles created before the zero-copy conversion (versions 0 and 1).
+/// Only read by `migrate_user_profile`.
+#[derive(AnchorDeserialize)]
+pub struct LegacyUserProfile {
+    pub owner: Pubkey,
+    pub reputation_score: u8,
+    pub active_loans: u32,
+    pub total_borrowed_lifetime: u64,
+    pub total_repaid_lifetime: u64,
+    pub liquidation_count: u16,
+    pub last_active_timestamp: i64,
+    pub role: UserRole,
+    pub action_history: [[u8; 25]; 50],
+    pub history_idx: u8,
+    pub kyc_verified: bool,
+    pub aml_flagged: bool,
+    pub country_code: [u8; 2],
+    pub bump: u8,
+    pub case_count: u32,
+    pub version: u8,
+}
+
+impl LegacyUserProfile {
+    pub const LEN: usize = 8 + 32 + 1 + 4 + 8 + 8 + 2 + 8 + 1 + (50 * 25) + 1 + 1 + 1 + 2 + 1 + 4 + 1;
+}
@@ -24,10 +24,54 @@ pub struct Vault {
     pub liquidation_penalty: u16,
     pub liquidation_bonus: u16,
     
+    // Governed parameters (see `VaultConfig`)
+    pub min_collateral_ratio: u16,
+    pub max_leverage: u8,
+    pub optimal_utilization: u16,
+    pub base_rate: u16,
+    pub max_rate: u16,
+    pub last_config_change: i64,
+    
+    // Circuit breakers (see `circuit_breaker`); a zero limit disables that breaker
+    pub max_outflow_bps: u16,    // net outflow per window, relative to assets at window start
+    pub outflow_window: u32,     // seconds
+    pub max_price_move_bps: u16, // between consecutive oracle reads
+    pub window_start: i64,
+    pub window_start_assets: u64,
+    pub window_outflow: u64,
+    pub window_inflow: u64,
+    pub last_oracle_price: u64,
+    pub freeze_reason: u8, // `FreezeReason`; non-zero means only the guardian can clear
+    
+    // Risk engine bounds (see `refresh_risk`); a zero max disables that adjustment
+    pub min_risk_factor: u16,
+    pub max_risk_factor: u16,
+    pub min_liquidation_penalty: u16,
+    pub max_liquidation_penalty: u16,
+    pub realized_volatility: u16, // bps per hour, from the last refresh
+    pub last_risk_refresh: i64,
+    
+    // Outstanding flash loan; non-zero principal means a loan is open in this transaction
+    pub flash_loan_amount: u64,
+    pub flash_loan_fee: u64,
+    
+    // Exposure caps; 0 = uncapped
+    pub supply_cap: u64,      // total_assets
+    pub borrow_cap: u64,      // total_liabilities
+    pub user_borrow_cap: u64, // debt per position
+    
+    pub has_withdrawal_queue: u8, // `WithdrawalQueue` opened; liquidity then fills it first
+    
+    // Appended past the original layout; older vaults gain them through `migrate_vault`
+    pub total_collateral: u64, // base collateral held for positions; not lendable, so outside `total_assets`
+    pub debt_oracle: Pubkey,   // debt asset price feed; `oracle_config` prices the collateral
+    
     // Reserved for future upgrades
-    pub reserved: [u8; 128],
+    pub reserved: [u8; 3],
 }
 
+/// Full set of governed vault parameters. Changed through the timelocked
+/// `propose_config_change` / `execute_config_change` flow using a `VaultConfigUpdate`.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
 pub struct VaultConfig {
     pub min_collateral_ratio: u16,
@@ -35,84 +79,450 @@ pub struct VaultConfig {
     pub optimal_utilization: u16,
     pub base_rate: u16,
     pub max_rate: u16,
+    pub collateral_ratio: u16,
+    pub risk_factor: u16,
+    pub liquidation_penalty: u16,
+    pub liquidation_bonus: u16,
+    pub performance_fee_rate: u16,
+    pub management_fee_rate: u16,
+    pub flash_loan_fee_rate: u16,
+    pub oracle_config: Pubkey,
+    pub debt_oracle: Pubkey,
+    pub min_risk_factor: u16,
+    pub max_risk_factor: u16,
+    pub min_liquidation_penalty: u16,
+    pub max_liquidation_penalty: u16,
+    pub supply_cap: u64,
+    pub borrow_cap: u64,
+    pub user_borrow_cap: u64,
+}
+
+/// Partial update applied on top of the vault's current `VaultConfig`.
+/// `None` leaves the field unchanged.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub struct VaultConfigUpdate {
+    pub min_collateral_ratio: Option<u16>,
+    pub max_leverage: Option<u8>,
+    pub optimal_utilization: Option<u16>,
+    pub base_rate: Option<u16>,
+    pub max_rate: Option<u16>,
+    pub collateral_ratio: Option<u16>,
+    pub risk_factor: Option<u16>,
+    pub liquidation_penalty: Option<u16>,
+    pub liquidation_bonus: Option<u16>,
+    pub performance_fee_rate: Option<u16>,
+    pub management_fee_rate: Option<u16>,
+    pub flash_loan_fee_rate: Option<u16>,
+    pub oracle_config: Option<Pubkey>,
+    pub debt_oracle: Option<Pubkey>,
+    pub min_risk_factor: Option<u16>,
+    pub max_risk_factor: Option<u16>,
+    pub min_liquidation_penalty: Option<u16>,
+    pub max_liquidation_penalty: Option<u16>,
+    pub supply_cap: Option<u64>,
+    pub borrow_cap: Option<u64>,
+    pub user_borrow_cap: Option<u64>,
+}
+
+/// Bits of `VaultConfigChangedEvent::changed_fields`.
+pub mod config_fields {
+    pub const MIN_COLLATERAL_RATIO: u32 = 1 << 0;
+    pub const MAX_LEVERAGE: u32 = 1 << 1;
+    pub const OPTIMAL_UTILIZATION: u32 = 1 << 2;
+    pub const BASE_RATE: u32 = 1 << 3;
+    pub const MAX_RATE: u32 = 1 << 4;
+    pub const COLLATERAL_RATIO: u32 = 1 << 5;
+    pub const RISK_FACTOR: u32 = 1 << 6;
+    pub const LIQUIDATION_PENALTY: u32 = 1 << 7;
+    pub const LIQUIDATION_BONUS: u32 = 1 << 8;
+    pub const PERFORMANCE_FEE_RATE: u32 = 1 << 9;
+    pub const MANAGEMENT_FEE_RATE: u32 = 1 << 10;
+    pub const FLASH_LOAN_FEE_RATE: u32 = 1 << 11;
+    pub const ORACLE_CONFIG: u32 = 1 << 12;
+    pub const MIN_RISK_FACTOR: u32 = 1 << 13;
+    pub const MAX_RISK_FACTOR: u32 = 1 << 14;
+    pub const MIN_LIQUIDATION_PENALTY: u32 = 1 << 15;
+    pub const MAX_LIQUIDATION_PENALTY: u32 = 1 << 16;
+    pub const SUPPLY_CAP: u32 = 1 << 17;
+    pub const BORROW_CAP: u32 = 1 << 18;
+    pub const USER_BORROW_CAP: u32 = 1 << 19;
+    pub const DEBT_ORACLE: u32 = 1 << 20;
+
+    pub const FEES: u32 = PERFORMANCE_FEE_RATE | MANAGEMENT_FEE_RATE | FLASH_LOAN_FEE_RATE;
+}
+
+impl VaultConfig {
+    pub const LEN: usize = 2 + 1 + 2 + 2 + 2 + 2 + 2 + 2 + 2 + 2 + 2 + 2 + 32 + 32 + 2 + 2 + 2 + 2 + 8 + 8 + 8;
+    pub const MAX_RISK_FACTOR: u16 = 1000;
+    pub const MIN_COLLATERAL_RATIO: u16 = 10000; // 100%
+    pub const MAX_COLLATERAL_RATIO: u16 = 50000; // 500%
+    pub const MAX_LEVERAGE: u8 = 20;
+    pub const MAX_RATE: u16 = 50000; // 500% APR
+    pub const MAX_LIQUIDATION_PENALTY: u16 = 5000;
+    pub const MAX_PERFORMANCE_FEE_RATE: u16 = 5000;
+    pub const MAX_MANAGEMENT_FEE_RATE: u16 = 1000;
+    pub const MAX_FLASH_LOAN_FEE_RATE: u16 = 1000;
+
+    /// Apply `update`, returning the merged config and a `config_fields` mask of the fields that changed.
+    pub fn merge(&self, update: &VaultConfigUpdate) -> (VaultConfig, u32) {
+        let mut next = *self;
+        next.min_collateral_ratio = update.min_collateral_ratio.unwrap_or(self.min_collateral_ratio);
+        next.max_leverage = update.max_leverage.unwrap_or(self.max_leverage);
+        next.optimal_utilization = update.optimal_utilization.unwrap_or(self.optimal_utilization);
+        next.base_rate = update.base_rate.unwrap_or(self.base_rate);
+        next.max_rate = update.max_rate.unwrap_or(self.max_rate);
+        next.collateral_ratio = update.collateral_ratio.unwrap_or(self.collateral_ratio);
+        next.risk_factor = update.risk_factor.unwrap_or(self.risk_factor);
+        next.liquidation_penalty = update.liquidation_penalty.unwrap_or(self.liquidation_penalty);
+        next.liquidation_bonus = update.liquidation_bonus.unwrap_or(self.liquidation_bonus);
+        next.performance_fee_rate = update.performance_fee_rate.unwrap_or(self.performance_fee_rate);
+        next.management_fee_rate = update.management_fee_rate.unwrap_or(self.management_fee_rate);
+        next.flash_loan_fee_rate = update.flash_loan_fee_rate.unwrap_or(self.flash_loan_fee_rate);
+        next.oracle_config = update.oracle_config.unwrap_or(self.oracle_config);
+        next.debt_oracle = update.debt_oracle.unwrap_or(self.debt_oracle);
+        next.min_risk_factor = update.min_risk_factor.unwrap_or(self.min_risk_factor);
+        next.max_risk_factor = update.max_risk_factor.unwrap_or(self.max_risk_factor);
+        next.min_liquidation_penalty = update.min_liquidation_penalty.unwrap_or(self.min_liquidation_penalty);
+        next.max_liquidation_penalty = update.max_liquidation_penalty.unwrap_or(self.max_liquidation_penalty);
+        next.supply_cap = update.supply_cap.unwrap_or(self.supply_cap);
+        next.borrow_cap = update.borrow_cap.unwrap_or(self.borrow_cap);
+        next.user_borrow_cap = update.user_borrow_cap.unwrap_or(self.user_borrow_cap);
+
+        let changed = self.diff(&next);
+        (next, changed)
+    }
+
+    /// `config_fields` mask of the fields that differ between `self` and `other`.
+    pub fn diff(&self, other: &VaultConfig) -> u32 {
+        use config_fields::*;
+        let mut changed = 0u32;
+        let mut mark = |differs: bool, bit: u32| if differs { changed |= bit };
+        mark(self.min_collateral_ratio != other.min_collateral_ratio, MIN_COLLATERAL_RATIO);
+        mark(self.max_leverage != other.max_leverage, MAX_LEVERAGE);
+        mark(self.optimal_utilization != other.optimal_utilization, OPTIMAL_UTILIZATION);
+        mark(self.base_rate != other.base_rate, BASE_RATE);
+        mark(self.max_rate != other.max_rate, MAX_RATE);
+        mark(self.collateral_ratio != other.collateral_ratio, COLLATERAL_RATIO);
+        mark(self.risk_factor != other.risk_factor, RISK_FACTOR);
+        mark(self.liquidation_penalty != other.liquidation_penalty, LIQUIDATION_PENALTY);
+        mark(self.liquidation_bonus != other.liquidation_bonus, LIQUIDATION_BONUS);
+        mark(self.performance_fee_rate != other.performance_fee_rate, PERFORMANCE_FEE_RATE);
+        mark(self.management_fee_rate != other.management_fee_rate, MANAGEMENT_FEE_RATE);
+        mark(self.flash_loan_fee_rate != other.flash_loan_fee_rate, FLASH_LOAN_FEE_RATE);
+        mark(self.oracle_config != other.oracle_config, ORACLE_CONFIG);
+        mark(self.debt_oracle != other.debt_oracle, DEBT_ORACLE);
+        mark(self.min_risk_factor != other.min_risk_factor, MIN_RISK_FACTOR);
+        mark(self.max_risk_factor != other.max_risk_factor, MAX_RISK_FACTOR);
+        mark(self.min_liquidation_penalty != other.min_liquidation_penalty, MIN_LIQUIDATION_PENALTY);
+        mark(self.max_liquidation_penalty != other.max_liquidation_penalty, MAX_LIQUIDATION_PENALTY);
+        mark(self.supply_cap != other.supply_cap, SUPPLY_CAP);
+        mark(self.borrow_cap != other.borrow_cap, BORROW_CAP);
+        mark(self.user_borrow_cap != other.user_borrow_cap, USER_BORROW_CAP);
+        changed
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.risk_factor <= Self::MAX_RISK_FACTOR, crate::errors::FluxError::InvalidRiskFactor);
+        require!(self.collateral_ratio >= Self::MIN_COLLATERAL_RATIO, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.liquidation_bonus < self.liquidation_penalty, crate::errors::FluxError::InvalidVaultConfig);
+        require!(
+            self.min_collateral_ratio >= Self::MIN_COLLATERAL_RATIO && self.min_collateral_ratio <= self.collateral_ratio,
+            crate::errors::FluxError::InvalidVaultConfig
+        );
+        require!(self.collateral_ratio <= Self::MAX_COLLATERAL_RATIO, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.max_leverage >= 1 && self.max_leverage <= Self::MAX_LEVERAGE, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.optimal_utilization > 0 && self.optimal_utilization <= 10000, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.base_rate <= self.max_rate && self.max_rate <= Self::MAX_RATE, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.liquidation_penalty <= Self::MAX_LIQUIDATION_PENALTY, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.performance_fee_rate <= Self::MAX_PERFORMANCE_FEE_RATE, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.management_fee_rate <= Self::MAX_MANAGEMENT_FEE_RATE, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.flash_loan_fee_rate <= Self::MAX_FLASH_LOAN_FEE_RATE, crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.oracle_config != Pubkey::default(), crate::errors::FluxError::InvalidVaultConfig);
+        require!(self.debt_oracle != Pubkey::default(), crate::errors::FluxError::InvalidVaultConfig);
+
+        // Risk engine bounds; the live value must sit inside them so adjustments start in range
+        if self.max_risk_factor > 0 {
+            require!(
+                self.min_risk_factor <= self.risk_factor
+                    && self.risk_factor <= self.max_risk_factor
+                    && self.max_risk_factor <= Self::MAX_RISK_FACTOR,
+                crate::errors::FluxError::InvalidVaultConfig
+            );
+        }
+        if self.max_liquidation_penalty > 0 {
+            require!(
+                self.liquidation_bonus < self.min_liquidation_penalty
+                    && self.min_liquidation_penalty <= self.liquidation_penalty
+                    && self.liquidation_penalty <= self.max_liquidation_penalty
+                    && self.max_liquidation_penalty <= Self::MAX_LIQUIDATION_PENALTY,
+                crate::errors::FluxError::InvalidVaultConfig
+            );
+        }
+        require!(
+            self.borrow_cap == 0 || self.user_borrow_cap <= self.borrow_cap,
+            crate::errors::FluxError::InvalidVaultConfig
+        );
+        Ok(())
+    }
+}
+
+impl VaultConfigUpdate {
+    // Every field serialized as Some
+    pub const LEN: usize = VaultConfig::LEN + 21;
+
+    pub fn is_empty(&self) -> bool {
+        *self == VaultConfigUpdate::default()
+    }
+
+    /// Fee fields are owned by the fee manager; everything else by the risk manager.
+    pub fn touches_fees(&self) -> bool {
+        self.performance_fee_rate.is_some()
+            || self.management_fee_rate.is_some()
+            || self.flash_loan_fee_rate.is_some()
+    }
+
+    pub fn touches_risk(&self) -> bool {
+        let fees_only = VaultConfigUpdate {
+            performance_fee_rate: self.performance_fee_rate,
+            management_fee_rate: self.management_fee_rate,
+            flash_loan_fee_rate: self.flash_loan_fee_rate,
+            ..Default::default()
+        };
+        *self != fees_only
+    }
 }
 
 impl Vault {
     // Calculated size to ensure future compatibility
-    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 1 + 2 + 2 + 32 + 1 + 16 + 8 + 2 + 2 + 2 + 2 + 2 + 128;
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 1 + 2 + 2 + 32 + 1 + 16 + 8 + 2 + 2 + 2 + 2 + 2
+        + 2 + 1 + 2 + 2 + 2 + 8 + 2 + 4 + 2 + 8 + 8 + 8 + 8 + 8 + 1 + 2 + 2 + 2 + 2 + 2 + 8 + 8 + 8 + 8 + 8 + 8 + 1
+        + 8 + 32 + 3;
+    // Size before `total_collateral` and `debt_oracle`; the old 3 reserved bytes now open `total_collateral`
+    pub const LEGACY_LEN: usize = Self::LEN - 8 - 32;
+    
+    // Timelock between proposal and execution, and minimum spacing between executed changes
+    pub const CONFIG_TIMELOCK: i64 = 48 * 60 * 60;
+    pub const CONFIG_COOLDOWN: i64 = 24 * 60 * 60;
 
-    pub fn calculate_health_factor(&self, oracle_price: u64, _oracle_decimals: u8) -> Result<u64> {
-        // Mock complex health factor calculation
-        // HF = (Collateral * Price * LiquidationThreshold) / Debt
-        if self.total_liabilities == 0 {
-            return Ok(u64::MAX);
-        }
+    // Fixed borrow rate of 5 / 1e9 per second, and the same rate annualized (~15.8% APR)
+    pub const INTEREST_RATE_PER_SECOND: u128 = 5;
+    pub const INTEREST_RATE_SCALE: u128 = 1_000_000_000;
+    pub const SECONDS_PER_YEAR: u128 = 365 * 24 * 60 * 60;
+    pub const DEFAULT_BORROW_RATE_BPS: u64 = 1577;
 
-        // Simulating heavy math operations
-        let collateral_val = self.total_assets as u128;
-        let price_norm = oracle_price as u128;
-        let threshold = 8500u128; // 85% LTV
+    // Risk engine: volatility range mapped onto the governance bounds, and minimum crank spacing
+    pub const LOW_VOLATILITY_BPS: u16 = 100;
+    pub const HIGH_VOLATILITY_BPS: u16 = 1000;
+    pub const RISK_SMOOTHING_DIVISOR: u16 = 4;
+    pub const RISK_REFRESH_INTERVAL: i64 = 60;
 
-        let numerator = collateral_val
-            .checked_mul(price_norm).ok_or(error!(crate::errors::FluxError::ArithmeticError))?
-            .checked_mul(threshold).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
-        
-        let denominator = (self.total_liabilities as u128)
-            .checked_mul(10000).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+    pub fn config(&self) -> VaultConfig {
+        VaultConfig {
+            min_collateral_ratio: self.min_collateral_ratio,
+            max_leverage: self.max_leverage,
+            optimal_utilization: self.optimal_utilization,
+            base_rate: self.base_rate,
+            max_rate: self.max_rate,
+            collateral_ratio: self.collateral_ratio,
+            risk_factor: self.risk_factor,
+            liquidation_penalty: self.liquidation_penalty,
+            liquidation_bonus: self.liquidation_bonus,
+            performance_fee_rate: self.performance_fee_rate,
+            management_fee_rate: self.management_fee_rate,
+            flash_loan_fee_rate: self.flash_loan_fee_rate,
+            oracle_config: self.oracle_config,
+            debt_oracle: self.debt_oracle,
+            min_risk_factor: self.min_risk_factor,
+            max_risk_factor: self.max_risk_factor,
+            min_liquidation_penalty: self.min_liquidation_penalty,
+            max_liquidation_penalty: self.max_liquidation_penalty,
+            supply_cap: self.supply_cap,
+            borrow_cap: self.borrow_cap,
+            user_borrow_cap: self.user_borrow_cap,
+        }
+    }
 
-        let hf = numerator.checked_div(denominator).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
-        
-        Ok(hf as u64)
+    pub fn apply_config(&mut self, config: &VaultConfig, now: i64) {
+        self.min_collateral_ratio = config.min_collateral_ratio;
+        self.max_leverage = config.max_leverage;
+        self.optimal_utilization = config.optimal_utilization;
+        self.base_rate = config.base_rate;
+        self.max_rate = config.max_rate;
+        self.collateral_ratio = config.collateral_ratio;
+        self.risk_factor = config.risk_factor;
+        self.liquidation_penalty = config.liquidation_penalty;
+        self.liquidation_bonus = config.liquidation_bonus;
+        self.performance_fee_rate = config.performance_fee_rate;
+        self.management_fee_rate = config.management_fee_rate;
+        self.flash_loan_fee_rate = config.flash_loan_fee_rate;
+        if self.oracle_config != config.oracle_config {
+            // A new feed must not be measured against the old feed's last price
+            self.last_oracle_price = 0;
+        }
+        self.oracle_config = config.oracle_config;
+        self.debt_oracle = config.debt_oracle;
+        self.min_risk_factor = config.min_risk_factor;
+        self.max_risk_factor = config.max_risk_factor;
+        self.min_liquidation_penalty = config.min_liquidation_penalty;
+        self.max_liquidation_penalty = config.max_liquidation_penalty;
+        self.supply_cap = config.supply_cap;
+        self.borrow_cap = config.borrow_cap;
+        self.user_borrow_cap = config.user_borrow_cap;
+        self.last_config_change = now;
+    }
+
+    pub fn require_config_cooldown(&self, now: i64) -> Result<()> {
+        require!(
+            now.saturating_sub(self.last_config_change) >= Self::CONFIG_COOLDOWN,
+            crate::errors::FluxError::ConfigCooldown
+        );
+        Ok(())
     }
 
     pub fn accrue_interest(&mut self, current_time: i64) -> Result<()> {
         let time_delta = current_time.checked_sub(self.last_update).unwrap_or(0);
-        if time_delta == 0 {
+        if time_delta <= 0 {
             return Ok(());
         }
 
         // Simple interest model simulation
         // Interest = Principal * Rate * Time
-        let rate_per_second = 5u128; // very small number
         let interest = (self.total_liabilities as u128)
-            .checked_mul(rate_per_second).unwrap()
-            .checked_mul(time_delta as u128).unwrap()
-            .checked_div(1_000_000_000).unwrap();
+            .checked_mul(Self::INTEREST_RATE_PER_SECOND).ok_or(error!(crate::errors::FluxError::ArithmeticError))?
+            .checked_mul(time_delta as u128).ok_or(error!(crate::errors::FluxError::ArithmeticError))?
+            / Self::INTEREST_RATE_SCALE;
 
-        self.interest_accumulator = self.interest_accumulator.checked_add(interest).unwrap();
-        self.total_liabilities = self.total_liabilities.checked_add(interest as u64).unwrap();
+        self.interest_accumulator = self.interest_accumulator.checked_add(interest).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+        let interest = u64::try_from(interest).map_err(|_| error!(crate::errors::FluxError::ArithmeticError))?;
+        // Interest owed by borrowers is also owed to depositors: it raises both sides equally
+        self.total_liabilities = self.total_liabilities.checked_add(interest).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+        self.total_assets = self.total_assets.checked_add(interest).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
         self.last_update = current_time;
 
         msg!("Interest accrued: {} lamports over {} seconds", interest, time_delta);
         Ok(())
     }
 
-    pub fn validate_collateral(&self, min_ratio: u16) -> bool {
-        // Complex validation logic
+    /// Liabilities over assets in bps, capped at 100%.
+    pub fn utilization_bps(&self) -> u64 {
         if self.total_liabilities == 0 {
-            return true;
+            return 0;
         }
-        
-        let current_ratio = (self.total_assets as u128)
-            .checked_mul(10000).unwrap()
-            .checked_div(self.total_liabilities as u128).unwrap_or(0);
-            
-        current_ratio >= min_ratio as u128
+        if self.total_assets == 0 {
+            return 10000;
+        }
+        ((self.total_liabilities as u128 * 10000 / self.total_assets as u128) as u64).min(10000)
+    }
+
+    /// Annual borrow rate in bps: the fixed per-second rate `accrue_interest` charges, annualized.
+    pub fn borrow_rate_bps(&self) -> u64 {
+        Self::DEFAULT_BORROW_RATE_BPS
+    }
+
+    /// Annual rate earned by depositors in bps, after the performance fee.
+    pub fn supply_rate_bps(&self) -> u64 {
+        let gross = self.borrow_rate_bps() * self.utilization_bps() / 10000;
+        gross * (10000 - (self.performance_fee_rate as u64).min(10000)) / 10000
     }
 
+    /// Check that depositing `amount` keeps `total_assets` within the supply cap.
+    pub fn require_supply_cap(&self, amount: u64) -> Result<()> {
+        let total = self.total_assets.checked_add(amount).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+        require!(self.supply_cap == 0 || total <= self.supply_cap, crate::errors::FluxError::SupplyCapExceeded);
+        Ok(())
+    }
+
+    /// Check that borrowing `amount` keeps the vault within its borrow cap and
+    /// the position, ending at `position_debt`, within the per-user cap.
+    pub fn require_borrow_cap(&self, amount: u64, position_debt: u64) -> Result<()> {
+        let total = self.total_liabilities.checked_add(amount).ok_or(error!(crate::errors::FluxError::ArithmeticError))?;
+        require!(self.borrow_cap == 0 || total <= self.borrow_cap, crate::errors::FluxError::BorrowCapExceeded);
+        require!(
+            self.user_borrow_cap == 0 || position_debt <= self.user_borrow_cap,
+            crate::errors::FluxError::BorrowCapExceeded
+        );
+        Ok(())
+    }
+
+    /// Reject vault-mutating instructions while a flash loan is open.
+    pub fn require_no_flash_loan(&self) -> Result<()> {
+        require!(self.flash_loan_amount == 0, crate::errors::FluxError::FlashLoanActive);
+        Ok(())
+    }
+
+    pub fn has_withdrawal_queue(&self) -> bool {
+        self.has_withdrawal_queue != 0
+    }
+
+    /// Accrual raises assets and liabilities together, so only a loss the vault has not
+    /// covered leaves liabilities above assets.
     pub fn is_solvent(&self) -> bool {
         self.total_assets >= self.total_liabilities
     }
 
-    pub fn apply_risk_adjustment(&mut self, market_volatility: u16) {
-        // Dynamic risk adjustment based on market conditions
-        if market_volatility > 500 { // High volatility > 5%
-            self.risk_factor = self.risk_factor.saturating_add(100);
-            self.liquidation_penalty = self.liquidation_penalty.saturating_add(500); // Increase penalty
-            msg!("High volatility detected: Risk factor increased to {}", self.risk_factor);
-        } else {
-            self.risk_factor = self.risk_factor.saturating_sub(10);
-            if self.risk_factor < 100 { self.risk_factor = 100; }
+    /// Move `risk_factor` and `liquidation_penalty` part of the way toward targets
+    /// interpolated from `volatility_bps` within the governance bounds. Repeated
+    /// calls converge on the target instead of ratcheting.
+    pub fn apply_risk_adjustment(&mut self, volatility_bps: u16) {
+        self.realized_volatility = volatility_bps;
+        let span = (Self::HIGH_VOLATILITY_BPS - Self::LOW_VOLATILITY_BPS) as u32;
+        let level = (volatility_bps.clamp(Self::LOW_VOLATILITY_BPS, Self::HIGH_VOLATILITY_BPS)
+            - Self::LOW_VOLATILITY_BPS) as u32;
+
+        if self.max_risk_factor > 0 {
+            let target = lerp(self.min_risk_factor, self.max_risk_factor, level, span);
+            self.risk_factor = step_toward(self.risk_factor, target)
+                .clamp(self.min_risk_factor, self.max_risk_factor);
+        }
+        if self.max_liquidation_penalty > 0 {
+            let target = lerp(self.min_liquidation_penalty, self.max_liquidation_penalty, level, span);
+            self.liquidation_penalty = step_toward(self.liquidation_penalty, target)
+                .clamp(self.min_liquidation_penalty, self.max_liquidation_penalty);
+        }
+        msg!("Volatility {} bps: risk factor {}, liquidation penalty {}",
+            volatility_bps, self.risk_factor, self.liquidation_penalty);
+    }
+}
+
+fn lerp(min: u16, max: u16, level: u32, span: u32) -> u16 {
+    let range = max.saturating_sub(min) as u32;
+    min + (range * level / span) as u16
+}
+
+/// Close `RISK_SMOOTHING_DIVISOR`-th of the gap per refresh, at least one unit.
+fn step_toward(current: u16, target: u16) -> u16 {
+    let gap = current.abs_diff(target);
+    let step = (gap / Vault::RISK_SMOOTHING_DIVISOR).max(gap.min(1));
+    if target > current { current + step } else { current - step }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_maps_level_onto_the_range() {
+        assert_eq!(lerp(200, 1000, 0, 900), 200);
+        assert_eq!(lerp(200, 1000, 450, 900), 600);
+        assert_eq!(lerp(200, 1000, 900, 900), 1000);
+        // Inverted bounds collapse to the minimum
+        assert_eq!(lerp(1000, 200, 900, 900), 1000);
+    }
+
+    #[test]
+    fn step_toward_closes_a_fraction_of_the_gap() {
+        assert_eq!(step_toward(100, 500), 200);
+        assert_eq!(step_toward(500, 100), 400);
+        // At least one unit, and never past the target
+        assert_eq!(step_toward(100, 102), 101);
+        assert_eq!(step_toward(102, 100), 101);
+        assert_eq!(step_toward(100, 100), 100);
+
+        let mut current = 0;
+        for _ in 0..40 {
+            current = step_toward(current, 1000);
+            assert!(current <= 1000);
         }
+        assert_eq!(current, 1000);
     }
 }
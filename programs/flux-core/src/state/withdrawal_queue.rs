@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use crate::state::vault::Vault;
+
+// Copyright (c) 2025 FLUX Protocol. All rights reserved.
+
+/// FIFO queue of withdrawals waiting on vault liquidity.
+///
+/// Tickets are ordered by their offset in the running `total_requested` sum, so
+/// advancing `total_filled` fills them strictly in order without visiting each one.
+/// Seeds: [b"withdrawal_queue", vault]
+#[account]
+pub struct WithdrawalQueue {
+    pub vault: Pubkey,
+    pub total_requested: u64, // cumulative amount of every ticket issued
+    pub total_filled: u64,    // cumulative amount set aside for tickets, in order
+    pub escrowed: u64,        // filled but not yet claimed
+    pub ticket_count: u64,
+    pub bump: u8,
+
+    // Reserved for future upgrades
+    pub reserved: [u8; 32],
+}
+
+/// A depositor's place in the queue, holding the shares it locked. Closed to the owner
+/// once fully claimed.
+/// Seeds: [b"withdrawal_ticket", queue, id]
+#[account]
+pub struct WithdrawalTicket {
+    pub queue: Pubkey,
+    pub supply_position: Pubkey,
+    pub owner: Pubkey,
+    pub id: u64,
+    pub amount: u64,
+    pub start: u64, // `total_requested` when the ticket was issued
+    pub claimed: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl WithdrawalQueue {
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 1 + 32;
+
+    pub fn pending(&self) -> u64 {
+        self.total_requested - self.total_filled
+    }
+
+    /// Set aside the vault's free liquidity for pending tickets. The filled amount
+    /// leaves `total_assets`, so it can no longer be borrowed. Returns the amount filled.
+    pub fn fill(&mut self, vault: &mut Vault) -> u64 {
+        let liquidity = vault.total_assets.saturating_sub(vault.total_liabilities);
+        let filled = self.pending().min(liquidity);
+        self.total_filled += filled;
+        self.escrowed += filled;
+        vault.total_assets -= filled;
+        filled
+    }
+
+    /// Amount of `ticket` filled and not yet claimed.
+    pub fn claimable(&self, ticket: &WithdrawalTicket) -> u64 {
+        let filled = self.total_filled.saturating_sub(ticket.start).min(ticket.amount);
+        filled - ticket.claimed
+    }
+}
+
+impl WithdrawalTicket {
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    pub fn is_settled(&self) -> bool {
+        self.claimed == self.amount
+    }
+}
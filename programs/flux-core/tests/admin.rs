@@ -0,0 +1,347 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::system_program;
+use flux_core::errors::FluxError;
+use flux_core::events::VaultConfigChangedEvent;
+use flux_core::state::config::{pause_flags, ProtocolConfig};
+use flux_core::state::governance::PendingConfigChange;
+use flux_core::state::vault::{config_fields, Vault, VaultConfig, VaultConfigUpdate};
+
+use common::*;
+use fixtures::*;
+
+fn init_protocol_config(bank: &mut Bank, program_data: Pubkey, signer: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::InitProtocolConfig {
+            protocol_config: protocol_config_address(),
+            program: flux_core::ID,
+            program_data,
+            admin: *signer,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitProtocolConfig {
+            guardian: Pubkey::new_unique(),
+            risk_manager: Pubkey::new_unique(),
+            fee_manager: Pubkey::new_unique(),
+            insurance_fund: Pubkey::new_unique(),
+        },
+    ))
+}
+
+#[test]
+fn init_protocol_config_requires_upgrade_authority() {
+    let mut bank = bank();
+    let deployer = bank.funded_key();
+    let attacker = bank.funded_key();
+    let program_data = bank.set_upgrade_authority(flux_core::ID, Some(deployer));
+
+    assert_eq!(init_protocol_config(&mut bank, program_data, &attacker), Err(err(FluxError::UnauthorizedAccess)));
+    assert!(!bank.exists(&protocol_config_address()));
+
+    init_protocol_config(&mut bank, program_data, &deployer).unwrap();
+    let config: ProtocolConfig = bank.read(&protocol_config_address());
+    assert_eq!(config.admin, deployer);
+}
+
+#[test]
+fn init_protocol_config_rejects_foreign_program_data() {
+    let mut bank = bank();
+    let deployer = bank.funded_key();
+    let attacker = bank.funded_key();
+    bank.set_upgrade_authority(flux_core::ID, Some(deployer));
+
+    // Program data naming the attacker, but not the one flux-core points at
+    let mut forged = 3u32.to_le_bytes().to_vec();
+    forged.extend_from_slice(&0u64.to_le_bytes());
+    forged.push(1);
+    forged.extend_from_slice(attacker.as_ref());
+    let forged_key = Pubkey::new_unique();
+    bank.set_account(forged_key, TestAccount {
+        lamports: 1,
+        owner: bpf_loader_upgradeable::ID,
+        data: forged,
+        executable: false,
+    });
+
+    assert_eq!(init_protocol_config(&mut bank, forged_key, &attacker), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn init_protocol_config_fails_once_immutable() {
+    let mut bank = bank();
+    let deployer = bank.funded_key();
+    let program_data = bank.set_upgrade_authority(flux_core::ID, None);
+
+    assert_eq!(init_protocol_config(&mut bank, program_data, &deployer), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn pause_flags_reject_unknown_bits() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+
+    let unknown = !pause_flags::ALL;
+    assert_eq!(pause(&mut bank, &protocol, &protocol.guardian, unknown), Err(err(FluxError::InvalidPauseFlags)));
+    assert_eq!(
+        pause(&mut bank, &protocol, &protocol.admin, pause_flags::DEPOSITS | 1 << 15),
+        Err(err(FluxError::InvalidPauseFlags))
+    );
+    pause(&mut bank, &protocol, &protocol.guardian, pause_flags::ALL).unwrap();
+}
+
+#[test]
+fn guardian_pauses_and_only_admin_unpauses() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let paused = |bank: &Bank| bank.read::<ProtocolConfig>(&protocol.config).paused;
+
+    assert_eq!(
+        pause(&mut bank, &protocol, &protocol.risk_manager, pause_flags::BORROWS),
+        Err(err(FluxError::UnauthorizedAccess))
+    );
+    pause(&mut bank, &protocol, &protocol.guardian, pause_flags::BORROWS).unwrap();
+    pause(&mut bank, &protocol, &protocol.guardian, pause_flags::BORROWS | pause_flags::TRANSFERS).unwrap();
+    assert_eq!(paused(&bank), pause_flags::BORROWS | pause_flags::TRANSFERS);
+
+    // Dropping any bit is an unpause, even while adding another
+    assert_eq!(
+        pause(&mut bank, &protocol, &protocol.guardian, pause_flags::TRANSFERS | pause_flags::UNLOADS),
+        Err(err(FluxError::UnauthorizedAccess))
+    );
+    pause(&mut bank, &protocol, &protocol.admin, pause_flags::TRANSFERS).unwrap();
+    assert_eq!(paused(&bank), pause_flags::TRANSFERS);
+}
+
+/// A borrower with collateral and debt who also supplies liquidity, in a vault with a withdrawal queue.
+struct PauseEnv {
+    protocol: Protocol,
+    market: Market,
+    owner: Pubkey,
+}
+
+fn pause_env() -> (Bank, PauseEnv) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+    init_withdrawal_queue(&mut bank, &protocol, &market).unwrap();
+    let owner = init_borrower(&mut bank, &market, 10 * SOL);
+    borrow(&mut bank, &market, &owner, 5 * SOL).unwrap();
+    supply(&mut bank, &market, &owner, SOL).unwrap();
+    bank.warp(60);
+    (bank, PauseEnv { protocol, market, owner })
+}
+
+type Operation = fn(&mut Bank, &PauseEnv) -> TxResult;
+
+/// One instruction per gated operation, with the flag that gates it.
+fn gated_operations() -> [(u16, &'static str, Operation); 7] {
+    [
+        (pause_flags::DEPOSITS, "fetch_assets", |bank, env| fetch(bank, &env.market, SOL)),
+        (pause_flags::DEPOSITS, "deposit_collateral", |bank, env| deposit_collateral(bank, &env.market, &env.owner, SOL)),
+        (pause_flags::WITHDRAWALS, "request_withdrawal", |bank, env| request_withdrawal(bank, &env.market, &env.owner, 1)),
+        (pause_flags::BORROWS, "borrow", |bank, env| borrow(bank, &env.market, &env.owner, 1)),
+        (pause_flags::LIQUIDATIONS, "liquidate_position", |bank, env| {
+            let liquidator = bank.funded_key();
+            liquidate(bank, &env.market, &env.owner, &liquidator)
+        }),
+        (pause_flags::TRANSFERS, "xfer_funds", |bank, env| xfer(bank, &env.protocol, &env.owner, 1)),
+        (pause_flags::UNLOADS, "unload_vault", |bank, env| unload(bank, &env.market)),
+    ]
+}
+
+#[test]
+fn each_pause_flag_blocks_only_its_operations() {
+    let flags = [
+        pause_flags::DEPOSITS,
+        pause_flags::WITHDRAWALS,
+        pause_flags::BORROWS,
+        pause_flags::LIQUIDATIONS,
+        pause_flags::TRANSFERS,
+        pause_flags::UNLOADS,
+    ];
+    for flag in flags {
+        let (mut bank, env) = pause_env();
+        pause(&mut bank, &env.protocol, &env.protocol.guardian, flag).unwrap();
+        for (gate, name, operation) in gated_operations() {
+            let result = operation(&mut bank, &env);
+            if gate == flag {
+                assert_eq!(result, Err(err(FluxError::ProtocolPaused)), "{name} under {flag:#b}");
+            } else {
+                assert_ne!(result, Err(err(FluxError::ProtocolPaused)), "{name} under {flag:#b}");
+            }
+        }
+
+        pause(&mut bank, &env.protocol, &env.protocol.admin, 0).unwrap();
+        for (_, name, operation) in gated_operations().into_iter().filter(|(gate, ..)| *gate == flag) {
+            assert_ne!(operation(&mut bank, &env), Err(err(FluxError::ProtocolPaused)), "{name} after unpause");
+        }
+    }
+}
+
+#[test]
+fn config_proposals_are_role_gated() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+    let outsider = bank.funded_key();
+    let risk = VaultConfigUpdate { risk_factor: Some(500), ..Default::default() };
+    let fees = VaultConfigUpdate { management_fee_rate: Some(100), ..Default::default() };
+
+    assert_eq!(propose_config(&mut bank, &market, &outsider, risk), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(propose_config(&mut bank, &market, &protocol.admin, risk), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(propose_config(&mut bank, &market, &protocol.risk_manager, fees), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(propose_config(&mut bank, &market, &protocol.fee_manager, risk), Err(err(FluxError::UnauthorizedAccess)));
+    assert!(!bank.exists(&pending_config_address(&market)));
+
+    propose_config(&mut bank, &market, &protocol.risk_manager, risk).unwrap();
+    let pending: PendingConfigChange = bank.read(&pending_config_address(&market));
+    assert_eq!((pending.proposer, pending.update), (protocol.risk_manager, risk));
+}
+
+#[test]
+fn config_changes_wait_for_the_timelock() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+    let debt_oracle = Pubkey::new_unique();
+    let update = VaultConfigUpdate { risk_factor: Some(500), debt_oracle: Some(debt_oracle), ..Default::default() };
+    propose_config(&mut bank, &market, &protocol.risk_manager, update).unwrap();
+
+    bank.warp(Vault::CONFIG_TIMELOCK - 1);
+    assert_eq!(execute_config(&mut bank, &market, &protocol.risk_manager), Err(err(FluxError::TimelockNotElapsed)));
+
+    bank.warp(1);
+    execute_config(&mut bank, &market, &protocol.risk_manager).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.risk_factor, state.debt_oracle, state.last_config_change), (500, debt_oracle, bank.now));
+    assert!(!bank.exists(&pending_config_address(&market)));
+
+    // A debt oracle can be replaced but never cleared
+    let cleared = VaultConfigUpdate { debt_oracle: Some(Pubkey::default()), ..Default::default() };
+    assert_eq!(propose_config(&mut bank, &market, &protocol.risk_manager, cleared), Err(err(FluxError::InvalidVaultConfig)));
+}
+
+#[test]
+fn executed_changes_emit_the_changed_fields() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+    let before = vault(&bank, &market).config();
+    let debt_oracle = Pubkey::new_unique();
+    let update = VaultConfigUpdate {
+        risk_factor: Some(500),
+        max_leverage: Some(2),
+        borrow_cap: Some(50 * SOL),
+        debt_oracle: Some(debt_oracle),
+        // Unchanged values are not reported
+        collateral_ratio: Some(before.collateral_ratio),
+        ..Default::default()
+    };
+    propose_config(&mut bank, &market, &protocol.risk_manager, update).unwrap();
+    bank.warp(Vault::CONFIG_TIMELOCK);
+    execute_config(&mut bank, &market, &protocol.risk_manager).unwrap();
+
+    let event: VaultConfigChangedEvent = bank.event().expect("config change event");
+    assert_eq!((event.vault, event.proposer, event.timestamp), (market.vault, protocol.risk_manager, bank.now));
+    assert_eq!(
+        event.changed_fields,
+        config_fields::RISK_FACTOR | config_fields::MAX_LEVERAGE | config_fields::BORROW_CAP | config_fields::DEBT_ORACLE
+    );
+    assert_eq!(event.previous, before);
+    let expected = VaultConfig { risk_factor: 500, max_leverage: 2, borrow_cap: 50 * SOL, debt_oracle, ..before };
+    assert_eq!(event.current, expected);
+    assert_eq!(event.current, vault(&bank, &market).config());
+}
+
+#[test]
+fn config_changes_respect_the_cooldown() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+    let update = VaultConfigUpdate { risk_factor: Some(500), ..Default::default() };
+    propose_config(&mut bank, &market, &protocol.risk_manager, update).unwrap();
+    bank.warp(Vault::CONFIG_TIMELOCK);
+
+    // The timelock outlasts the cooldown, so only a change applied since proposing hits it
+    let applied = bank.now - Vault::CONFIG_COOLDOWN + 1;
+    edit_vault(&mut bank, &market, |vault| vault.last_config_change = applied);
+    assert_eq!(execute_config(&mut bank, &market, &protocol.risk_manager), Err(err(FluxError::ConfigCooldown)));
+    bank.warp(1);
+    execute_config(&mut bank, &market, &protocol.risk_manager).unwrap();
+    assert_eq!(vault(&bank, &market).risk_factor, 500);
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+
+    let risk_factor = VaultConfigUpdate { risk_factor: Some(VaultConfig::MAX_RISK_FACTOR + 1), ..Default::default() };
+    assert_eq!(propose_config(&mut bank, &market, &protocol.risk_manager, risk_factor), Err(err(FluxError::InvalidRiskFactor)));
+
+    for update in [
+        // Below 100% collateralization, even with the liquidation ratio lowered to match
+        VaultConfigUpdate { collateral_ratio: Some(9999), min_collateral_ratio: Some(9999), ..Default::default() },
+        // The liquidator's bonus must stay below the penalty it is paid from
+        VaultConfigUpdate { liquidation_bonus: Some(800), ..Default::default() },
+        VaultConfigUpdate { liquidation_bonus: Some(900), ..Default::default() },
+    ] {
+        assert_eq!(propose_config(&mut bank, &market, &protocol.risk_manager, update), Err(err(FluxError::InvalidVaultConfig)));
+    }
+    assert!(!bank.exists(&pending_config_address(&market)));
+
+    // Validated again at execution, against the config current at that point
+    let bonus = VaultConfigUpdate { liquidation_bonus: Some(700), ..Default::default() };
+    propose_config(&mut bank, &market, &protocol.risk_manager, bonus).unwrap();
+    edit_vault(&mut bank, &market, |vault| vault.liquidation_penalty = 700);
+    bank.warp(Vault::CONFIG_TIMELOCK);
+    assert_eq!(execute_config(&mut bank, &market, &protocol.risk_manager), Err(err(FluxError::InvalidVaultConfig)));
+}
+
+fn migrate_vault(bank: &mut Bank, market: &Market, admin: &Pubkey, total_collateral: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::MigrateVault {
+            vault: market.vault,
+            protocol_config: protocol_config_address(),
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::MigrateVault { total_collateral, debt_oracle: market.debt_feed },
+    ))
+}
+
+#[test]
+fn migrate_vault_grows_a_legacy_vault() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    init_borrower(&mut bank, &market, SOL / 10);
+    let current = vault(&bank, &market);
+
+    // Before the split, collateral was counted in `total_assets` and the layout ended
+    // at `has_withdrawal_queue` plus 3 reserved bytes
+    let mut legacy = current.clone();
+    legacy.total_assets += legacy.total_collateral;
+    let mut data = Vec::new();
+    legacy.try_serialize(&mut data).unwrap();
+    data.truncate(Vault::LEGACY_LEN - 3);
+    data.extend_from_slice(&[0; 3]);
+    let legacy_rent = Rent::default().minimum_balance(Vault::LEGACY_LEN);
+    bank.set_account(market.vault, TestAccount { lamports: legacy_rent, owner: flux_core::ID, data, executable: false });
+
+    assert_eq!(migrate_vault(&mut bank, &market, &market.authority, SOL / 10), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(
+        migrate_vault(&mut bank, &market, &protocol.admin, legacy.total_assets + 1),
+        Err(err(FluxError::InvalidAmount))
+    );
+    migrate_vault(&mut bank, &market, &protocol.admin, SOL / 10).unwrap();
+
+    let account = bank.account(&market.vault).unwrap();
+    assert_eq!(account.data.len(), Vault::LEN);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(Vault::LEN));
+    let migrated = vault(&bank, &market);
+    assert_eq!((migrated.total_assets, migrated.total_collateral), (current.total_assets, SOL / 10));
+    assert_eq!((migrated.debt_oracle, migrated.oracle_config), (market.debt_feed, market.collateral_feed));
+    assert_eq!((migrated.authority, migrated.has_withdrawal_queue), (current.authority, current.has_withdrawal_queue));
+
+    // Already at the current size
+    assert_eq!(migrate_vault(&mut bank, &market, &protocol.admin, 0), Err(err(FluxError::InvalidVaultLayout)));
+}
@@ -0,0 +1,114 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use flux_core::errors::FluxError;
+use flux_core::state::governance::PendingAuthority;
+
+use common::*;
+use fixtures::*;
+
+fn pending_address(market: &Market) -> Pubkey {
+    pda(&[b"pending_authority", market.vault.as_ref()], &flux_core::ID)
+}
+
+fn propose(bank: &mut Bank, market: &Market, authority: &Pubkey, new_authority: Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ProposeAuthority {
+            vault: market.vault,
+            pending_authority: pending_address(market),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::ProposeAuthority { new_authority },
+    ))
+}
+
+fn accept(bank: &mut Bank, market: &Market, proposer: &Pubkey, signer: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::AcceptAuthority {
+            vault: market.vault,
+            pending_authority: pending_address(market),
+            proposer: *proposer,
+            new_authority: *signer,
+        },
+        flux_core::instruction::AcceptAuthority {},
+    ))
+}
+
+fn cancel(bank: &mut Bank, market: &Market, authority: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::CancelAuthorityTransfer {
+            vault: market.vault,
+            pending_authority: pending_address(market),
+            authority: *authority,
+        },
+        flux_core::instruction::CancelAuthorityTransfer {},
+    ))
+}
+
+#[test]
+fn only_the_vault_authority_proposes_a_valid_successor() {
+    let mut bank = bank();
+    let market = init_market(&mut bank);
+    let outsider = bank.funded_key();
+
+    assert_eq!(propose(&mut bank, &market, &outsider, outsider), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(propose(&mut bank, &market, &market.authority, market.authority), Err(err(FluxError::InvalidAuthority)));
+    assert_eq!(propose(&mut bank, &market, &market.authority, Pubkey::default()), Err(err(FluxError::InvalidAuthority)));
+
+    let successor = bank.funded_key();
+    propose(&mut bank, &market, &market.authority, successor).unwrap();
+    let pending: PendingAuthority = bank.read(&pending_address(&market));
+    assert_eq!((pending.vault, pending.proposer, pending.new_authority), (market.vault, market.authority, successor));
+    assert_eq!(vault(&bank, &market).authority, market.authority);
+}
+
+#[test]
+fn only_the_proposed_key_accepts() {
+    let mut bank = bank();
+    let market = init_market(&mut bank);
+    let successor = bank.funded_key();
+    let outsider = bank.funded_key();
+    propose(&mut bank, &market, &market.authority, successor).unwrap();
+    let rent = bank.lamports(&pending_address(&market));
+    let before = bank.lamports(&market.authority);
+
+    assert_eq!(accept(&mut bank, &market, &market.authority, &outsider), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(accept(&mut bank, &market, &market.authority, &market.authority), Err(err(FluxError::UnauthorizedAccess)));
+    // The rent goes back to the proposer, not whoever is named
+    assert_eq!(
+        accept(&mut bank, &market, &outsider, &successor),
+        Err(err(anchor_lang::error::ErrorCode::ConstraintHasOne))
+    );
+
+    accept(&mut bank, &market, &market.authority, &successor).unwrap();
+    assert_eq!(vault(&bank, &market).authority, successor);
+    assert!(!bank.exists(&pending_address(&market)));
+    assert_eq!(bank.lamports(&market.authority), before + rent);
+
+    // The previous authority is now an outsider
+    assert_eq!(propose(&mut bank, &market, &market.authority, outsider), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn only_the_vault_authority_cancels() {
+    let mut bank = bank();
+    let market = init_market(&mut bank);
+    let successor = bank.funded_key();
+    propose(&mut bank, &market, &market.authority, successor).unwrap();
+
+    assert_eq!(cancel(&mut bank, &market, &successor), Err(err(FluxError::UnauthorizedAccess)));
+    cancel(&mut bank, &market, &market.authority).unwrap();
+    assert!(!bank.exists(&pending_address(&market)));
+
+    assert_eq!(
+        accept(&mut bank, &market, &market.authority, &successor),
+        Err(err(anchor_lang::error::ErrorCode::AccountNotInitialized))
+    );
+    assert_eq!(vault(&bank, &market).authority, market.authority);
+
+    // A fresh proposal can follow the cancelled one
+    propose(&mut bank, &market, &market.authority, successor).unwrap();
+}
@@ -0,0 +1,240 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use flux_core::circuit_breaker::{CircuitBreakerParams, FreezeReason};
+use flux_core::errors::FluxError;
+use flux_core::state::vault::{Vault, VaultConfigUpdate};
+
+use common::*;
+use fixtures::*;
+
+fn update_config(market: &Market, authority: &Pubkey) -> flux_core::accounts::UpdateConfig {
+    flux_core::accounts::UpdateConfig {
+        vault: market.vault,
+        protocol_config: protocol_config_address(),
+        authority: *authority,
+    }
+}
+
+fn set_breaker(bank: &mut Bank, market: &Market, authority: &Pubkey, params: CircuitBreakerParams) -> TxResult {
+    bank.process(ix(update_config(market, authority), flux_core::instruction::SetCircuitBreaker { params }))
+}
+
+fn reset_breaker(bank: &mut Bank, market: &Market, authority: &Pubkey) -> TxResult {
+    bank.process(ix(update_config(market, authority), flux_core::instruction::ResetCircuitBreaker {}))
+}
+
+fn price_history_address(market: &Market) -> Pubkey {
+    pda(&[b"price_history", market.vault.as_ref()], &flux_core::ID)
+}
+
+fn init_price_history(bank: &mut Bank, market: &Market) {
+    let payer = bank.funded_key();
+    bank.process(ix(
+        flux_core::accounts::InitPriceHistory {
+            vault: market.vault,
+            price_history: price_history_address(market),
+            payer,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitPriceHistory {},
+    ))
+    .expect("init_price_history");
+}
+
+fn refresh_risk(bank: &mut Bank, market: &Market, feed: Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::RefreshRisk {
+            vault: market.vault,
+            price_history: price_history_address(market),
+            price_feed: feed,
+        },
+        flux_core::instruction::RefreshRisk {},
+    ))
+}
+
+fn freeze_reason(bank: &Bank, market: &Market) -> Option<FreezeReason> {
+    let state = vault(bank, market);
+    state.is_frozen.then(|| FreezeReason::from(state.freeze_reason))
+}
+
+/// 10% net outflow per hour, 10% price move between reads.
+const PARAMS: CircuitBreakerParams = CircuitBreakerParams {
+    max_outflow_bps: 1000,
+    outflow_window: 3600,
+    max_price_move_bps: 1000,
+};
+
+/// 100 SOL of liquidity and two borrowers with 10 SOL of collateral each, then breakers set to `PARAMS`.
+fn setup() -> (Bank, Protocol, Market, [Pubkey; 2]) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let owners = [init_borrower(&mut bank, &market, 10 * SOL), init_borrower(&mut bank, &market, 10 * SOL)];
+    set_breaker(&mut bank, &market, &protocol.risk_manager, PARAMS).unwrap();
+    (bank, protocol, market, owners)
+}
+
+#[test]
+fn breakers_are_set_by_the_risk_manager_within_bounds() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+
+    for authority in [protocol.admin, protocol.guardian, market.authority] {
+        assert_eq!(set_breaker(&mut bank, &market, &authority, PARAMS), Err(err(FluxError::UnauthorizedAccess)));
+    }
+    for params in [
+        CircuitBreakerParams { max_outflow_bps: 10001, ..PARAMS },
+        CircuitBreakerParams { max_price_move_bps: 10001, ..PARAMS },
+        CircuitBreakerParams { outflow_window: 0, ..PARAMS },
+        CircuitBreakerParams { outflow_window: CircuitBreakerParams::MAX_WINDOW + 1, ..PARAMS },
+    ] {
+        let result = set_breaker(&mut bank, &market, &protocol.risk_manager, params);
+        assert_eq!(result, Err(err(FluxError::InvalidVaultConfig)));
+    }
+
+    set_breaker(&mut bank, &market, &protocol.risk_manager, PARAMS).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.max_outflow_bps, state.outflow_window, state.max_price_move_bps), (1000, 3600, 1000));
+    assert_eq!((state.window_start, state.window_start_assets), (bank.now, 0));
+}
+
+#[test]
+fn net_outflow_over_the_window_freezes_the_vault() {
+    let (mut bank, _, market, [owner, other]) = setup();
+
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    // Repayments offset outflow within the window
+    repay(&mut bank, &market, &owner, 2 * SOL).unwrap();
+    borrow(&mut bank, &market, &other, 6 * SOL).unwrap();
+
+    // 12 SOL out net of 2 SOL in is exactly the limit; one more lamport trips it
+    borrow(&mut bank, &market, &other, 1).unwrap();
+    assert_eq!(freeze_reason(&bank, &market), Some(FreezeReason::Outflow));
+    assert!(bank.logs_contain("CIRCUIT BREAKER"));
+    // The tripping borrow moved nothing
+    assert_eq!(position(&bank, &market, &other).debt_amount, 6 * SOL);
+    assert_eq!(borrow(&mut bank, &market, &owner, 1), Err(err(FluxError::VaultFrozen)));
+}
+
+#[test]
+fn the_outflow_window_rolls_over() {
+    let (mut bank, _, market, [owner, other]) = setup();
+
+    borrow(&mut bank, &market, &owner, 10 * SOL).unwrap();
+    bank.warp(PARAMS.outflow_window as i64);
+    borrow(&mut bank, &market, &other, 5 * SOL).unwrap();
+
+    assert_eq!(freeze_reason(&bank, &market), None);
+    assert_eq!(vault(&bank, &market).window_start, bank.now);
+}
+
+#[test]
+fn price_moves_are_measured_on_the_vaults_oracle_only() {
+    let (mut bank, _, market, [owner, _]) = setup();
+    init_price_history(&mut bank, &market);
+
+    // The first read anchors the price
+    refresh_risk(&mut bank, &market, market.collateral_feed).unwrap();
+    assert_eq!(vault(&bank, &market).last_oracle_price, COLLATERAL_PRICE);
+
+    // A foreign feed can neither move the anchor nor trip the breaker
+    let crashed = price_feed(&mut bank, COLLATERAL_PRICE / 10, 6);
+    bank.warp(Vault::RISK_REFRESH_INTERVAL);
+    assert_eq!(refresh_risk(&mut bank, &market, crashed), Err(err(FluxError::OracleMismatch)));
+    let mut accounts = manage_debt(&bank, &market, &owner);
+    accounts.price_feed_collateral = crashed;
+    let result = bank.process(ix(accounts, flux_core::instruction::Borrow { amount: SOL }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+    let state = vault(&bank, &market);
+    assert_eq!((state.is_frozen, state.last_oracle_price), (false, COLLATERAL_PRICE));
+
+    // A 10% move is tolerated and re-anchors
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 9 / 10, 6);
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(vault(&bank, &market).last_oracle_price, COLLATERAL_PRICE * 9 / 10);
+
+    // 20% off the new anchor is not
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 72 / 100, 6);
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(freeze_reason(&bank, &market), Some(FreezeReason::PriceMove));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, SOL);
+}
+
+#[test]
+fn replacing_the_oracle_restarts_the_price_anchor() {
+    let (mut bank, protocol, market, [owner, _]) = setup();
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(vault(&bank, &market).last_oracle_price, COLLATERAL_PRICE);
+
+    // The new feed quotes in other units; it must not be compared with the old one
+    let feed = price_feed(&mut bank, COLLATERAL_PRICE * 1000, 9);
+    let update = VaultConfigUpdate { oracle_config: Some(feed), ..Default::default() };
+    propose_config(&mut bank, &market, &protocol.risk_manager, update).unwrap();
+    bank.warp(Vault::CONFIG_TIMELOCK);
+    execute_config(&mut bank, &market, &protocol.risk_manager).unwrap();
+    assert_eq!(vault(&bank, &market).last_oracle_price, 0);
+
+    let market = Market { collateral_feed: feed, ..market };
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.is_frozen, state.last_oracle_price), (false, COLLATERAL_PRICE * 1000));
+}
+
+#[test]
+fn insolvency_freezes_the_vault_on_the_next_operation() {
+    let (mut bank, _, market, [owner, _]) = setup();
+    edit_vault(&mut bank, &market, |vault| vault.total_liabilities = vault.total_assets + 1);
+
+    deposit_collateral(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(freeze_reason(&bank, &market), Some(FreezeReason::Insolvent));
+    assert_eq!(position(&bank, &market, &owner).collateral_amount, 10 * SOL);
+}
+
+#[test]
+fn interest_accrued_at_full_utilization_is_not_insolvency() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 10 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL);
+    set_breaker(&mut bank, &market, &protocol.risk_manager, CircuitBreakerParams { max_outflow_bps: 0, ..PARAMS }).unwrap();
+    borrow(&mut bank, &market, &owner, 10 * SOL).unwrap();
+
+    // A year of interest on every lamport lent out
+    bank.warp(365 * 24 * 60 * 60);
+    deposit_collateral(&mut bank, &market, &owner, SOL).unwrap();
+    let state = vault(&bank, &market);
+    assert!(state.total_liabilities > 10 * SOL);
+    assert_eq!(state.total_assets, state.total_liabilities);
+    assert_eq!(freeze_reason(&bank, &market), None);
+
+    bank.warp(365 * 24 * 60 * 60);
+    repay(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(freeze_reason(&bank, &market), None);
+}
+
+#[test]
+fn only_the_guardian_clears_an_automatic_freeze() {
+    let (mut bank, protocol, market, [owner, other]) = setup();
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    borrow(&mut bank, &market, &other, 4 * SOL + 1).unwrap();
+    assert_eq!(freeze_reason(&bank, &market), Some(FreezeReason::Outflow));
+
+    // The admin's manual unfreeze does not apply to breaker trips
+    let result = bank.process(ix(update_config(&market, &protocol.admin), flux_core::instruction::EmergencyUnfreeze {}));
+    assert_eq!(result, Err(err(FluxError::CircuitBreakerTripped)));
+    for authority in [protocol.admin, protocol.risk_manager, market.authority] {
+        assert_eq!(reset_breaker(&mut bank, &market, &authority), Err(err(FluxError::UnauthorizedAccess)));
+    }
+
+    bank.warp(60);
+    reset_breaker(&mut bank, &market, &protocol.guardian).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!(freeze_reason(&bank, &market), None);
+    assert_eq!((state.window_start, state.window_start_assets, state.window_outflow), (bank.now, 100 * SOL, 0));
+    borrow(&mut bank, &market, &other, SOL).unwrap();
+
+    // A manual freeze is not the guardian's to reset
+    bank.process(ix(update_config(&market, &protocol.guardian), flux_core::instruction::EmergencyFreeze {})).unwrap();
+    assert_eq!(reset_breaker(&mut bank, &market, &protocol.guardian), Err(err(FluxError::CircuitBreakerNotTripped)));
+}
@@ -0,0 +1,504 @@
+//! In-process test harness: runs program entrypoints against an in-memory account set.
+//!
+//! Accounts are laid out the way the runtime serializes them, so `realloc`, `assign` and
+//! `close` behave as on-chain. Syscall stubs serve the clock, rent, return data and events, and
+//! execute CPIs into the system program and into added programs. Each `process` call is one transaction: a failing
+//! instruction discards every change made by the transaction.
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::Once;
+
+use anchor_lang::solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable,
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_stubs::{self, SyscallStubs},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program,
+    sysvar::instructions::{construct_instructions_data, store_current_index, BorrowedAccountMeta, BorrowedInstruction},
+    sysvar,
+};
+use anchor_lang::__private::bytemuck;
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorDeserialize, Discriminator};
+
+pub type TxResult = Result<(), ProgramError>;
+
+pub type Entry = for<'a> fn(&Pubkey, &'a [AccountInfo<'a>], &[u8]) -> ProgramResult;
+
+pub const SOL: u64 = 1_000_000_000;
+pub const START_TIME: i64 = 1_700_000_000;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TestAccount {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+pub struct Bank {
+    accounts: HashMap<Pubkey, TestAccount>,
+    programs: HashMap<Pubkey, Entry>,
+    pub now: i64,
+    pub slot: u64,
+    pub return_data: Option<(Pubkey, Vec<u8>)>,
+    pub logs: Vec<String>,
+    pub events: Vec<Vec<u8>>,
+}
+
+thread_local! {
+    static CLOCK: Cell<(i64, u64)> = const { Cell::new((0, 0)) };
+    static PROGRAM: Cell<Pubkey> = const { Cell::new(Pubkey::new_from_array([0; 32])) };
+    static RETURN_DATA: RefCell<Option<(Pubkey, Vec<u8>)>> = const { RefCell::new(None) };
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    static EVENTS: RefCell<Vec<Vec<u8>>> = const { RefCell::new(Vec::new()) };
+    static PROGRAMS: RefCell<HashMap<Pubkey, Entry>> = RefCell::new(HashMap::new());
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|logs| logs.borrow_mut().push(message.to_string()));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        EVENTS.with(|events| events.borrow_mut().push(fields.concat()));
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let (unix_timestamp, slot) = CLOCK.with(Cell::get);
+        let clock = Clock { slot, unix_timestamp, ..Clock::default() };
+        unsafe { std::ptr::write_unaligned(var_addr as *mut Clock, clock) };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { std::ptr::write_unaligned(var_addr as *mut Rent, Rent::default()) };
+        SUCCESS
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        let program = PROGRAM.with(Cell::get);
+        RETURN_DATA.with(|cell| *cell.borrow_mut() = Some((program, data.to_vec())));
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.with(|cell| cell.borrow().clone())
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id != system_program::ID {
+            return invoke_program(instruction, account_infos, signers_seeds);
+        }
+        let info = |index: usize| -> Result<&AccountInfo, ProgramError> {
+            let key = instruction.accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)?.pubkey;
+            account_infos.iter().find(|info| *info.key == key).ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let instruction = limited_deserialize::<SystemInstruction>(&instruction.data, 1024)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        match instruction {
+            SystemInstruction::CreateAccount { lamports, space, owner } => {
+                let (from, to) = (info(0)?, info(1)?);
+                allocate(to, space as usize)?;
+                move_lamports(from, to, lamports)?;
+                to.assign(&owner);
+            }
+            SystemInstruction::Transfer { lamports } => move_lamports(info(0)?, info(1)?, lamports)?,
+            SystemInstruction::Allocate { space } => allocate(info(0)?, space as usize)?,
+            SystemInstruction::Assign { owner } => {
+                let account = info(0)?;
+                if *account.owner != system_program::ID {
+                    return Err(ProgramError::IncorrectProgramId);
+                }
+                account.assign(&owner);
+            }
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+        Ok(())
+    }
+}
+
+/// Run an added program's entrypoint over the caller's accounts. Signer privileges are
+/// those of the caller plus the PDAs it signs for.
+fn invoke_program(instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+    let entry = PROGRAMS.with(|programs| programs.borrow().get(&instruction.program_id).copied())
+        .ok_or(ProgramError::IncorrectProgramId)?;
+    let caller = PROGRAM.with(Cell::get);
+    let signed: Vec<Pubkey> = signers_seeds.iter()
+        .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+        .collect::<Result<_, _>>()
+        .map_err(|_| ProgramError::InvalidSeeds)?;
+
+    let mut infos = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let info = account_infos.iter().find(|info| *info.key == meta.pubkey).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if meta.is_signer && !info.is_signer && !signed.contains(&meta.pubkey) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if meta.is_writable && !info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+        infos.push(AccountInfo { is_signer: meta.is_signer, is_writable: meta.is_writable, ..info.clone() });
+    }
+
+    PROGRAM.with(|program| program.set(instruction.program_id));
+    // The callee only borrows the caller's buffers for the duration of the call
+    let infos: &[AccountInfo] = unsafe { std::mem::transmute::<&[AccountInfo], _>(infos.as_slice()) };
+    let result = entry(&instruction.program_id, infos, &instruction.data);
+    PROGRAM.with(|program| program.set(caller));
+    result
+}
+
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    let mut from = from.try_borrow_mut_lamports()?;
+    **from = from.checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+    let mut to = to.try_borrow_mut_lamports()?;
+    **to += lamports;
+    Ok(())
+}
+
+/// Allocate data for a fresh system-owned account; mirrors the system program's
+/// `AccountAlreadyInUse` (custom error 0) for accounts that are already set up.
+fn allocate(account: &AccountInfo, space: usize) -> ProgramResult {
+    if *account.owner != system_program::ID || !account.data_is_empty() {
+        return Err(ProgramError::Custom(0));
+    }
+    let mut data = account.try_borrow_mut_data()?;
+    if space > ACCOUNT_SLACK {
+        return Err(ProgramError::InvalidRealloc);
+    }
+    unsafe {
+        let ptr = data.as_mut_ptr();
+        *(ptr.offset(-8) as *mut u64) = space as u64;
+        *data = std::slice::from_raw_parts_mut(ptr, space);
+    }
+    data.fill(0);
+    Ok(())
+}
+
+// Serialized account: [pad 4][original_data_len u32][key][owner][lamports][data_len][data][slack]
+const KEY: usize = 8;
+const OWNER: usize = 40;
+const LAMPORTS: usize = 72;
+const DATA: usize = 88;
+const ACCOUNT_SLACK: usize = MAX_PERMITTED_DATA_INCREASE + 64 * 1024;
+
+struct Serialized {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl Serialized {
+    fn new(key: &Pubkey, account: &TestAccount) -> Self {
+        let len = account.data.len();
+        let mut words = vec![0u64; (DATA + len + ACCOUNT_SLACK).div_ceil(8)];
+        let bytes = unsafe { std::slice::from_raw_parts_mut(words.as_mut_ptr() as *mut u8, words.len() * 8) };
+        bytes[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        bytes[KEY..OWNER].copy_from_slice(key.as_ref());
+        bytes[OWNER..LAMPORTS].copy_from_slice(account.owner.as_ref());
+        bytes[LAMPORTS..LAMPORTS + 8].copy_from_slice(&account.lamports.to_le_bytes());
+        bytes[DATA - 8..DATA].copy_from_slice(&(len as u64).to_le_bytes());
+        bytes[DATA..DATA + len].copy_from_slice(&account.data);
+        Self { words, len }
+    }
+
+    fn base(&mut self) -> *mut u8 {
+        self.words.as_mut_ptr() as *mut u8
+    }
+
+    /// # Safety
+    /// The returned info borrows `self`'s buffer and must be dropped before it.
+    unsafe fn info(&mut self, is_signer: bool, is_writable: bool, executable: bool) -> AccountInfo<'static> {
+        let base = self.base();
+        AccountInfo::new(
+            &*(base.add(KEY) as *const Pubkey),
+            is_signer,
+            is_writable,
+            &mut *(base.add(LAMPORTS) as *mut u64),
+            std::slice::from_raw_parts_mut(base.add(DATA), self.len),
+            &*(base.add(OWNER) as *const Pubkey),
+            executable,
+            0,
+        )
+    }
+}
+
+static STUBS: Once = Once::new();
+
+impl Default for Bank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        STUBS.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(Stubs));
+        });
+        let mut bank = Self {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            now: START_TIME,
+            slot: 1,
+            return_data: None,
+            logs: Vec::new(),
+            events: Vec::new(),
+        };
+        bank.set_account(system_program::ID, TestAccount { lamports: 1, executable: true, ..TestAccount::default() });
+        bank
+    }
+
+    pub fn add_program(&mut self, program_id: Pubkey, entry: Entry) {
+        self.programs.insert(program_id, entry);
+        PROGRAMS.with(|programs| programs.borrow_mut().insert(program_id, entry));
+        self.set_account(program_id, TestAccount {
+            lamports: 1,
+            owner: bpf_loader_upgradeable::ID,
+            executable: true,
+            ..TestAccount::default()
+        });
+    }
+
+    /// Deploy `program_id` behind the upgradeable loader with `upgrade_authority`.
+    /// Returns the program data address.
+    pub fn set_upgrade_authority(&mut self, program_id: Pubkey, upgrade_authority: Option<Pubkey>) -> Pubkey {
+        let (program_data, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+        let mut program = 2u32.to_le_bytes().to_vec();
+        program.extend_from_slice(program_data.as_ref());
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        match upgrade_authority {
+            Some(key) => {
+                data.push(1);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.push(0),
+        }
+        let account = self.accounts.get_mut(&program_id).expect("program not added");
+        account.data = program;
+        self.set_account(program_data, TestAccount {
+            lamports: 1,
+            owner: bpf_loader_upgradeable::ID,
+            data,
+            executable: false,
+        });
+        program_data
+    }
+
+    pub fn set_account(&mut self, key: Pubkey, account: TestAccount) {
+        self.accounts.insert(key, account);
+    }
+
+    pub fn account(&self, key: &Pubkey) -> Option<&TestAccount> {
+        self.accounts.get(key)
+    }
+
+    pub fn remove_account(&mut self, key: &Pubkey) {
+        self.accounts.remove(key);
+    }
+
+    pub fn lamports(&self, key: &Pubkey) -> u64 {
+        self.accounts.get(key).map_or(0, |account| account.lamports)
+    }
+
+    pub fn fund(&mut self, key: &Pubkey, lamports: u64) {
+        self.accounts.entry(*key).or_default().lamports += lamports;
+    }
+
+    /// A fresh funded keypair address.
+    pub fn funded_key(&mut self) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.fund(&key, 100 * SOL);
+        key
+    }
+
+    /// Store an Anchor account (discriminator + Borsh) owned by `owner`, padded to `space`.
+    pub fn write<T: AccountSerialize>(&mut self, key: Pubkey, owner: Pubkey, value: &T, space: usize) {
+        let mut data = Vec::with_capacity(space);
+        value.try_serialize(&mut data).expect("serialize");
+        data.resize(space.max(data.len()), 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set_account(key, TestAccount { lamports, owner, data, executable: false });
+    }
+
+    /// Store a zero-copy account: discriminator followed by the raw struct bytes.
+    pub fn write_zero_copy<T: bytemuck::Pod + Discriminator>(&mut self, key: Pubkey, owner: Pubkey, value: &T, space: usize) {
+        let mut data = T::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(bytemuck::bytes_of(value));
+        data.resize(space.max(data.len()), 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        self.set_account(key, TestAccount { lamports, owner, data, executable: false });
+    }
+
+    pub fn read<T: AccountDeserialize>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).unwrap_or_else(|| panic!("missing account {key}"));
+        T::try_deserialize(&mut account.data.as_slice()).expect("deserialize")
+    }
+
+    pub fn read_zero_copy<T: bytemuck::Pod + Discriminator>(&self, key: &Pubkey) -> T {
+        let account = self.accounts.get(key).unwrap_or_else(|| panic!("missing account {key}"));
+        assert_eq!(account.data[..8], T::DISCRIMINATOR, "discriminator mismatch for {key}");
+        bytemuck::pod_read_unaligned(&account.data[8..8 + std::mem::size_of::<T>()])
+    }
+
+    pub fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.get(key).is_some_and(|account| account.lamports > 0)
+    }
+
+    pub fn warp(&mut self, seconds: i64) {
+        self.now += seconds;
+        self.slot += 1;
+    }
+
+    pub fn process(&mut self, instruction: Instruction) -> Result<(), ProgramError> {
+        self.process_transaction(&[instruction])
+    }
+
+    /// Run `instructions` as one atomic transaction.
+    pub fn process_transaction(&mut self, instructions: &[Instruction]) -> Result<(), ProgramError> {
+        let snapshot = self.accounts.clone();
+        self.return_data = None;
+        for (index, instruction) in instructions.iter().enumerate() {
+            if let Err(err) = self.execute(instruction, instructions, index) {
+                self.accounts = snapshot;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `instruction` as if invoked through CPI while `outer[outer_index]` executes;
+    /// the instructions sysvar shows the outer transaction.
+    pub fn process_as_cpi(&mut self, outer: &[Instruction], outer_index: usize, instruction: Instruction) -> Result<(), ProgramError> {
+        let snapshot = self.accounts.clone();
+        self.return_data = None;
+        let result = self.execute(&instruction, outer, outer_index);
+        if result.is_err() {
+            self.accounts = snapshot;
+        }
+        result
+    }
+
+    fn execute(&mut self, instruction: &Instruction, sysvar_view: &[Instruction], current_index: usize) -> Result<(), ProgramError> {
+        let entry = *self.programs.get(&instruction.program_id).ok_or(ProgramError::IncorrectProgramId)?;
+
+        if instruction.accounts.iter().any(|meta| meta.pubkey == sysvar::instructions::ID) {
+            let borrowed: Vec<BorrowedInstruction> = sysvar_view.iter().map(|ix| BorrowedInstruction {
+                program_id: &ix.program_id,
+                accounts: ix.accounts.iter().map(|meta| BorrowedAccountMeta {
+                    pubkey: &meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                }).collect(),
+                data: &ix.data,
+            }).collect();
+            let mut data = construct_instructions_data(&borrowed);
+            store_current_index(&mut data, current_index as u16);
+            self.set_account(sysvar::instructions::ID, TestAccount {
+                lamports: 1,
+                owner: sysvar::ID,
+                data,
+                executable: false,
+            });
+        }
+
+        // One serialized buffer per unique key; duplicates share it like in the runtime
+        let mut keys: Vec<Pubkey> = Vec::new();
+        let mut flags: HashMap<Pubkey, (bool, bool)> = HashMap::new();
+        for meta in &instruction.accounts {
+            let entry = flags.entry(meta.pubkey).or_insert_with(|| {
+                keys.push(meta.pubkey);
+                (false, false)
+            });
+            entry.0 |= meta.is_signer;
+            entry.1 |= meta.is_writable;
+        }
+        let mut buffers: Vec<Serialized> = keys.iter().map(|key| {
+            Serialized::new(key, self.accounts.get(key).unwrap_or(&TestAccount::default()))
+        }).collect();
+        let unique: Vec<AccountInfo<'static>> = keys.iter().zip(buffers.iter_mut()).map(|(key, buffer)| {
+            let (is_signer, is_writable) = flags[key];
+            let executable = self.accounts.get(key).is_some_and(|account| account.executable);
+            unsafe { buffer.info(is_signer, is_writable, executable) }
+        }).collect();
+        let infos: Vec<AccountInfo<'static>> = instruction.accounts.iter().map(|meta| {
+            let position = keys.iter().position(|key| *key == meta.pubkey).unwrap();
+            unique[position].clone()
+        }).collect();
+
+        CLOCK.with(|clock| clock.set((self.now, self.slot)));
+        PROGRAM.with(|program| program.set(instruction.program_id));
+        RETURN_DATA.with(|cell| *cell.borrow_mut() = None);
+        LOGS.with(|logs| logs.borrow_mut().clear());
+        EVENTS.with(|events| events.borrow_mut().clear());
+
+        // Leak-free: `infos` and `unique` are dropped below, before `buffers`
+        let infos: &'static [AccountInfo<'static>] = unsafe { std::mem::transmute::<&[AccountInfo<'static>], _>(infos.as_slice()) };
+        let result = entry(&instruction.program_id, infos, &instruction.data);
+
+        self.logs = LOGS.with(|logs| logs.borrow().clone());
+        self.events = EVENTS.with(|events| events.borrow().clone());
+        self.return_data = RETURN_DATA.with(|cell| cell.borrow_mut().take()).or(self.return_data.take());
+        result?;
+
+        for (key, info) in keys.iter().zip(unique.iter()) {
+            let lamports = **info.lamports.borrow();
+            let data = info.data.borrow().to_vec();
+            let owner = unsafe { std::ptr::read_volatile(info.owner as *const Pubkey) };
+            if lamports == 0 && *key != sysvar::instructions::ID {
+                self.accounts.remove(key);
+                continue;
+            }
+            let executable = info.executable;
+            self.accounts.insert(*key, TestAccount { lamports, owner, data, executable });
+        }
+        drop(unique);
+        drop(buffers);
+        Ok(())
+    }
+
+    /// Whether the last executed instruction logged a line containing `needle`.
+    pub fn logs_contain(&self, needle: &str) -> bool {
+        self.logs.iter().any(|line| line.contains(needle))
+    }
+
+    /// The last `T` event emitted by the last executed instruction.
+    pub fn event<T: AnchorDeserialize + Discriminator>(&self) -> Option<T> {
+        self.events.iter().rev()
+            .find(|data| data.starts_with(&T::DISCRIMINATOR))
+            .map(|data| T::deserialize(&mut &data[8..]).expect("deserialize event"))
+    }
+}
+
+/// `ProgramError` for an Anchor or program error code.
+pub fn err<E: Into<u32>>(error: E) -> ProgramError {
+    ProgramError::Custom(error.into())
+}
+
+pub fn pda(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+/// Mark `key` as a signer in `metas`; used to build instructions for keys that sign as a
+/// non-first account.
+pub fn signer(mut metas: Vec<AccountMeta>, key: &Pubkey) -> Vec<AccountMeta> {
+    for meta in metas.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = true;
+    }
+    metas
+}
@@ -0,0 +1,251 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use flux_core::errors::FluxError;
+use flux_core::state::compliance::{CaseAction, CaseRecord, ComplianceConfig, TransferRecord, TravelRuleInfo};
+use flux_core::state::config::ProtocolRoles;
+use flux_core::state::user_profile::UserRole;
+
+use common::*;
+use fixtures::*;
+
+fn case_address(subject: &Pubkey, sequence: u32) -> Pubkey {
+    pda(&[b"case", subject.as_ref(), &sequence.to_le_bytes()], &flux_core::ID)
+}
+
+fn update_flag(bank: &mut Bank, officer: &Pubkey, subject: &Pubkey, flag: bool, case_id: u64) -> TxResult {
+    let user_profile = profile_address(subject);
+    let sequence = profile(bank, subject).case_count;
+    let accounts = flux_core::accounts::UpdateAmlFlag {
+        user_profile,
+        case_record: case_address(&user_profile, sequence),
+        officer_profile: profile_address(officer),
+        officer: *officer,
+        system_program: system_program::ID,
+    };
+    let instruction = if flag {
+        ix(accounts, flux_core::instruction::FlagAccount { case_id, reason_code: 7 })
+    } else {
+        ix(accounts, flux_core::instruction::ClearFlag { case_id, reason_code: 0 })
+    };
+    bank.process(instruction)
+}
+
+#[test]
+fn officer_role_assigned_by_admin_can_flag_and_clear() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let officer = bank.funded_key();
+    let subject = bank.funded_key();
+    init_profile(&mut bank, &officer);
+    init_profile(&mut bank, &subject);
+
+    // A Standard profile cannot act until the admin assigns a compliance role
+    assert_eq!(update_flag(&mut bank, &officer, &subject, true, 1), Err(err(FluxError::UnauthorizedAccess)));
+    assign_role(&mut bank, &protocol, &officer, UserRole::Compliance).unwrap();
+
+    update_flag(&mut bank, &officer, &subject, true, 1).unwrap();
+    assert!(profile(&bank, &subject).is_aml_flagged());
+    update_flag(&mut bank, &officer, &subject, false, 1).unwrap();
+    assert!(!profile(&bank, &subject).is_aml_flagged());
+
+    // Both actions are kept in the subject's case log
+    let subject_profile = profile_address(&subject);
+    let flagged: CaseRecord = bank.read(&case_address(&subject_profile, 0));
+    let cleared: CaseRecord = bank.read(&case_address(&subject_profile, 1));
+    assert_eq!((flagged.action, flagged.case_id, flagged.officer), (CaseAction::Flag, 1, officer));
+    assert_eq!((cleared.action, cleared.sequence), (CaseAction::Clear, 1));
+    assert_eq!(profile(&bank, &subject).case_count, 2);
+}
+
+#[test]
+fn auditor_role_can_flag() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let auditor = bank.funded_key();
+    let subject = bank.funded_key();
+    init_profile(&mut bank, &auditor);
+    init_profile(&mut bank, &subject);
+    assign_role(&mut bank, &protocol, &auditor, UserRole::Auditor).unwrap();
+
+    update_flag(&mut bank, &auditor, &subject, true, 9).unwrap();
+    assert_eq!(update_flag(&mut bank, &auditor, &subject, true, 9), Err(err(FluxError::AccountFlagged)));
+}
+
+#[test]
+fn only_admin_assigns_roles_and_revoking_removes_access() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let officer = bank.funded_key();
+    let subject = bank.funded_key();
+    init_profile(&mut bank, &officer);
+    init_profile(&mut bank, &subject);
+
+    let impostor = Protocol { admin: officer, ..protocol };
+    assert_eq!(
+        assign_role(&mut bank, &impostor, &officer, UserRole::Compliance),
+        Err(err(FluxError::UnauthorizedAccess))
+    );
+
+    assign_role(&mut bank, &protocol, &officer, UserRole::Compliance).unwrap();
+    assign_role(&mut bank, &protocol, &officer, UserRole::Standard).unwrap();
+    assert_eq!(update_flag(&mut bank, &officer, &subject, true, 1), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn roles_are_only_read_from_and_written_to_canonical_profiles() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let officer = bank.funded_key();
+    let subject = bank.funded_key();
+    init_profile(&mut bank, &officer);
+    init_profile(&mut bank, &subject);
+
+    // A program-owned copy of the officer's profile, at an address outside the profile PDAs
+    let forged = Pubkey::new_unique();
+    let mut copy = bank.account(&profile_address(&officer)).unwrap().clone();
+    bank.set_account(forged, copy.clone());
+    let result = bank.process(ix(
+        flux_core::accounts::AssignUserRole { user_profile: forged, protocol_config: protocol.config, admin: protocol.admin },
+        flux_core::instruction::AssignUserRole { role: UserRole::Compliance },
+    ));
+    assert_eq!(result, Err(err(anchor_lang::error::ErrorCode::ConstraintSeeds)));
+
+    // Nor does a forged Compliance profile grant the role
+    assign_role(&mut bank, &protocol, &officer, UserRole::Compliance).unwrap();
+    copy.data = bank.account(&profile_address(&officer)).unwrap().data.clone();
+    bank.set_account(forged, copy);
+    assign_role(&mut bank, &protocol, &officer, UserRole::Standard).unwrap();
+
+    let user_profile = profile_address(&subject);
+    let result = bank.process(ix(
+        flux_core::accounts::UpdateAmlFlag {
+            user_profile,
+            case_record: case_address(&user_profile, 0),
+            officer_profile: forged,
+            officer,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::FlagAccount { case_id: 1, reason_code: 7 },
+    ));
+    assert_eq!(result, Err(err(anchor_lang::error::ErrorCode::ConstraintSeeds)));
+    assert!(!profile(&bank, &subject).is_aml_flagged());
+}
+
+#[test]
+fn clearing_an_unflagged_profile_fails() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let officer = bank.funded_key();
+    let subject = bank.funded_key();
+    init_profile(&mut bank, &officer);
+    init_profile(&mut bank, &subject);
+    assign_role(&mut bank, &protocol, &officer, UserRole::Compliance).unwrap();
+
+    assert_eq!(update_flag(&mut bank, &officer, &subject, false, 1), Err(err(FluxError::AccountNotFlagged)));
+}
+
+fn set_threshold(bank: &mut Bank, protocol: &Protocol, authority: &Pubkey, threshold: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::UpdateComplianceConfig {
+            compliance_config: compliance_config_address(),
+            protocol_config: protocol.config,
+            authority: *authority,
+        },
+        flux_core::instruction::SetTravelRuleThreshold { threshold },
+    ))
+}
+
+#[test]
+fn compliance_config_init_requires_upgrade_authority() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let attacker = bank.funded_key();
+
+    assert_eq!(init_compliance_config(&mut bank, &protocol, &attacker), Err(err(FluxError::UnauthorizedAccess)));
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+
+    let config: ComplianceConfig = bank.read(&compliance_config_address());
+    assert_eq!(config.travel_rule_threshold, ComplianceConfig::DEFAULT_TRAVEL_RULE_THRESHOLD);
+}
+
+#[test]
+fn travel_rule_threshold_follows_the_protocol_admin() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+
+    assert_eq!(set_threshold(&mut bank, &protocol, &protocol.risk_manager, 5), Err(err(FluxError::UnauthorizedAccess)));
+    set_threshold(&mut bank, &protocol, &protocol.admin, 5).unwrap();
+    assert_eq!(bank.read::<ComplianceConfig>(&compliance_config_address()).travel_rule_threshold, 5);
+
+    // Rotating the admin role moves control of the threshold with it
+    let new_admin = bank.funded_key();
+    bank.process(ix(
+        flux_core::accounts::UpdateProtocolRoles { protocol_config: protocol.config, admin: protocol.admin },
+        flux_core::instruction::UpdateProtocolRoles {
+            roles: ProtocolRoles {
+                admin: new_admin,
+                guardian: protocol.guardian,
+                risk_manager: protocol.risk_manager,
+                fee_manager: protocol.fee_manager,
+            },
+        },
+    ))
+    .unwrap();
+    assert_eq!(set_threshold(&mut bank, &protocol, &protocol.admin, 6), Err(err(FluxError::UnauthorizedAccess)));
+    set_threshold(&mut bank, &protocol, &new_admin, 6).unwrap();
+}
+
+/// A compliance config requiring travel-rule data from 5 SOL, and a profile past its transfer cooldown.
+fn travel_rule_setup() -> (Bank, Protocol, Pubkey) {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+    set_threshold(&mut bank, &protocol, &protocol.admin, 5 * SOL).unwrap();
+    let owner = bank.funded_key();
+    init_profile(&mut bank, &owner);
+    edit_profile(&mut bank, &owner, |profile| profile.kyc_verified = 1);
+    bank.warp(ComplianceConfig::TRANSFER_COOLDOWN);
+    (bank, protocol, owner)
+}
+
+#[test]
+fn transfer_records_are_keyed_by_profile_and_nonce() {
+    let (mut bank, protocol, owner) = travel_rule_setup();
+    let info = TravelRuleInfo { payload_hash: [7; 32], counterparty_vasp: [9; 32] };
+
+    assert_eq!(xfer(&mut bank, &protocol, &owner, 6 * SOL), Err(err(FluxError::TravelRuleRequired)));
+    xfer_with_travel_rule(&mut bank, &protocol, &owner, 6 * SOL, Some(info)).unwrap();
+
+    // The same payload again, from the same sender and from another, gets its own record
+    bank.warp(ComplianceConfig::TRANSFER_COOLDOWN);
+    xfer_with_travel_rule(&mut bank, &protocol, &owner, 6 * SOL, Some(info)).unwrap();
+    let other = bank.funded_key();
+    init_profile(&mut bank, &other);
+    edit_profile(&mut bank, &other, |profile| profile.kyc_verified = 1);
+    bank.warp(ComplianceConfig::TRANSFER_COOLDOWN);
+    xfer_with_travel_rule(&mut bank, &protocol, &other, 6 * SOL, Some(info)).unwrap();
+
+    for (sender, nonce) in [(owner, 0), (owner, 1), (other, 0)] {
+        let record: TransferRecord = bank.read(&transfer_record_address(&sender, nonce));
+        assert_eq!((record.user_profile, record.sender, record.nonce), (profile_address(&sender), sender, nonce));
+        assert_eq!((record.payload_hash, record.counterparty_vasp, record.amount), (info.payload_hash, info.counterparty_vasp, 6 * SOL));
+    }
+    assert_eq!((profile(&bank, &owner).transfer_records, profile(&bank, &other).transfer_records), (2, 1));
+}
+
+#[test]
+fn transfer_records_need_a_counterparty_vasp() {
+    let (mut bank, protocol, owner) = travel_rule_setup();
+    let info = TravelRuleInfo { payload_hash: [7; 32], counterparty_vasp: [0; 32] };
+
+    assert_eq!(
+        xfer_with_travel_rule(&mut bank, &protocol, &owner, 6 * SOL, Some(info)),
+        Err(err(FluxError::InvalidCounterpartyVasp))
+    );
+    assert!(!bank.exists(&transfer_record_address(&owner, 0)));
+    assert_eq!(profile(&bank, &owner).transfer_records, 0);
+}
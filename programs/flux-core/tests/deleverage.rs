@@ -0,0 +1,150 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+use flux_core::state::position::{AutoDeleverage, AutoDeleverageParams};
+
+use common::*;
+use fixtures::*;
+
+/// Keepers may act below 1.5, repaying up to half the debt per call for 0.001 SOL.
+const PARAMS: AutoDeleverageParams = AutoDeleverageParams {
+    trigger_health_factor: 15000,
+    max_repay_bps: 5000,
+    keeper_fee: SOL / 1000,
+    max_slippage_bps: 100,
+};
+
+fn automation_address(market: &Market, owner: &Pubkey) -> Pubkey {
+    pda(&[b"auto_deleverage", position_address(market, owner).as_ref()], &flux_core::ID)
+}
+
+fn enable_auto_deleverage(bank: &mut Bank, market: &Market, owner: &Pubkey, params: AutoDeleverageParams) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::EnableAutoDeleverage {
+            vault: market.vault,
+            position: position_address(market, owner),
+            automation: automation_address(market, owner),
+            owner: *owner,
+            system_program: anchor_lang::system_program::ID,
+        },
+        flux_core::instruction::EnableAutoDeleverage { params },
+    ))
+}
+
+fn disable_auto_deleverage(bank: &mut Bank, market: &Market, owner: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::DisableAutoDeleverage {
+            vault: market.vault,
+            position: position_address(market, owner),
+            automation: automation_address(market, owner),
+            owner: *owner,
+        },
+        flux_core::instruction::DisableAutoDeleverage {},
+    ))
+}
+
+fn deleverage(bank: &mut Bank, market: &Market, owner: &Pubkey, authority: &Pubkey, collateral_amount: u64, min_amount_out: u64) -> TxResult {
+    let accounts = deleverage_accounts(bank, market, owner, authority);
+    bank.process(ix(accounts, flux_core::instruction::Deleverage { collateral_amount, min_amount_out }))
+}
+
+/// A borrower with 0.1 SOL of collateral (worth 10) owing 6 SOL, at a health factor of 1.39,
+/// and a keeper.
+fn setup() -> (Bank, Market, Pubkey, Pubkey) {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    let keeper = bank.funded_key();
+    (bank, market, owner, keeper)
+}
+
+#[test]
+fn params_are_validated_and_stored() {
+    let (mut bank, market, owner, _) = setup();
+
+    for params in [
+        AutoDeleverageParams { trigger_health_factor: 10000, ..PARAMS },
+        AutoDeleverageParams { trigger_health_factor: AutoDeleverage::MAX_TRIGGER_HEALTH_FACTOR + 1, ..PARAMS },
+        AutoDeleverageParams { max_repay_bps: 0, ..PARAMS },
+        AutoDeleverageParams { max_slippage_bps: AutoDeleverage::MAX_SLIPPAGE_BPS + 1, ..PARAMS },
+    ] {
+        assert_eq!(enable_auto_deleverage(&mut bank, &market, &owner, params), Err(err(FluxError::InvalidAutoDeleverage)));
+    }
+
+    enable_auto_deleverage(&mut bank, &market, &owner, PARAMS).unwrap();
+    let automation: AutoDeleverage = bank.read(&automation_address(&market, &owner));
+    assert_eq!((automation.trigger_health_factor, automation.max_repay_bps), (15000, 5000));
+    assert_eq!((automation.keeper_fee, automation.max_slippage_bps), (SOL / 1000, 100));
+
+    disable_auto_deleverage(&mut bank, &market, &owner).unwrap();
+    assert!(!bank.exists(&automation_address(&market, &owner)));
+}
+
+#[test]
+fn keepers_act_only_below_the_owners_trigger() {
+    let (mut bank, market, owner, keeper) = setup();
+
+    assert_eq!(deleverage(&mut bank, &market, &owner, &keeper, SOL / 100, 0), Err(err(FluxError::UnauthorizedAccess)));
+    enable_auto_deleverage(&mut bank, &market, &owner, AutoDeleverageParams { trigger_health_factor: 13000, ..PARAMS }).unwrap();
+    assert_eq!(deleverage(&mut bank, &market, &owner, &keeper, SOL / 100, 0), Err(err(FluxError::DeleverageNotTriggered)));
+
+    disable_auto_deleverage(&mut bank, &market, &owner).unwrap();
+    enable_auto_deleverage(&mut bank, &market, &owner, PARAMS).unwrap();
+    deleverage(&mut bank, &market, &owner, &keeper, SOL / 100, 0).unwrap();
+
+    // 0.01 SOL sold at 100 repays 1 SOL; the keeper's fee comes out of the collateral too
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.debt_amount, after.collateral_amount), (5 * SOL, SOL / 10 - SOL / 100 - SOL / 1000));
+    assert_eq!(vault(&bank, &market).total_collateral, after.collateral_amount);
+}
+
+#[test]
+fn keepers_repay_at_most_the_owners_limit() {
+    let (mut bank, market, owner, keeper) = setup();
+    enable_auto_deleverage(&mut bank, &market, &owner, PARAMS).unwrap();
+
+    // 4 SOL is more than half of the 6 SOL owed
+    assert_eq!(deleverage(&mut bank, &market, &owner, &keeper, 4 * SOL / 100, 0), Err(err(FluxError::DeleverageLimitExceeded)));
+    deleverage(&mut bank, &market, &owner, &keeper, 3 * SOL / 100, 0).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 3 * SOL);
+
+    // The owner is not bound by the keeper terms
+    deleverage(&mut bank, &market, &owner, &owner, 2 * SOL / 100, 0).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, SOL);
+}
+
+#[test]
+fn keeper_swaps_are_bounded_by_the_oracle_quote() {
+    let (mut bank, market, owner, keeper) = setup();
+    enable_auto_deleverage(&mut bank, &market, &owner, AutoDeleverageParams { max_slippage_bps: 0, ..PARAMS }).unwrap();
+
+    // With no slippage allowed the floor is the full 1 SOL quote, which a keeper may only raise
+    assert_eq!(
+        deleverage(&mut bank, &market, &owner, &keeper, SOL / 100, SOL + 1),
+        Err(err(FluxError::SlippageExceeded))
+    );
+    deleverage(&mut bank, &market, &owner, &keeper, SOL / 100, 0).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 5 * SOL);
+
+    // The feeds behind the quote are the vault's own
+    let rogue = price_feed(&mut bank, DEBT_PRICE * 10, 6);
+    let mut accounts = deleverage_accounts(&bank, &market, &owner, &keeper);
+    accounts.price_feed_debt = rogue;
+    let result = bank.process(ix(accounts, flux_core::instruction::Deleverage { collateral_amount: SOL / 100, min_amount_out: 0 }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+}
+
+#[test]
+fn a_full_repayment_closes_the_owners_loan() {
+    let (mut bank, market, owner, keeper) = setup();
+    enable_auto_deleverage(&mut bank, &market, &owner, AutoDeleverageParams { max_repay_bps: 10000, ..PARAMS }).unwrap();
+
+    // 0.07 SOL sold at 100 fetches 7 SOL, of which only the 6 owed is repaid
+    deleverage(&mut bank, &market, &owner, &keeper, 7 * SOL / 100, 0).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 0);
+    assert_eq!(profile(&bank, &owner).active_loans, 0);
+    close_profile(&mut bank, &owner, false).unwrap();
+}
@@ -0,0 +1,782 @@
+//! flux-core specific setup on top of the `common` harness.
+#![allow(dead_code)]
+
+use anchor_lang::prelude::*;
+use anchor_lang::__private::bytemuck;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use flux_core::state::compliance::TravelRuleInfo;
+use flux_core::state::order::OrderParams;
+use flux_core::state::position::Position;
+use flux_core::state::reserve::{Reserve, ReserveConfig};
+use flux_core::state::supply::SupplyPosition;
+use flux_core::state::reputation::{ReputationParams, ReputationTier, ReputationWeights};
+use flux_core::state::user_profile::{ActionHistory, UserAction, UserProfile, UserRole};
+use flux_core::state::vault::{Vault, VaultConfigUpdate};
+use flux_core::state::withdrawal_queue::WithdrawalQueue;
+
+use crate::common::*;
+
+pub fn bank() -> Bank {
+    let mut bank = Bank::new();
+    bank.add_program(flux_core::ID, flux_core::entry);
+    // Instructions that take `Program<Token>` only check it is the executable token program
+    bank.set_account(anchor_spl::token::ID, TestAccount { lamports: 1, executable: true, ..TestAccount::default() });
+    bank
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: flux_core::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Protocol {
+    pub config: Pubkey,
+    pub program_data: Pubkey,
+    pub admin: Pubkey,
+    pub guardian: Pubkey,
+    pub risk_manager: Pubkey,
+    pub fee_manager: Pubkey,
+    pub insurance_fund: Pubkey,
+}
+
+pub fn protocol_config_address() -> Pubkey {
+    pda(&[b"protocol_config"], &flux_core::ID)
+}
+
+/// Deploy with a fresh upgrade authority, which initializes the config and becomes admin.
+pub fn init_protocol(bank: &mut Bank) -> Protocol {
+    let admin = bank.funded_key();
+    let protocol = Protocol {
+        config: protocol_config_address(),
+        program_data: bank.set_upgrade_authority(flux_core::ID, Some(admin)),
+        admin,
+        guardian: bank.funded_key(),
+        risk_manager: bank.funded_key(),
+        fee_manager: bank.funded_key(),
+        insurance_fund: Pubkey::new_unique(),
+    };
+    bank.process(ix(
+        flux_core::accounts::InitProtocolConfig {
+            protocol_config: protocol.config,
+            program: flux_core::ID,
+            program_data: protocol.program_data,
+            admin: protocol.admin,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitProtocolConfig {
+            guardian: protocol.guardian,
+            risk_manager: protocol.risk_manager,
+            fee_manager: protocol.fee_manager,
+            insurance_fund: protocol.insurance_fund,
+        },
+    ))
+    .expect("init_protocol_config");
+    protocol
+}
+
+pub fn profile_address(owner: &Pubkey) -> Pubkey {
+    pda(&[b"profile", owner.as_ref()], &flux_core::ID)
+}
+
+pub fn init_profile(bank: &mut Bank, owner: &Pubkey) -> Pubkey {
+    let user_profile = profile_address(owner);
+    bank.process(ix(
+        flux_core::accounts::InitUserProfile {
+            user_profile,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitUserProfile {},
+    ))
+    .expect("init_user_profile");
+    user_profile
+}
+
+pub fn profile(bank: &Bank, owner: &Pubkey) -> UserProfile {
+    bank.read_zero_copy(&profile_address(owner))
+}
+
+pub fn close_profile(bank: &mut Bank, owner: &Pubkey, with_history: bool) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::CloseUserProfile {
+            user_profile: profile_address(owner),
+            action_history: with_history.then(|| history_address(owner)),
+            owner: *owner,
+        },
+        flux_core::instruction::CloseUserProfile {},
+    ))
+}
+
+pub fn assign_role(bank: &mut Bank, protocol: &Protocol, owner: &Pubkey, role: UserRole) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::AssignUserRole {
+            user_profile: profile_address(owner),
+            protocol_config: protocol.config,
+            admin: protocol.admin,
+        },
+        flux_core::instruction::AssignUserRole { role },
+    ))
+}
+
+/// Overwrite fields of a stored zero-copy profile.
+pub fn edit_profile(bank: &mut Bank, owner: &Pubkey, edit: impl FnOnce(&mut UserProfile)) {
+    let key = profile_address(owner);
+    let mut profile: UserProfile = bank.read_zero_copy(&key);
+    edit(&mut profile);
+    bank.write_zero_copy(key, flux_core::ID, &profile, UserProfile::LEN);
+}
+
+pub fn compliance_config_address() -> Pubkey {
+    pda(&[b"compliance_config"], &flux_core::ID)
+}
+
+pub fn init_compliance_config(bank: &mut Bank, protocol: &Protocol, authority: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::InitComplianceConfig {
+            compliance_config: compliance_config_address(),
+            program: flux_core::ID,
+            program_data: protocol.program_data,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitComplianceConfig {},
+    ))
+}
+
+pub fn reputation_config_address() -> Pubkey {
+    pda(&[b"reputation_config"], &flux_core::ID)
+}
+
+pub fn reputation_params() -> ReputationParams {
+    ReputationParams {
+        weights: ReputationWeights { repayment: 50, liquidation: 1000, account_age: 100, volume: 200 },
+        baseline_score: 50,
+        decay_bps_per_day: 100,
+        premium_min_score: 70,
+        premium_min_volume: 100 * SOL,
+        tiers: [
+            ReputationTier { min_score: 0, borrow_limit: 10 * SOL, collateral_ratio_discount_bps: 0 },
+            ReputationTier { min_score: 60, borrow_limit: 100 * SOL, collateral_ratio_discount_bps: 1000 },
+            ReputationTier { min_score: 80, borrow_limit: 1000 * SOL, collateral_ratio_discount_bps: 2000 },
+        ],
+    }
+}
+
+pub fn init_reputation_config(bank: &mut Bank, protocol: &Protocol, authority: &Pubkey, params: ReputationParams) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::InitReputationConfig {
+            reputation_config: reputation_config_address(),
+            program: flux_core::ID,
+            program_data: protocol.program_data,
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitReputationConfig { params },
+    ))
+}
+
+pub fn history_address(owner: &Pubkey) -> Pubkey {
+    pda(&[b"history", profile_address(owner).as_ref()], &flux_core::ID)
+}
+
+pub fn init_history(bank: &mut Bank, owner: &Pubkey) -> Pubkey {
+    let action_history = history_address(owner);
+    bank.process(ix(
+        flux_core::accounts::InitActionHistory {
+            user_profile: profile_address(owner),
+            action_history,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitActionHistory {},
+    ))
+    .expect("init_action_history");
+    action_history
+}
+
+/// Header and entries of a stored action history, oldest entry first.
+pub fn history_entries(bank: &Bank, owner: &Pubkey) -> (ActionHistory, Vec<UserAction>) {
+    let data = &bank.account(&history_address(owner)).expect("history").data;
+    let header: ActionHistory = bytemuck::pod_read_unaligned(&data[8..ActionHistory::HEADER_LEN]);
+    let entry = |slot: u32| -> UserAction {
+        let offset = ActionHistory::HEADER_LEN + slot as usize * UserAction::LEN;
+        bytemuck::pod_read_unaligned(&data[offset..offset + UserAction::LEN])
+    };
+    let start = if header.len == header.capacity { header.head } else { 0 };
+    let entries = (0..header.len).map(|i| entry((start + i) % header.capacity)).collect();
+    (header, entries)
+}
+
+/// xfer_funds of `amount` lamports from `owner` to a fresh wallet, without travel-rule data.
+pub fn xfer(bank: &mut Bank, protocol: &Protocol, owner: &Pubkey, amount: u64) -> TxResult {
+    xfer_with_travel_rule(bank, protocol, owner, amount, None)
+}
+
+pub fn transfer_record_address(owner: &Pubkey, nonce: u32) -> Pubkey {
+    pda(&[b"transfer_record", profile_address(owner).as_ref(), nonce.to_le_bytes().as_ref()], &flux_core::ID)
+}
+
+/// xfer_funds carrying `travel_rule`, recorded at the profile's next transfer record.
+pub fn xfer_with_travel_rule(
+    bank: &mut Bank,
+    protocol: &Protocol,
+    owner: &Pubkey,
+    amount: u64,
+    travel_rule: Option<TravelRuleInfo>,
+) -> TxResult {
+    let record = travel_rule.map(|_| transfer_record_address(owner, profile(bank, owner).transfer_records));
+    let history = history_address(owner);
+    let has_history = bank.exists(&history);
+    bank.process(ix(
+        flux_core::accounts::XferFunds {
+            user_profile: profile_address(owner),
+            action_history: has_history.then_some(history),
+            authority: *owner,
+            recipient: Pubkey::new_unique(),
+            system_program: system_program::ID,
+            protocol_config: protocol.config,
+            blacklist_registry: Pubkey::new_unique(),
+            compliance_config: compliance_config_address(),
+            transfer_record: record,
+            instructions_sysvar: sysvar::instructions::ID,
+        },
+        flux_core::instruction::XferFunds { amount, travel_rule },
+    ))
+}
+
+/// A price feed account in the layout `oracle::read_price` expects.
+pub fn price_feed(bank: &mut Bank, price: u64, decimals: u8) -> Pubkey {
+    let feed = Pubkey::new_unique();
+    set_price(bank, &feed, price, decimals);
+    feed
+}
+
+pub fn set_price(bank: &mut Bank, feed: &Pubkey, price: u64, decimals: u8) {
+    let mut data = price.to_le_bytes().to_vec();
+    data.push(decimals);
+    bank.set_account(*feed, TestAccount { lamports: 1, owner: Pubkey::default(), data, executable: false });
+}
+
+/// A vault and the two price feeds it is configured with. There is no instruction that
+/// creates vaults, so tests store one directly.
+#[derive(Clone, Copy)]
+pub struct Market {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub collateral_feed: Pubkey,
+    pub debt_feed: Pubkey,
+}
+
+/// Collateral at 100 and the debt asset at 1, both with 6 decimals.
+pub const COLLATERAL_PRICE: u64 = 100_000_000;
+pub const DEBT_PRICE: u64 = 1_000_000;
+
+/// 150% collateral ratio, 120% liquidation ratio, 3x max leverage.
+pub fn init_market(bank: &mut Bank) -> Market {
+    let market = Market {
+        vault: Pubkey::new_unique(),
+        authority: bank.funded_key(),
+        collateral_feed: price_feed(bank, COLLATERAL_PRICE, 6),
+        debt_feed: price_feed(bank, DEBT_PRICE, 6),
+    };
+    let mut vault = Vault::try_deserialize_unchecked(&mut vec![0u8; Vault::LEN].as_slice()).expect("zeroed vault");
+    vault.authority = market.authority;
+    vault.last_update = bank.now;
+    vault.collateral_ratio = 15000;
+    vault.min_collateral_ratio = 12000;
+    vault.max_leverage = 3;
+    vault.optimal_utilization = 8000;
+    vault.liquidation_penalty = 800;
+    vault.liquidation_bonus = 500;
+    vault.oracle_config = market.collateral_feed;
+    vault.debt_oracle = market.debt_feed;
+    bank.write(market.vault, flux_core::ID, &vault, Vault::LEN);
+    market
+}
+
+pub fn vault(bank: &Bank, market: &Market) -> Vault {
+    bank.read(&market.vault)
+}
+
+pub fn edit_vault(bank: &mut Bank, market: &Market, edit: impl FnOnce(&mut Vault)) {
+    let mut vault = vault(bank, market);
+    edit(&mut vault);
+    bank.write(market.vault, flux_core::ID, &vault, Vault::LEN);
+}
+
+pub fn supply_position_address(market: &Market, owner: &Pubkey) -> Pubkey {
+    pda(&[b"supply_position", market.vault.as_ref(), owner.as_ref()], &flux_core::ID)
+}
+
+pub fn supply_position(bank: &Bank, market: &Market, owner: &Pubkey) -> SupplyPosition {
+    bank.read(&supply_position_address(market, owner))
+}
+
+pub fn open_supply_position(bank: &mut Bank, market: &Market, owner: &Pubkey) -> Pubkey {
+    let supply_position = supply_position_address(market, owner);
+    bank.process(ix(
+        flux_core::accounts::OpenSupplyPosition {
+            vault: market.vault,
+            supply_position,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::OpenSupplyPosition {},
+    ))
+    .expect("open_supply_position");
+    supply_position
+}
+
+/// Lendable liquidity supplied by the vault authority.
+pub fn fetch(bank: &mut Bank, market: &Market, amount: u64) -> TxResult {
+    supply(bank, market, &market.authority, amount)
+}
+
+/// Lendable liquidity supplied through fetch_assets by `depositor`, whose supply
+/// position is opened on first use.
+pub fn supply(bank: &mut Bank, market: &Market, depositor: &Pubkey, amount: u64) -> TxResult {
+    let supply_position = supply_position_address(market, depositor);
+    if !bank.exists(&supply_position) {
+        open_supply_position(bank, market, depositor);
+    }
+    let withdrawal_queue = withdrawal_queue(bank, market);
+    bank.process(ix(
+        flux_core::accounts::FetchAssets {
+            vault: market.vault,
+            supply_position,
+            withdrawal_queue,
+            authority: *depositor,
+            system_program: system_program::ID,
+            protocol_config: protocol_config_address(),
+            oracle_feed: market.collateral_feed,
+            history_buffer: Pubkey::new_unique(),
+        },
+        flux_core::instruction::FetchAssets { amount },
+    ))
+}
+
+pub fn position_address(market: &Market, owner: &Pubkey) -> Pubkey {
+    pda(&[b"position", market.vault.as_ref(), owner.as_ref()], &flux_core::ID)
+}
+
+pub fn position(bank: &Bank, market: &Market, owner: &Pubkey) -> Position {
+    bank.read(&position_address(market, owner))
+}
+
+pub fn open_position(bank: &mut Bank, market: &Market, owner: &Pubkey) -> Pubkey {
+    let position = position_address(market, owner);
+    bank.process(ix(
+        flux_core::accounts::OpenPosition {
+            vault: market.vault,
+            position,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::OpenPosition {},
+    ))
+    .expect("open_position");
+    position
+}
+
+pub fn deposit_collateral(bank: &mut Bank, market: &Market, owner: &Pubkey, amount: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::DepositCollateral {
+            vault: market.vault,
+            position: position_address(market, owner),
+            protocol_config: protocol_config_address(),
+            owner: *owner,
+        },
+        flux_core::instruction::DepositCollateral { amount },
+    ))
+}
+
+/// ManageDebt accounts for `owner`'s position, priced by the market's own feeds. Optional
+/// accounts are passed when they exist.
+pub fn manage_debt(bank: &Bank, market: &Market, owner: &Pubkey) -> flux_core::accounts::ManageDebt {
+    let history = history_address(owner);
+    flux_core::accounts::ManageDebt {
+        vault: market.vault,
+        position: position_address(market, owner),
+        user_profile: profile_address(owner),
+        action_history: bank.exists(&history).then_some(history),
+        risk_category: None,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        reputation_config: reputation_config_address(),
+        protocol_config: protocol_config_address(),
+        owner: *owner,
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+        instructions_sysvar: sysvar::instructions::ID,
+    }
+}
+
+pub fn borrow(bank: &mut Bank, market: &Market, owner: &Pubkey, amount: u64) -> TxResult {
+    let accounts = manage_debt(bank, market, owner);
+    bank.process(ix(accounts, flux_core::instruction::Borrow { amount }))
+}
+
+pub fn repay(bank: &mut Bank, market: &Market, owner: &Pubkey, amount: u64) -> TxResult {
+    let accounts = manage_debt(bank, market, owner);
+    bank.process(ix(accounts, flux_core::instruction::Repay { amount }))
+}
+
+/// Protocol, reputation config and a vault with `liquidity` to lend.
+pub fn init_lending(bank: &mut Bank, liquidity: u64) -> (Protocol, Market) {
+    let protocol = init_protocol(bank);
+    init_reputation_config(bank, &protocol, &protocol.admin, reputation_params()).expect("init_reputation_config");
+    let market = init_market(bank);
+    if liquidity > 0 {
+        fetch(bank, &market, liquidity).expect("fetch_assets");
+    }
+    (protocol, market)
+}
+
+/// A funded borrower with a profile and a position holding `collateral`.
+pub fn init_borrower(bank: &mut Bank, market: &Market, collateral: u64) -> Pubkey {
+    let owner = bank.funded_key();
+    init_profile(bank, &owner);
+    open_position(bank, market, &owner);
+    if collateral > 0 {
+        deposit_collateral(bank, market, &owner, collateral).expect("deposit_collateral");
+    }
+    owner
+}
+
+/// Stand-ins for the Jupiter program and token accounts; swaps are simulated in-program.
+pub const JUPITER: Pubkey = anchor_lang::solana_program::pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+pub fn liquidate_accounts(bank: &Bank, market: &Market, owner: &Pubkey, liquidator: &Pubkey) -> flux_core::accounts::LiquidatePosition {
+    flux_core::accounts::LiquidatePosition {
+        vault: market.vault,
+        position: position_address(market, owner),
+        user_profile: profile_address(owner),
+        collateral_reserve: None,
+        collateral_deposit: None,
+        risk_category: None,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+        liquidator_token_account: Pubkey::new_unique(),
+        authority: *liquidator,
+        protocol_config: protocol_config_address(),
+        token_program: anchor_spl::token::ID,
+        system_program: system_program::ID,
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+    }
+}
+
+/// Liquidate `owner`'s base collateral.
+pub fn liquidate(bank: &mut Bank, market: &Market, owner: &Pubkey, liquidator: &Pubkey) -> TxResult {
+    let accounts = liquidate_accounts(bank, market, owner, liquidator);
+    bank.process(ix(accounts, flux_core::instruction::LiquidatePosition {}))
+}
+
+/// Deleverage accounts for `owner`'s position called by `authority`; `automation` is passed
+/// when the owner has enabled it.
+pub fn deleverage_accounts(bank: &Bank, market: &Market, owner: &Pubkey, authority: &Pubkey) -> flux_core::accounts::Deleverage {
+    let position = position_address(market, owner);
+    let automation = pda(&[b"auto_deleverage", position.as_ref()], &flux_core::ID);
+    flux_core::accounts::Deleverage {
+        vault: market.vault,
+        position,
+        user_profile: profile_address(owner),
+        automation: bank.exists(&automation).then_some(automation),
+        risk_category: None,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        protocol_config: protocol_config_address(),
+        authority: *authority,
+        keeper_token_account: Pubkey::new_unique(),
+        owner_token_account: Pubkey::new_unique(),
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+    }
+}
+
+pub fn unwind_accounts(bank: &Bank, market: &Market, owner: &Pubkey) -> flux_core::accounts::Unwind {
+    flux_core::accounts::Unwind {
+        vault: market.vault,
+        position: position_address(market, owner),
+        user_profile: profile_address(owner),
+        risk_category: None,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        protocol_config: protocol_config_address(),
+        owner: *owner,
+        owner_token_account: Pubkey::new_unique(),
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+    }
+}
+
+pub fn order_address(market: &Market, owner: &Pubkey, id: u8) -> Pubkey {
+    pda(&[b"position_order", position_address(market, owner).as_ref(), &[id]], &flux_core::ID)
+}
+
+pub fn place_order(bank: &mut Bank, market: &Market, owner: &Pubkey, id: u8, params: OrderParams) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::PlaceOrder {
+            vault: market.vault,
+            position: position_address(market, owner),
+            order: order_address(market, owner, id),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::PlaceOrder { id, params },
+    ))
+}
+
+pub fn execute_order_accounts(bank: &Bank, market: &Market, owner: &Pubkey, id: u8, executor: &Pubkey) -> flux_core::accounts::ExecuteOrder {
+    flux_core::accounts::ExecuteOrder {
+        vault: market.vault,
+        position: position_address(market, owner),
+        order: order_address(market, owner, id),
+        owner: *owner,
+        user_profile: profile_address(owner),
+        risk_category: None,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        protocol_config: protocol_config_address(),
+        executor: *executor,
+        executor_token_account: Pubkey::new_unique(),
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+    }
+}
+
+pub fn unload(bank: &mut Bank, market: &Market) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::UnloadVault {
+            vault: market.vault,
+            incinerator_program: Pubkey::new_unique(),
+            authority: market.authority,
+            protocol_config: protocol_config_address(),
+        },
+        flux_core::instruction::UnloadVault {},
+    ))
+}
+
+pub fn xfer_venue_address(venue: &Pubkey) -> Pubkey {
+    pda(&[b"xfer_venue", venue.as_ref()], &flux_core::ID)
+}
+
+pub fn add_xfer_venue(bank: &mut Bank, admin: &Pubkey, venue: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::AddXferVenue {
+            xfer_venue: xfer_venue_address(venue),
+            venue: *venue,
+            protocol_config: protocol_config_address(),
+            admin: *admin,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::AddXferVenue {},
+    ))
+}
+
+/// Rebalance accounts sending `Xfer` steps to a fresh wallet off the venue allow-list, with
+/// whichever of the authority's compliance accounts exist.
+pub fn rebalance_accounts(bank: &Bank, market: &Market, authority: &Pubkey) -> flux_core::accounts::Rebalance {
+    let existing = |key: Pubkey| bank.exists(&key).then_some(key);
+    flux_core::accounts::Rebalance {
+        vault: market.vault,
+        withdrawal_queue: withdrawal_queue(bank, market),
+        protocol_config: protocol_config_address(),
+        authority: *authority,
+        authority_token_account: Pubkey::new_unique(),
+        recipient: Pubkey::new_unique(),
+        xfer_venue: None,
+        user_profile: existing(profile_address(authority)),
+        action_history: existing(history_address(authority)),
+        compliance_config: existing(compliance_config_address()),
+        transfer_record: None,
+        instructions_sysvar: sysvar::instructions::ID,
+        system_program: system_program::ID,
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+        incinerator_program: Pubkey::new_unique(),
+        price_feed_collateral: market.collateral_feed,
+        price_feed_debt: market.debt_feed,
+    }
+}
+
+pub fn pending_config_address(market: &Market) -> Pubkey {
+    pda(&[b"pending_config", market.vault.as_ref()], &flux_core::ID)
+}
+
+pub fn propose_config(bank: &mut Bank, market: &Market, authority: &Pubkey, update: VaultConfigUpdate) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ProposeConfigChange {
+            vault: market.vault,
+            pending_config: pending_config_address(market),
+            protocol_config: protocol_config_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::ProposeConfigChange { update },
+    ))
+}
+
+pub fn execute_config(bank: &mut Bank, market: &Market, proposer: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ExecuteConfigChange {
+            vault: market.vault,
+            pending_config: pending_config_address(market),
+            proposer: *proposer,
+        },
+        flux_core::instruction::ExecuteConfigChange {},
+    ))
+}
+
+pub fn withdrawal_queue_address(market: &Market) -> Pubkey {
+    pda(&[b"withdrawal_queue", market.vault.as_ref()], &flux_core::ID)
+}
+
+/// The vault's withdrawal queue, if one has been opened.
+pub fn withdrawal_queue(bank: &Bank, market: &Market) -> Option<Pubkey> {
+    let queue = withdrawal_queue_address(market);
+    bank.exists(&queue).then_some(queue)
+}
+
+pub fn init_withdrawal_queue(bank: &mut Bank, protocol: &Protocol, market: &Market) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::InitWithdrawalQueue {
+            vault: market.vault,
+            withdrawal_queue: withdrawal_queue_address(market),
+            protocol_config: protocol.config,
+            authority: protocol.risk_manager,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitWithdrawalQueue {},
+    ))
+}
+
+pub fn pause(bank: &mut Bank, protocol: &Protocol, authority: &Pubkey, paused: u16) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::SetPauseFlags { protocol_config: protocol.config, authority: *authority },
+        flux_core::instruction::SetPauseFlags { paused },
+    ))
+}
+
+pub fn request_withdrawal(bank: &mut Bank, market: &Market, owner: &Pubkey, amount: u64) -> TxResult {
+    let queue = withdrawal_queue_address(market);
+    let ticket_count = bank.read::<WithdrawalQueue>(&queue).ticket_count;
+    bank.process(ix(
+        flux_core::accounts::RequestWithdrawal {
+            vault: market.vault,
+            withdrawal_queue: queue,
+            supply_position: supply_position_address(market, owner),
+            ticket: pda(&[b"withdrawal_ticket", queue.as_ref(), &ticket_count.to_le_bytes()], &flux_core::ID),
+            protocol_config: protocol_config_address(),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::RequestWithdrawal { amount },
+    ))
+}
+
+/// An initialized SPL token mint with `decimals`.
+pub fn mint(bank: &mut Bank, decimals: u8) -> Pubkey {
+    let mint = Pubkey::new_unique();
+    let state = anchor_spl::token::spl_token::state::Mint { decimals, is_initialized: true, ..Default::default() };
+    let mut data = vec![0u8; anchor_spl::token::spl_token::state::Mint::LEN];
+    state.pack_into_slice(&mut data);
+    bank.set_account(mint, TestAccount { lamports: 1, owner: anchor_spl::token::ID, data, executable: false });
+    mint
+}
+
+pub fn reserve_address(market: &Market, mint: &Pubkey) -> Pubkey {
+    pda(&[b"reserve", market.vault.as_ref(), mint.as_ref()], &flux_core::ID)
+}
+
+/// 50% LTV, 80% liquidation threshold, uncapped, no category.
+pub fn reserve_config(oracle: Pubkey) -> ReserveConfig {
+    ReserveConfig { oracle, ltv_bps: 5000, liquidation_threshold_bps: 8000, deposit_cap: 0, category: 0 }
+}
+
+pub fn add_reserve(
+    bank: &mut Bank,
+    protocol: &Protocol,
+    market: &Market,
+    mint: &Pubkey,
+    config: ReserveConfig,
+    risk_category: Option<Pubkey>,
+) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::AddReserve {
+            vault: market.vault,
+            mint: *mint,
+            reserve: reserve_address(market, mint),
+            risk_category,
+            protocol_config: protocol.config,
+            authority: protocol.risk_manager,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::AddReserve { config },
+    ))
+}
+
+pub fn collateral_deposit_address(market: &Market, owner: &Pubkey, reserve: &Pubkey) -> Pubkey {
+    pda(&[b"collateral", position_address(market, owner).as_ref(), reserve.as_ref()], &flux_core::ID)
+}
+
+pub fn open_collateral_deposit(bank: &mut Bank, market: &Market, owner: &Pubkey, reserve: &Pubkey) -> Pubkey {
+    let deposit = collateral_deposit_address(market, owner, reserve);
+    bank.process(ix(
+        flux_core::accounts::OpenCollateralDeposit {
+            vault: market.vault,
+            reserve: *reserve,
+            position: position_address(market, owner),
+            collateral_deposit: deposit,
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::OpenCollateralDeposit {},
+    ))
+    .expect("open_collateral_deposit");
+    deposit
+}
+
+pub fn deposit_reserve_collateral(bank: &mut Bank, market: &Market, owner: &Pubkey, reserve: &Pubkey, amount: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::DepositReserveCollateral {
+            vault: market.vault,
+            reserve: *reserve,
+            position: position_address(market, owner),
+            collateral_deposit: collateral_deposit_address(market, owner, reserve),
+            protocol_config: protocol_config_address(),
+            owner: *owner,
+            owner_token_account: Pubkey::new_unique(),
+        },
+        flux_core::instruction::DepositReserveCollateral { amount },
+    ))
+}
+
+/// `instruction` followed by the `[reserve, collateral_deposit, price_feed]` triple of each of
+/// `owner`'s `reserves`, as health checks expect.
+pub fn with_reserves(bank: &Bank, market: &Market, owner: &Pubkey, reserves: &[Pubkey], mut instruction: Instruction) -> Instruction {
+    for reserve in reserves {
+        let oracle = bank.read::<Reserve>(reserve).oracle;
+        instruction.accounts.extend([
+            AccountMeta::new_readonly(*reserve, false),
+            AccountMeta::new_readonly(collateral_deposit_address(market, owner, reserve), false),
+            AccountMeta::new_readonly(oracle, false),
+        ]);
+    }
+    instruction
+}
@@ -0,0 +1,152 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar;
+use flux_core::errors::FluxError;
+use flux_core::state::config::ProtocolConfig;
+
+use common::*;
+use fixtures::*;
+
+fn flash_borrow(market: &Market, borrower: &Pubkey, amount: u64) -> Instruction {
+    ix(
+        flux_core::accounts::FlashBorrow {
+            vault: market.vault,
+            protocol_config: protocol_config_address(),
+            borrower: *borrower,
+            borrower_token_account: Pubkey::new_unique(),
+            instructions_sysvar: sysvar::instructions::ID,
+        },
+        flux_core::instruction::FlashBorrow { amount },
+    )
+}
+
+fn flash_repay(market: &Market, repayer: &Pubkey, insurance_fund: Pubkey) -> Instruction {
+    ix(
+        flux_core::accounts::FlashRepay {
+            vault: market.vault,
+            repayer: *repayer,
+            repayer_token_account: Pubkey::new_unique(),
+            protocol_config: protocol_config_address(),
+            insurance_fund,
+            incinerator_program: Pubkey::new_unique(),
+        },
+        flux_core::instruction::FlashRepay {},
+    )
+}
+
+fn set_insurance_fund(bank: &mut Bank, admin: &Pubkey, insurance_fund: Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::UpdateProtocolRoles { protocol_config: protocol_config_address(), admin: *admin },
+        flux_core::instruction::SetInsuranceFund { insurance_fund },
+    ))
+}
+
+/// 100 SOL of liquidity at a 0.3% flash loan fee, and a funded borrower.
+fn setup() -> (Bank, Protocol, Market, Pubkey) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    edit_vault(&mut bank, &market, |vault| vault.flash_loan_fee_rate = 30);
+    let borrower = bank.funded_key();
+    (bank, protocol, market, borrower)
+}
+
+#[test]
+fn repayment_in_the_same_transaction_splits_the_fee() {
+    let (mut bank, protocol, market, borrower) = setup();
+
+    bank.process_transaction(&[
+        flash_borrow(&market, &borrower, 10 * SOL),
+        flash_repay(&market, &borrower, protocol.insurance_fund),
+    ])
+    .unwrap();
+
+    // 0.3% of 10 SOL; depositors keep 70%, the insurance fund and incinerator get the rest
+    let state = vault(&bank, &market);
+    assert_eq!(state.total_assets, 100 * SOL + 21_000_000);
+    assert_eq!((state.flash_loan_amount, state.flash_loan_fee), (0, 0));
+    assert!(bank.logs_contain("Flash loan repaid: 10000000000 + 30000000 fee"));
+}
+
+#[test]
+fn borrow_requires_a_later_repay_for_the_same_vault() {
+    let (mut bank, protocol, market, borrower) = setup();
+    let other = init_market(&mut bank);
+
+    assert_eq!(bank.process(flash_borrow(&market, &borrower, SOL)), Err(err(FluxError::FlashLoanNotRepaid)));
+    // A repay that runs first, or repays another vault, does not count
+    let result = bank.process_transaction(&[
+        flash_repay(&market, &borrower, protocol.insurance_fund),
+        flash_borrow(&market, &borrower, SOL),
+    ]);
+    assert_eq!(result, Err(err(FluxError::NoActiveFlashLoan)));
+    let result = bank.process_transaction(&[
+        flash_borrow(&market, &borrower, SOL),
+        flash_repay(&other, &borrower, protocol.insurance_fund),
+    ]);
+    assert_eq!(result, Err(err(FluxError::FlashLoanNotRepaid)));
+
+    assert_eq!(vault(&bank, &market).flash_loan_amount, 0);
+}
+
+#[test]
+fn borrow_rejects_cpi() {
+    let (mut bank, protocol, market, borrower) = setup();
+
+    // A wrapper program calling in could run code between the borrow and the repay it points to
+    let wrapper = Instruction { program_id: JUPITER, accounts: vec![], data: vec![] };
+    let outer = [wrapper, flash_repay(&market, &borrower, protocol.insurance_fund)];
+    let result = bank.process_as_cpi(&outer, 0, flash_borrow(&market, &borrower, SOL));
+    assert_eq!(result, Err(err(FluxError::FlashLoanCpiForbidden)));
+}
+
+#[test]
+fn open_loans_block_reentry() {
+    let (mut bank, protocol, market, borrower) = setup();
+    let owner = init_borrower(&mut bank, &market, 10 * SOL);
+
+    let result = bank.process_transaction(&[
+        flash_borrow(&market, &borrower, SOL),
+        flash_borrow(&market, &borrower, SOL),
+        flash_repay(&market, &borrower, protocol.insurance_fund),
+    ]);
+    assert_eq!(result, Err(err(FluxError::FlashLoanActive)));
+
+    // Borrowed liquidity cannot be borrowed again against collateral before repayment
+    let accounts = manage_debt(&bank, &market, &owner);
+    let result = bank.process_transaction(&[
+        flash_borrow(&market, &borrower, 90 * SOL),
+        ix(accounts, flux_core::instruction::Borrow { amount: SOL }),
+        flash_repay(&market, &borrower, protocol.insurance_fund),
+    ]);
+    assert_eq!(result, Err(err(FluxError::FlashLoanActive)));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 0);
+}
+
+#[test]
+fn repay_pays_only_the_configured_insurance_fund() {
+    let (mut bank, protocol, market, borrower) = setup();
+
+    let attacker_fund = Pubkey::new_unique();
+    let result = bank.process_transaction(&[
+        flash_borrow(&market, &borrower, SOL),
+        flash_repay(&market, &borrower, attacker_fund),
+    ]);
+    assert_eq!(result, Err(err(FluxError::InsuranceFundMismatch)));
+
+    // Only the admin may move the fund, and never to the default key
+    let fund = Pubkey::new_unique();
+    assert_eq!(set_insurance_fund(&mut bank, &protocol.guardian, fund), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(set_insurance_fund(&mut bank, &protocol.admin, Pubkey::default()), Err(err(FluxError::InvalidInsuranceFund)));
+    set_insurance_fund(&mut bank, &protocol.admin, fund).unwrap();
+    assert_eq!(bank.read::<ProtocolConfig>(&protocol.config).insurance_fund, fund);
+
+    let result = bank.process_transaction(&[
+        flash_borrow(&market, &borrower, SOL),
+        flash_repay(&market, &borrower, protocol.insurance_fund),
+    ]);
+    assert_eq!(result, Err(err(FluxError::InsuranceFundMismatch)));
+    bank.process_transaction(&[flash_borrow(&market, &borrower, SOL), flash_repay(&market, &borrower, fund)]).unwrap();
+}
@@ -0,0 +1,128 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+
+use common::*;
+use fixtures::*;
+
+fn leverage(bank: &mut Bank, market: &Market, owner: &Pubkey, target_leverage_bps: u64, min_collateral_out: u64) -> TxResult {
+    let accounts = flux_core::accounts::Leverage {
+        debt: manage_debt(bank, market, owner),
+        jupiter_program: JUPITER,
+        token_in: Pubkey::new_unique(),
+        token_out: Pubkey::new_unique(),
+    };
+    bank.process(ix(accounts, flux_core::instruction::Leverage { target_leverage_bps, min_collateral_out }))
+}
+
+fn unwind(bank: &mut Bank, market: &Market, owner: &Pubkey, target_leverage_bps: u64, min_amount_out: u64) -> TxResult {
+    let accounts = unwind_accounts(bank, market, owner);
+    bank.process(ix(accounts, flux_core::instruction::Unwind { target_leverage_bps, min_amount_out }))
+}
+
+/// 100 SOL to lend and an unlevered owner with 0.05 SOL of collateral (worth 5).
+fn setup() -> (Bank, Market, Pubkey) {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL / 20);
+    (bank, market, owner)
+}
+
+#[test]
+fn leverage_borrows_enough_to_reach_the_target() {
+    let (mut bank, market, owner) = setup();
+
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+
+    // Doubling 5 of equity borrows 5 SOL and buys 0.05 SOL of collateral with it
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.collateral_amount, after.debt_amount), (SOL / 10, 5 * SOL));
+    assert!(bank.logs_contain(&format!("Leveraged to 20000 bps: borrowed {}, bought {} collateral", 5 * SOL, SOL / 20)));
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_collateral, state.total_liabilities), (SOL / 10, 5 * SOL));
+
+    // The swap must deliver what the caller asked for
+    assert_eq!(leverage(&mut bank, &market, &owner, 25000, SOL / 20 + 1), Err(err(FluxError::SlippageExceeded)));
+}
+
+#[test]
+fn leverage_is_capped_by_the_vaults_max_leverage() {
+    let (mut bank, market, owner) = setup();
+    edit_vault(&mut bank, &market, |vault| vault.max_leverage = 2);
+
+    assert_eq!(leverage(&mut bank, &market, &owner, 10000, 0), Err(err(FluxError::LeverageExceeded)));
+    assert_eq!(leverage(&mut bank, &market, &owner, 20001, 0), Err(err(FluxError::LeverageExceeded)));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 0);
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+
+    // Raising the cap still leaves the borrow limit: 3x at a 66.66% LTV is just out of reach
+    edit_vault(&mut bank, &market, |vault| vault.max_leverage = 3);
+    assert_eq!(leverage(&mut bank, &market, &owner, 30000, 0), Err(err(FluxError::InsufficientCollateral)));
+    leverage(&mut bank, &market, &owner, 29000, 0).unwrap();
+    assert!(bank.logs_contain("Leveraged to 29000 bps"));
+}
+
+#[test]
+fn unwind_sells_down_to_the_target() {
+    let (mut bank, market, owner) = setup();
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+
+    assert_eq!(unwind(&mut bank, &market, &owner, 9999, 0), Err(err(FluxError::LeverageExceeded)));
+
+    // 1.5x of 5 equity is 7.5 of collateral: 2.5 is sold to repay 2.5 SOL
+    unwind(&mut bank, &market, &owner, 15000, 0).unwrap();
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.collateral_amount, after.debt_amount), (SOL / 10 - SOL / 40, 5 * SOL / 2));
+    assert!(bank.logs_contain(&format!("Unwound to 15000 bps: sold {} collateral, repaid {}", SOL / 40, 5 * SOL / 2)));
+
+    // A target above the current leverage sells nothing
+    assert_eq!(unwind(&mut bank, &market, &owner, 20000, 0), Err(err(FluxError::InvalidAmount)));
+
+    // 1x closes out the debt
+    assert_eq!(unwind(&mut bank, &market, &owner, 10000, 5 * SOL / 2 + 1), Err(err(FluxError::SlippageExceeded)));
+    unwind(&mut bank, &market, &owner, 10000, 0).unwrap();
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.collateral_amount, after.debt_amount), (SOL / 20, 0));
+    assert_eq!(unwind(&mut bank, &market, &owner, 10000, 0), Err(err(FluxError::InvalidAmount)));
+}
+
+#[test]
+fn unwinding_to_1x_closes_the_loan() {
+    let (mut bank, market, owner) = setup();
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+    assert_eq!(profile(&bank, &owner).active_loans, 1);
+    assert_eq!(close_profile(&mut bank, &owner, false), Err(err(FluxError::ActiveLoansOutstanding)));
+
+    unwind(&mut bank, &market, &owner, 15000, 0).unwrap();
+    assert_eq!(profile(&bank, &owner).active_loans, 1);
+    unwind(&mut bank, &market, &owner, 10000, 0).unwrap();
+    assert_eq!(profile(&bank, &owner).active_loans, 0);
+
+    // A new loan opens a single loan again
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+    assert_eq!(profile(&bank, &owner).active_loans, 1);
+    unwind(&mut bank, &market, &owner, 10000, 0).unwrap();
+    close_profile(&mut bank, &owner, false).unwrap();
+}
+
+#[test]
+fn unwind_requires_the_vaults_price_feeds() {
+    let (mut bank, market, owner) = setup();
+    leverage(&mut bank, &market, &owner, 20000, 0).unwrap();
+
+    // A crashed collateral price would size the sale to all of the collateral
+    let crashed = price_feed(&mut bank, COLLATERAL_PRICE / 2, 6);
+    let mut accounts = unwind_accounts(&bank, &market, &owner);
+    accounts.price_feed_collateral = crashed;
+    let result = bank.process(ix(accounts, flux_core::instruction::Unwind { target_leverage_bps: 10000, min_amount_out: 0 }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    let inflated = price_feed(&mut bank, DEBT_PRICE * 10, 6);
+    let mut accounts = unwind_accounts(&bank, &market, &owner);
+    accounts.price_feed_debt = inflated;
+    let result = bank.process(ix(accounts, flux_core::instruction::Unwind { target_leverage_bps: 10000, min_amount_out: 0 }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 5 * SOL);
+}
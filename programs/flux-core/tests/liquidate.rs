@@ -0,0 +1,86 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::Pubkey;
+use flux_core::errors::FluxError;
+
+use common::*;
+use fixtures::*;
+
+/// A borrower with 0.1 SOL of collateral (worth 10) owing 6 SOL of debt (worth 6).
+fn setup() -> (Bank, Market, Pubkey, Pubkey) {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    let liquidator = bank.funded_key();
+    (bank, market, owner, liquidator)
+}
+
+#[test]
+fn liquidation_requires_the_vaults_price_feeds() {
+    let (mut bank, market, owner, liquidator) = setup();
+
+    // A feed crashing the collateral price would make a healthy position look liquidatable
+    let crashed = price_feed(&mut bank, COLLATERAL_PRICE / 10, 6);
+    let mut accounts = liquidate_accounts(&bank, &market, &owner, &liquidator);
+    accounts.price_feed_collateral = crashed;
+    let result = bank.process(ix(accounts, flux_core::instruction::LiquidatePosition {}));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    let inflated = price_feed(&mut bank, DEBT_PRICE * 10, 6);
+    let mut accounts = liquidate_accounts(&bank, &market, &owner, &liquidator);
+    accounts.price_feed_debt = inflated;
+    let result = bank.process(ix(accounts, flux_core::instruction::LiquidatePosition {}));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    assert_eq!(liquidate(&mut bank, &market, &owner, &liquidator), Err(err(FluxError::VaultHealthy)));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 6 * SOL);
+}
+
+#[test]
+fn liquidation_starts_below_the_minimum_collateral_ratio() {
+    let (mut bank, market, owner, liquidator) = setup();
+
+    // 10 of collateral against 6 of debt is 167%; at 73 it is 122%, still above the 120% minimum
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 73 / 100, 6);
+    assert_eq!(liquidate(&mut bank, &market, &owner, &liquidator), Err(err(FluxError::VaultHealthy)));
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    liquidate(&mut bank, &market, &owner, &liquidator).unwrap();
+}
+
+#[test]
+fn liquidation_seizes_half_the_debt_plus_the_bonus() {
+    let (mut bank, market, owner, liquidator) = setup();
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    let before = vault(&bank, &market);
+
+    liquidate(&mut bank, &market, &owner, &liquidator).unwrap();
+
+    // 3 SOL of debt repaid with collateral worth 3 * 1.05 at 70
+    let seized = 45_000_000;
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.debt_amount, after.collateral_amount), (3 * SOL, SOL / 10 - seized));
+    let state = vault(&bank, &market);
+    assert_eq!(state.total_liabilities, before.total_liabilities - 3 * SOL);
+    assert_eq!(state.total_collateral, before.total_collateral - seized);
+    assert_eq!(state.total_assets, before.total_assets);
+}
+
+#[test]
+fn seizure_is_capped_by_the_collateral_held() {
+    let (mut bank, market, owner, liquidator) = setup();
+    // Collateral worth 2 cannot cover 3 * 1.05 of repayment; the repayment shrinks to match
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 20 / 100, 6);
+
+    liquidate(&mut bank, &market, &owner, &liquidator).unwrap();
+
+    let repaid = (3 * SOL as u128 * 2000 / 3150) as u64;
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.debt_amount, after.collateral_amount), (6 * SOL - repaid, 0));
+    assert_eq!(vault(&bank, &market).total_collateral, 0);
+
+    // Nothing is left to seize
+    assert_eq!(liquidate(&mut bank, &market, &owner, &liquidator), Err(err(FluxError::InvalidAmount)));
+}
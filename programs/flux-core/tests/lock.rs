@@ -0,0 +1,236 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+use flux_core::state::deposit_lock::{DepositLock, LockAccount, LockConfig, LockTerm, LockTerms};
+use flux_core::state::pipeline::PipelineStep;
+use flux_core::state::vault::Vault;
+
+use common::*;
+use fixtures::*;
+
+const DAY: i64 = 24 * 60 * 60;
+const YEAR: i64 = 365 * DAY;
+
+/// 10% boost on 90-day locks, 5% penalty for leaving early.
+const TERMS: LockTerms = LockTerms { boost_bps: [500, 1000, 1500], early_exit_penalty_bps: 500 };
+
+fn lock_config_address(market: &Market) -> Pubkey {
+    pda(&[b"lock_config", market.vault.as_ref()], &flux_core::ID)
+}
+
+fn lock_account_address(market: &Market, owner: &Pubkey) -> Pubkey {
+    pda(&[b"lock_account", market.vault.as_ref(), owner.as_ref()], &flux_core::ID)
+}
+
+fn lock_config(bank: &Bank, market: &Market) -> LockConfig {
+    bank.read(&lock_config_address(market))
+}
+
+fn locks(bank: &Bank, market: &Market, owner: &Pubkey) -> Vec<DepositLock> {
+    bank.read::<LockAccount>(&lock_account_address(market, owner)).locks
+}
+
+fn init_lock_config(bank: &mut Bank, market: &Market, authority: &Pubkey, terms: LockTerms) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::InitLockConfig {
+            vault: market.vault,
+            lock_config: lock_config_address(market),
+            protocol_config: protocol_config_address(),
+            authority: *authority,
+            system_program: anchor_lang::system_program::ID,
+        },
+        flux_core::instruction::InitLockConfig { terms },
+    ))
+}
+
+fn update_lock_config(bank: &mut Bank, market: &Market, authority: &Pubkey, terms: LockTerms) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::UpdateLockConfig {
+            vault: market.vault,
+            lock_config: lock_config_address(market),
+            protocol_config: protocol_config_address(),
+            authority: *authority,
+        },
+        flux_core::instruction::UpdateLockConfig { terms },
+    ))
+}
+
+fn lock(bank: &mut Bank, market: &Market, owner: &Pubkey, amount: u64, term: LockTerm) -> TxResult {
+    let accounts = flux_core::accounts::LockDeposit {
+        vault: market.vault,
+        lock_config: lock_config_address(market),
+        lock_account: lock_account_address(market, owner),
+        withdrawal_queue: withdrawal_queue(bank, market),
+        protocol_config: protocol_config_address(),
+        owner: *owner,
+        owner_token_account: Pubkey::new_unique(),
+    };
+    bank.process(ix(accounts, flux_core::instruction::LockDeposit { amount, term }))
+}
+
+fn unlock_accounts(market: &Market, owner: &Pubkey) -> flux_core::accounts::UnlockDeposit {
+    flux_core::accounts::UnlockDeposit {
+        vault: market.vault,
+        lock_config: lock_config_address(market),
+        lock_account: lock_account_address(market, owner),
+        protocol_config: protocol_config_address(),
+        owner: *owner,
+        owner_token_account: Pubkey::new_unique(),
+        incinerator_program: Pubkey::new_unique(),
+    }
+}
+
+fn withdraw_lock(bank: &mut Bank, market: &Market, owner: &Pubkey, index: u8) -> TxResult {
+    bank.process(ix(unlock_accounts(market, owner), flux_core::instruction::WithdrawLock { index }))
+}
+
+fn exit_lock_early(bank: &mut Bank, market: &Market, owner: &Pubkey, index: u8) -> TxResult {
+    bank.process(ix(unlock_accounts(market, owner), flux_core::instruction::ExitLockEarly { index }))
+}
+
+/// Boost on `amount` over the full term at `bps`.
+fn full_term_boost(amount: u64, bps: u16, term: LockTerm) -> u64 {
+    (amount as u128 * bps as u128 * term.duration() as u128 / (10000 * Vault::SECONDS_PER_YEAR)) as u64
+}
+
+/// 100 SOL supplied at a 2% management fee, locks enabled a year ago so the budget holds
+/// a year of fees, and a user with an open lock account.
+fn setup() -> (Bank, Protocol, Market, Pubkey) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    edit_vault(&mut bank, &market, |vault| vault.management_fee_rate = 200);
+    init_lock_config(&mut bank, &market, &protocol.fee_manager, TERMS).unwrap();
+    bank.warp(YEAR);
+    update_lock_config(&mut bank, &market, &protocol.fee_manager, TERMS).unwrap();
+
+    let owner = bank.funded_key();
+    bank.process(ix(
+        flux_core::accounts::OpenLockAccount {
+            vault: market.vault,
+            lock_account: lock_account_address(&market, &owner),
+            owner,
+            system_program: anchor_lang::system_program::ID,
+        },
+        flux_core::instruction::OpenLockAccount {},
+    ))
+    .unwrap();
+    (bank, protocol, market, owner)
+}
+
+#[test]
+fn terms_are_set_by_the_fee_manager_within_limits() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+
+    assert_eq!(init_lock_config(&mut bank, &market, &market.authority, TERMS), Err(err(FluxError::UnauthorizedAccess)));
+    let greedy = LockTerms { boost_bps: [500, 1000, LockTerms::MAX_BOOST_BPS + 1], ..TERMS };
+    assert_eq!(init_lock_config(&mut bank, &market, &protocol.fee_manager, greedy), Err(err(FluxError::InvalidLockConfig)));
+    let punitive = LockTerms { early_exit_penalty_bps: LockTerms::MAX_EARLY_EXIT_PENALTY_BPS + 1, ..TERMS };
+    assert_eq!(init_lock_config(&mut bank, &market, &protocol.fee_manager, punitive), Err(err(FluxError::InvalidLockConfig)));
+
+    init_lock_config(&mut bank, &market, &protocol.fee_manager, TERMS).unwrap();
+    assert_eq!(lock_config(&bank, &market).terms(), TERMS);
+    assert_eq!(update_lock_config(&mut bank, &market, &protocol.guardian, TERMS), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn the_management_fee_fills_the_boost_budget() {
+    let (mut bank, protocol, market, _) = setup();
+
+    // A year at 2% of 100 SOL leaves the vault for the budget
+    let config = lock_config(&bank, &market);
+    assert_eq!((config.boost_budget, config.boost_reserved), (2 * SOL, 0));
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_assets, state.last_fee_collection), (98 * SOL, bank.now));
+
+    // Unloading in a rebalance burns nothing the budget is owed
+    bank.warp(YEAR / 2);
+    let accounts = rebalance_accounts(&bank, &market, &market.authority);
+    bank.process(ix(accounts, flux_core::instruction::Rebalance { steps: vec![PipelineStep::Unload] })).unwrap();
+    assert_eq!(vault(&bank, &market).total_assets, 98 * SOL);
+
+    update_lock_config(&mut bank, &market, &protocol.fee_manager, TERMS).unwrap();
+    assert_eq!(lock_config(&bank, &market).boost_budget, 2 * SOL + 98 * SOL / 100);
+    // Collected once per period
+    update_lock_config(&mut bank, &market, &protocol.fee_manager, TERMS).unwrap();
+    assert_eq!(lock_config(&bank, &market).boost_budget, 2 * SOL + 98 * SOL / 100);
+}
+
+#[test]
+fn locks_reserve_the_full_term_boost_up_front() {
+    let (mut bank, protocol, market, owner) = setup();
+
+    lock(&mut bank, &market, &owner, 10 * SOL, LockTerm::Days90).unwrap();
+    let boost = full_term_boost(10 * SOL, 1000, LockTerm::Days90);
+    let opened = locks(&bank, &market, &owner);
+    assert_eq!(opened.len(), 1);
+    assert_eq!((opened[0].amount, opened[0].boost_bps, opened[0].boost), (10 * SOL, 1000, boost));
+    assert_eq!(opened[0].unlocks_at, bank.now + 90 * DAY);
+    let config = lock_config(&bank, &market);
+    assert_eq!((config.boost_budget, config.boost_reserved, config.total_locked), (2 * SOL - boost, boost, 10 * SOL));
+    assert_eq!(vault(&bank, &market).total_assets, 108 * SOL);
+
+    // New terms apply only to new locks
+    let lower = LockTerms { boost_bps: [100, 200, 300], ..TERMS };
+    update_lock_config(&mut bank, &market, &protocol.fee_manager, lower).unwrap();
+    lock(&mut bank, &market, &owner, 10 * SOL, LockTerm::Days90).unwrap();
+    let opened = locks(&bank, &market, &owner);
+    assert_eq!((opened[0].boost_bps, opened[0].boost), (1000, boost));
+    assert_eq!((opened[1].boost_bps, opened[1].boost), (200, full_term_boost(10 * SOL, 200, LockTerm::Days90)));
+}
+
+#[test]
+fn locks_cannot_promise_more_than_the_budget() {
+    let (mut bank, _, market, owner) = setup();
+
+    // 15% for half a year on 30 SOL is 2.22 SOL, beyond the 2 SOL budget
+    assert!(full_term_boost(30 * SOL, 1500, LockTerm::Days180) > 2 * SOL);
+    assert_eq!(lock(&mut bank, &market, &owner, 30 * SOL, LockTerm::Days180), Err(err(FluxError::InsufficientBoostBudget)));
+    assert!(locks(&bank, &market, &owner).is_empty());
+
+    lock(&mut bank, &market, &owner, 20 * SOL, LockTerm::Days180).unwrap();
+    let config = lock_config(&bank, &market);
+    assert_eq!(config.boost_budget + config.boost_reserved, 2 * SOL);
+}
+
+#[test]
+fn matured_locks_withdraw_with_the_boost() {
+    let (mut bank, _, market, owner) = setup();
+    lock(&mut bank, &market, &owner, 10 * SOL, LockTerm::Days30).unwrap();
+    let boost = full_term_boost(10 * SOL, 500, LockTerm::Days30);
+
+    bank.warp(30 * DAY - 1);
+    assert_eq!(withdraw_lock(&mut bank, &market, &owner, 0), Err(err(FluxError::LockNotMatured)));
+    assert_eq!(withdraw_lock(&mut bank, &market, &owner, 1), Err(err(FluxError::InvalidLockIndex)));
+
+    bank.warp(1);
+    assert_eq!(exit_lock_early(&mut bank, &market, &owner, 0), Err(err(FluxError::LockMatured)));
+    let before = vault(&bank, &market).total_assets;
+    withdraw_lock(&mut bank, &market, &owner, 0).unwrap();
+    assert!(bank.logs_contain(&format!("Unlocked {} (boost {}, penalty 0)", 10 * SOL, boost)));
+
+    assert!(locks(&bank, &market, &owner).is_empty());
+    let config = lock_config(&bank, &market);
+    assert_eq!((config.boost_budget, config.boost_reserved, config.total_locked), (2 * SOL - boost, 0, 0));
+    assert_eq!(vault(&bank, &market).total_assets, before - 10 * SOL);
+}
+
+#[test]
+fn early_exits_forfeit_the_boost_and_pay_the_penalty() {
+    let (mut bank, _, market, owner) = setup();
+    lock(&mut bank, &market, &owner, 10 * SOL, LockTerm::Days90).unwrap();
+    lock(&mut bank, &market, &owner, 4 * SOL, LockTerm::Days30).unwrap();
+    let kept = full_term_boost(4 * SOL, 500, LockTerm::Days30);
+
+    bank.warp(45 * DAY);
+    exit_lock_early(&mut bank, &market, &owner, 0).unwrap();
+    // 5% of the principal is burned and the whole boost goes back to the budget
+    assert!(bank.logs_contain(&format!("Unlocked {} (boost 0, penalty {})", 10 * SOL, SOL / 2)));
+
+    let remaining = locks(&bank, &market, &owner);
+    assert_eq!((remaining.len(), remaining[0].amount), (1, 4 * SOL));
+    let config = lock_config(&bank, &market);
+    assert_eq!((config.boost_budget, config.boost_reserved, config.total_locked), (2 * SOL - kept, kept, 4 * SOL));
+}
@@ -0,0 +1,237 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::system_program;
+use flux_core::errors::FluxError;
+use flux_core::state::config::{ProtocolConfig, ProtocolRoles};
+use flux_core::state::multisig::{AdminMultisig, AdminProposal, ProposedAccountMeta, ProposedInstruction};
+
+use common::*;
+use fixtures::*;
+
+const DAY: i64 = 24 * 60 * 60;
+
+fn multisig_address() -> Pubkey {
+    pda(&[b"admin_multisig"], &flux_core::ID)
+}
+
+fn signer_address() -> Pubkey {
+    pda(&[b"multisig_signer"], &flux_core::ID)
+}
+
+fn proposal_address(nonce: u64) -> Pubkey {
+    pda(&[b"admin_proposal", nonce.to_le_bytes().as_ref()], &flux_core::ID)
+}
+
+fn multisig(bank: &Bank) -> AdminMultisig {
+    bank.read(&multisig_address())
+}
+
+fn proposal(bank: &Bank, nonce: u64) -> AdminProposal {
+    bank.read(&proposal_address(nonce))
+}
+
+/// A protocol whose admin role is held by a 2-of-3 multisig. Returns the members.
+fn setup() -> (Bank, Protocol, Vec<Pubkey>) {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let members: Vec<Pubkey> = (0..3).map(|_| bank.funded_key()).collect();
+    bank.process(ix(
+        flux_core::accounts::InitAdminMultisig {
+            multisig: multisig_address(),
+            protocol_config: protocol.config,
+            admin: protocol.admin,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::InitAdminMultisig { signers: members.clone(), threshold: 2 },
+    ))
+    .unwrap();
+    bank.process(ix(
+        flux_core::accounts::UpdateProtocolRoles { protocol_config: protocol.config, admin: protocol.admin },
+        flux_core::instruction::UpdateProtocolRoles {
+            roles: ProtocolRoles {
+                admin: signer_address(),
+                guardian: protocol.guardian,
+                risk_manager: protocol.risk_manager,
+                fee_manager: protocol.fee_manager,
+            },
+        },
+    ))
+    .unwrap();
+    (bank, protocol, members)
+}
+
+fn proposed(instruction: &Instruction) -> ProposedInstruction {
+    ProposedInstruction {
+        accounts: instruction.accounts.iter().map(|meta| ProposedAccountMeta {
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        }).collect(),
+        data: instruction.data.clone(),
+    }
+}
+
+/// `set_insurance_fund`, signed by the multisig as admin.
+fn set_insurance_fund(insurance_fund: Pubkey) -> Instruction {
+    ix(
+        flux_core::accounts::UpdateProtocolRoles { protocol_config: protocol_config_address(), admin: signer_address() },
+        flux_core::instruction::SetInsuranceFund { insurance_fund },
+    )
+}
+
+fn set_members(signers: Vec<Pubkey>, threshold: u8) -> Instruction {
+    ix(
+        flux_core::accounts::SetMultisigMembers { multisig: multisig_address(), multisig_signer: signer_address() },
+        flux_core::instruction::SetMultisigMembers { signers, threshold },
+    )
+}
+
+/// Propose `instruction` as the next proposal; returns its nonce.
+fn create(bank: &mut Bank, proposer: &Pubkey, instruction: &Instruction, expires_in: i64) -> std::result::Result<u64, ProgramError> {
+    let nonce = multisig(bank).proposal_count;
+    bank.process(ix(
+        flux_core::accounts::CreateAdminProposal {
+            multisig: multisig_address(),
+            proposal: proposal_address(nonce),
+            proposer: *proposer,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::CreateAdminProposal { instruction: proposed(instruction), expires_in },
+    ))?;
+    Ok(nonce)
+}
+
+fn approve(bank: &mut Bank, member: &Pubkey, nonce: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ApproveAdminProposal {
+            multisig: multisig_address(),
+            proposal: proposal_address(nonce),
+            member: *member,
+        },
+        flux_core::instruction::ApproveAdminProposal {},
+    ))
+}
+
+/// Execute proposal `nonce`, passing `instruction`'s accounts through. The multisig
+/// signer PDA cannot sign the transaction, so it is passed unsigned.
+fn execute(bank: &mut Bank, nonce: u64, instruction: &Instruction) -> TxResult {
+    let executor = bank.funded_key();
+    let mut execute = ix(
+        flux_core::accounts::ExecuteAdminProposal {
+            multisig: multisig_address(),
+            proposal: proposal_address(nonce),
+            multisig_signer: signer_address(),
+            flux_program: flux_core::ID,
+            executor,
+        },
+        flux_core::instruction::ExecuteAdminProposal {},
+    );
+    execute.accounts.extend(instruction.accounts.iter().map(|meta| AccountMeta {
+        pubkey: meta.pubkey,
+        is_signer: false,
+        is_writable: meta.is_writable,
+    }));
+    bank.process(execute)
+}
+
+#[test]
+fn members_create_proposals() {
+    let (mut bank, _, members) = setup();
+    let instruction = set_insurance_fund(Pubkey::new_unique());
+
+    let stranger = bank.funded_key();
+    assert_eq!(create(&mut bank, &stranger, &instruction, DAY), Err(err(FluxError::NotMultisigMember)));
+    for expires_in in [AdminMultisig::MIN_EXPIRY - 1, AdminMultisig::MAX_EXPIRY + 1] {
+        assert_eq!(create(&mut bank, &members[1], &instruction, expires_in), Err(err(FluxError::InvalidMultisigConfig)));
+    }
+
+    let nonce = create(&mut bank, &members[1], &instruction, DAY).unwrap();
+    let created = proposal(&bank, nonce);
+    assert_eq!((created.nonce, created.proposer), (0, members[1]));
+    assert_eq!(created.instruction, proposed(&instruction));
+    // Proposing counts as the proposer's approval
+    assert_eq!((created.approvals, created.config_version), (0b010, 0));
+    assert_eq!((created.created_at, created.expires_at), (bank.now, bank.now + DAY));
+    assert!(!created.executed);
+    assert_eq!(multisig(&bank).proposal_count, 1);
+}
+
+#[test]
+fn proposals_execute_once_past_the_threshold() {
+    let (mut bank, _, members) = setup();
+    let insurance_fund = Pubkey::new_unique();
+    let instruction = set_insurance_fund(insurance_fund);
+    let nonce = create(&mut bank, &members[0], &instruction, DAY).unwrap();
+
+    assert_eq!(execute(&mut bank, nonce, &instruction), Err(err(FluxError::InsufficientApprovals)));
+    assert_eq!(approve(&mut bank, &members[0], nonce), Err(err(FluxError::AlreadyApproved)));
+    approve(&mut bank, &members[2], nonce).unwrap();
+    assert_eq!(proposal(&bank, nonce).approval_count(), 2);
+
+    execute(&mut bank, nonce, &instruction).unwrap();
+    assert!(proposal(&bank, nonce).executed);
+    let config: ProtocolConfig = bank.read(&protocol_config_address());
+    assert_eq!(config.insurance_fund, insurance_fund);
+}
+
+#[test]
+fn executed_proposals_cannot_be_replayed() {
+    let (mut bank, _, members) = setup();
+    let instruction = set_insurance_fund(Pubkey::new_unique());
+    let nonce = create(&mut bank, &members[0], &instruction, DAY).unwrap();
+    approve(&mut bank, &members[1], nonce).unwrap();
+    execute(&mut bank, nonce, &instruction).unwrap();
+
+    assert_eq!(execute(&mut bank, nonce, &instruction), Err(err(FluxError::ProposalAlreadyExecuted)));
+    assert_eq!(approve(&mut bank, &members[2], nonce), Err(err(FluxError::ProposalAlreadyExecuted)));
+}
+
+#[test]
+fn only_members_approve() {
+    let (mut bank, protocol, members) = setup();
+    let instruction = set_insurance_fund(Pubkey::new_unique());
+    let nonce = create(&mut bank, &members[0], &instruction, DAY).unwrap();
+
+    // Not even the admin the multisig took over from
+    for outsider in [bank.funded_key(), protocol.admin] {
+        assert_eq!(approve(&mut bank, &outsider, nonce), Err(err(FluxError::NotMultisigMember)));
+    }
+    assert_eq!(proposal(&bank, nonce).approval_count(), 1);
+}
+
+#[test]
+fn expired_proposals_are_rejected() {
+    let (mut bank, _, members) = setup();
+    let instruction = set_insurance_fund(Pubkey::new_unique());
+    let nonce = create(&mut bank, &members[0], &instruction, DAY).unwrap();
+    approve(&mut bank, &members[1], nonce).unwrap();
+
+    bank.warp(DAY);
+    assert_eq!(approve(&mut bank, &members[2], nonce), Err(err(FluxError::ProposalExpired)));
+    assert_eq!(execute(&mut bank, nonce, &instruction), Err(err(FluxError::ProposalExpired)));
+    assert!(!proposal(&bank, nonce).executed);
+}
+
+#[test]
+fn membership_changes_invalidate_open_proposals() {
+    let (mut bank, _, members) = setup();
+    let instruction = set_insurance_fund(Pubkey::new_unique());
+    let open = create(&mut bank, &members[0], &instruction, DAY).unwrap();
+    approve(&mut bank, &members[1], open).unwrap();
+
+    let rotated = vec![members[0], members[1], bank.funded_key()];
+    let change = set_members(rotated.clone(), 2);
+    let nonce = create(&mut bank, &members[0], &change, DAY).unwrap();
+    approve(&mut bank, &members[2], nonce).unwrap();
+    execute(&mut bank, nonce, &change).unwrap();
+
+    let updated = multisig(&bank);
+    assert_eq!((updated.signers, updated.threshold, updated.config_version), (rotated, 2, 1));
+
+    // Approved under the old membership, so no longer executable
+    assert_eq!(execute(&mut bank, open, &instruction), Err(err(FluxError::StaleProposal)));
+    assert_eq!(approve(&mut bank, &members[0], open), Err(err(FluxError::StaleProposal)));
+}
@@ -0,0 +1,148 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+use flux_core::state::order::{OrderAction, OrderKind, OrderParams};
+
+use common::*;
+use fixtures::*;
+
+/// Stop-loss at 80 repaying 2 SOL, with 1% slippage and a 0.001 SOL tip.
+const STOP_LOSS: OrderParams = OrderParams {
+    kind: OrderKind::StopLoss,
+    trigger_price: COLLATERAL_PRICE * 80 / 100,
+    action: OrderAction::Repay { amount: 2 * SOL },
+    max_slippage_bps: 100,
+    keeper_tip: SOL / 1000,
+};
+
+fn execute_order(bank: &mut Bank, market: &Market, owner: &Pubkey, id: u8, executor: &Pubkey) -> TxResult {
+    let accounts = execute_order_accounts(bank, market, owner, id, executor);
+    bank.process(ix(accounts, flux_core::instruction::ExecuteOrder {}))
+}
+
+/// A borrower with 0.1 SOL of collateral (worth 10) owing 6 SOL, and a keeper.
+fn setup() -> (Bank, Market, Pubkey, Pubkey) {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    let keeper = bank.funded_key();
+    (bank, market, owner, keeper)
+}
+
+#[test]
+fn orders_are_validated() {
+    let (mut bank, market, owner, _) = setup();
+
+    for params in [
+        OrderParams { trigger_price: 0, ..STOP_LOSS },
+        OrderParams { max_slippage_bps: OrderParams::MAX_SLIPPAGE_BPS + 1, ..STOP_LOSS },
+        OrderParams { action: OrderAction::Repay { amount: 0 }, ..STOP_LOSS },
+    ] {
+        assert_eq!(place_order(&mut bank, &market, &owner, 0, params), Err(err(FluxError::InvalidOrder)));
+    }
+    place_order(&mut bank, &market, &owner, 0, STOP_LOSS).unwrap();
+}
+
+#[test]
+fn orders_execute_only_once_the_collateral_price_crosses_the_trigger() {
+    let (mut bank, market, owner, keeper) = setup();
+    place_order(&mut bank, &market, &owner, 0, STOP_LOSS).unwrap();
+    let take_profit = OrderParams { kind: OrderKind::TakeProfit, trigger_price: COLLATERAL_PRICE * 120 / 100, ..STOP_LOSS };
+    place_order(&mut bank, &market, &owner, 1, take_profit).unwrap();
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 81 / 100, 6);
+    assert_eq!(execute_order(&mut bank, &market, &owner, 0, &keeper), Err(err(FluxError::OrderNotTriggered)));
+    assert_eq!(execute_order(&mut bank, &market, &owner, 1, &keeper), Err(err(FluxError::OrderNotTriggered)));
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 80 / 100, 6);
+    execute_order(&mut bank, &market, &owner, 0, &keeper).unwrap();
+    assert!(!bank.exists(&order_address(&market, &owner, 0)));
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 119 / 100, 6);
+    assert_eq!(execute_order(&mut bank, &market, &owner, 1, &keeper), Err(err(FluxError::OrderNotTriggered)));
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 120 / 100, 6);
+    execute_order(&mut bank, &market, &owner, 1, &keeper).unwrap();
+    assert!(!bank.exists(&order_address(&market, &owner, 1)));
+}
+
+#[test]
+fn sales_are_sized_from_the_oracle_quote_plus_slippage() {
+    let (mut bank, market, owner, keeper) = setup();
+    place_order(&mut bank, &market, &owner, 0, OrderParams { kind: OrderKind::TakeProfit, trigger_price: COLLATERAL_PRICE, ..STOP_LOSS }).unwrap();
+
+    // 2 SOL is quoted at 0.02 SOL of collateral; 1% more is sold and all of it repays
+    execute_order(&mut bank, &market, &owner, 0, &keeper).unwrap();
+    let sold = 2 * SOL / 100 * 101 / 100;
+    assert!(bank.logs_contain(&format!("sold {}, repaid {}", sold, 2 * SOL * 101 / 100)));
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.collateral_amount, after.debt_amount), (SOL / 10 - sold - SOL / 1000, 6 * SOL - 2 * SOL * 101 / 100));
+
+    // Closing repays the debt exactly; the excess belongs to the owner
+    let close = OrderParams { kind: OrderKind::TakeProfit, trigger_price: COLLATERAL_PRICE, action: OrderAction::Close, ..STOP_LOSS };
+    place_order(&mut bank, &market, &owner, 1, close).unwrap();
+    execute_order(&mut bank, &market, &owner, 1, &keeper).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 0);
+
+    // With the debt closed the owner holds no loan and may close the profile
+    assert_eq!(profile(&bank, &owner).active_loans, 0);
+    close_profile(&mut bank, &owner, false).unwrap();
+    assert!(!bank.exists(&profile_address(&owner)));
+}
+
+#[test]
+fn closing_fails_when_the_collateral_cannot_cover_the_debt() {
+    let (mut bank, market, owner, keeper) = setup();
+    let close = OrderParams { action: OrderAction::Close, trigger_price: COLLATERAL_PRICE * 60 / 100, ..STOP_LOSS };
+    place_order(&mut bank, &market, &owner, 0, close).unwrap();
+
+    // At 60 everything but the tip fetches 5.94 SOL against 6 owed
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 60 / 100, 6);
+    assert_eq!(execute_order(&mut bank, &market, &owner, 0, &keeper), Err(err(FluxError::SlippageExceeded)));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 6 * SOL);
+}
+
+#[test]
+fn the_tip_comes_out_of_collateral_and_cannot_hurt_health() {
+    let (mut bank, market, owner, keeper) = setup();
+    let small = OrderParams {
+        trigger_price: COLLATERAL_PRICE,
+        action: OrderAction::Repay { amount: SOL / 10 },
+        max_slippage_bps: 0,
+        keeper_tip: SOL / 20,
+        ..STOP_LOSS
+    };
+    place_order(&mut bank, &market, &owner, 0, small).unwrap();
+
+    // Half the collateral as a tip for repaying 0.1 SOL would leave the position worse off
+    assert_eq!(execute_order(&mut bank, &market, &owner, 0, &keeper), Err(err(FluxError::DeleverageWorsensHealth)));
+
+    let fair = OrderParams { action: OrderAction::Repay { amount: SOL }, keeper_tip: SOL / 1000, ..small };
+    place_order(&mut bank, &market, &owner, 1, fair).unwrap();
+    let before = vault(&bank, &market).total_collateral;
+    execute_order(&mut bank, &market, &owner, 1, &keeper).unwrap();
+    // 0.01 SOL sold for 1 SOL, plus the tip
+    assert_eq!(vault(&bank, &market).total_collateral, before - SOL / 100 - SOL / 1000);
+    assert_eq!(position(&bank, &market, &owner).collateral_amount, SOL / 10 - SOL / 100 - SOL / 1000);
+}
+
+#[test]
+fn execution_requires_the_vaults_price_feeds() {
+    let (mut bank, market, owner, keeper) = setup();
+    place_order(&mut bank, &market, &owner, 0, STOP_LOSS).unwrap();
+
+    // An inflated debt price would size the sale to all of the collateral
+    let inflated = price_feed(&mut bank, DEBT_PRICE * 10, 6);
+    let mut accounts = execute_order_accounts(&bank, &market, &owner, 0, &keeper);
+    accounts.price_feed_debt = inflated;
+    let result = bank.process(ix(accounts, flux_core::instruction::ExecuteOrder {}));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    let crashed = price_feed(&mut bank, COLLATERAL_PRICE / 2, 6);
+    let mut accounts = execute_order_accounts(&bank, &market, &owner, 0, &keeper);
+    accounts.price_feed_collateral = crashed;
+    let result = bank.process(ix(accounts, flux_core::instruction::ExecuteOrder {}));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+}
@@ -0,0 +1,130 @@
+mod common;
+mod fixtures;
+
+use flux_core::errors::FluxError;
+use flux_core::state::vault::{Vault, VaultConfigUpdate};
+
+use common::*;
+use fixtures::*;
+
+#[test]
+fn borrow_requires_the_vaults_price_feeds() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL);
+
+    // A feed reporting a collateral price 100x the real one would unlock 100x the debt
+    let inflated = price_feed(&mut bank, COLLATERAL_PRICE * 100, 6);
+    let mut accounts = manage_debt(&bank, &market, &owner);
+    accounts.price_feed_collateral = inflated;
+    let result = bank.process(ix(accounts, flux_core::instruction::Borrow { amount: 5 * SOL }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    let deflated = price_feed(&mut bank, DEBT_PRICE / 100, 6);
+    let mut accounts = manage_debt(&bank, &market, &owner);
+    accounts.price_feed_debt = deflated;
+    let result = bank.process(ix(accounts, flux_core::instruction::Repay { amount: 1 }));
+    assert_eq!(result, Err(err(FluxError::OracleMismatch)));
+
+    borrow(&mut bank, &market, &owner, 5 * SOL).unwrap();
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 5 * SOL);
+}
+
+#[test]
+fn collateral_is_tracked_apart_from_lendable_assets() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 0);
+    let owner = init_borrower(&mut bank, &market, 10 * SOL);
+
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_collateral, state.total_assets), (10 * SOL, 0));
+
+    // Collateral is not liquidity: with nothing supplied there is nothing to borrow
+    assert_eq!(borrow(&mut bank, &market, &owner, SOL), Err(err(FluxError::InsufficientLiquidity)));
+
+    fetch(&mut bank, &market, 2 * SOL).unwrap();
+    borrow(&mut bank, &market, &owner, 2 * SOL).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_assets, state.total_liabilities, state.total_collateral), (2 * SOL, 2 * SOL, 10 * SOL));
+    assert_eq!(state.utilization_bps(), 10000);
+}
+
+#[test]
+fn collateral_deposits_ignore_the_supply_cap() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, SOL);
+    edit_vault(&mut bank, &market, |vault| vault.supply_cap = SOL);
+    let owner = init_borrower(&mut bank, &market, 0);
+
+    deposit_collateral(&mut bank, &market, &owner, 50 * SOL).unwrap();
+    assert_eq!(fetch(&mut bank, &market, 1), Err(err(FluxError::SupplyCapExceeded)));
+}
+
+#[test]
+fn borrows_stop_at_the_vault_borrow_cap() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    edit_vault(&mut bank, &market, |vault| vault.borrow_cap = 8 * SOL);
+    let first = init_borrower(&mut bank, &market, SOL);
+    let second = init_borrower(&mut bank, &market, SOL);
+
+    borrow(&mut bank, &market, &first, 5 * SOL).unwrap();
+    // The cap is vault-wide, so the second borrower only gets what is left
+    assert_eq!(borrow(&mut bank, &market, &second, 3 * SOL + 1), Err(err(FluxError::BorrowCapExceeded)));
+    borrow(&mut bank, &market, &second, 3 * SOL).unwrap();
+    assert_eq!(vault(&bank, &market).total_liabilities, 8 * SOL);
+
+    // Repaying frees room under the cap
+    repay(&mut bank, &market, &first, SOL).unwrap();
+    borrow(&mut bank, &market, &second, SOL).unwrap();
+}
+
+#[test]
+fn borrows_stop_at_the_per_user_cap() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    edit_vault(&mut bank, &market, |vault| vault.user_borrow_cap = 5 * SOL);
+    let first = init_borrower(&mut bank, &market, SOL);
+    let second = init_borrower(&mut bank, &market, SOL);
+
+    // Counted on the position's total debt, not per borrow
+    borrow(&mut bank, &market, &first, 3 * SOL).unwrap();
+    borrow(&mut bank, &market, &first, 2 * SOL).unwrap();
+    assert_eq!(borrow(&mut bank, &market, &first, 1), Err(err(FluxError::BorrowCapExceeded)));
+
+    // Each position has its own allowance
+    borrow(&mut bank, &market, &second, 5 * SOL).unwrap();
+    assert_eq!(vault(&bank, &market).total_liabilities, 10 * SOL);
+}
+
+#[test]
+fn borrow_caps_change_through_the_timelock() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL);
+    borrow(&mut bank, &market, &owner, 5 * SOL).unwrap();
+
+    let update = VaultConfigUpdate { borrow_cap: Some(6 * SOL), user_borrow_cap: Some(4 * SOL), ..Default::default() };
+    assert_eq!(propose_config(&mut bank, &market, &protocol.fee_manager, update), Err(err(FluxError::UnauthorizedAccess)));
+    propose_config(&mut bank, &market, &protocol.risk_manager, update).unwrap();
+
+    // Nothing changes while the proposal waits
+    bank.warp(Vault::CONFIG_TIMELOCK - 1);
+    assert_eq!(execute_config(&mut bank, &market, &protocol.risk_manager), Err(err(FluxError::TimelockNotElapsed)));
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+
+    bank.warp(1);
+    execute_config(&mut bank, &market, &protocol.risk_manager).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.borrow_cap, state.user_borrow_cap), (6 * SOL, 4 * SOL));
+
+    // Debt already above the new per-user cap stays, but cannot grow until repaid below it
+    assert_eq!(borrow(&mut bank, &market, &owner, 1), Err(err(FluxError::BorrowCapExceeded)));
+    repay(&mut bank, &market, &owner, 3 * SOL).unwrap();
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+    assert_eq!(borrow(&mut bank, &market, &owner, 1), Err(err(FluxError::BorrowCapExceeded)));
+
+    // A per-user cap above the vault-wide cap is rejected
+    let inverted = VaultConfigUpdate { user_borrow_cap: Some(7 * SOL), ..Default::default() };
+    assert_eq!(propose_config(&mut bank, &market, &protocol.risk_manager, inverted), Err(err(FluxError::InvalidVaultConfig)));
+}
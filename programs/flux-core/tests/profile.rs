@@ -0,0 +1,254 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use flux_core::errors::FluxError;
+use flux_core::state::user_profile::{ActionHistory, ActionType, LegacyUserProfile, UserProfile, UserRole};
+
+use common::*;
+use fixtures::*;
+
+fn resize_history(bank: &mut Bank, owner: &Pubkey, new_capacity: u32) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ResizeActionHistory {
+            user_profile: profile_address(owner),
+            action_history: history_address(owner),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::ResizeActionHistory { new_capacity },
+    ))
+}
+
+fn migrate(bank: &mut Bank, owner: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::MigrateUserProfile {
+            user_profile: profile_address(owner),
+            owner: *owner,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::MigrateUserProfile {},
+    ))
+}
+
+/// Protocol, compliance config and a funded profile ready to transfer.
+fn setup() -> (Bank, Protocol, Pubkey) {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+    let owner = bank.funded_key();
+    init_profile(&mut bank, &owner);
+    (bank, protocol, owner)
+}
+
+/// `count` transfers of 1, 2, 3 ... lamports, spaced past the rate limit.
+fn transfer_n(bank: &mut Bank, protocol: &Protocol, owner: &Pubkey, count: u64) {
+    for amount in 1..=count {
+        bank.warp(31);
+        xfer(bank, protocol, owner, amount).unwrap();
+    }
+}
+
+#[test]
+fn close_also_closes_the_action_history() {
+    let (mut bank, _, owner) = setup();
+    init_history(&mut bank, &owner);
+    let rent = bank.lamports(&profile_address(&owner)) + bank.lamports(&history_address(&owner));
+    let before = bank.lamports(&owner);
+
+    assert_eq!(close_profile(&mut bank, &owner, false), Err(err(FluxError::MissingActionHistory)));
+    close_profile(&mut bank, &owner, true).unwrap();
+
+    assert!(!bank.exists(&profile_address(&owner)));
+    assert!(!bank.exists(&history_address(&owner)));
+    assert_eq!(bank.lamports(&owner), before + rent);
+}
+
+#[test]
+fn close_without_history_needs_no_history_account() {
+    let (mut bank, _, owner) = setup();
+    close_profile(&mut bank, &owner, false).unwrap();
+    assert!(!bank.exists(&profile_address(&owner)));
+}
+
+#[test]
+fn close_rejects_another_profiles_history() {
+    let (mut bank, _, owner) = setup();
+    let other = bank.funded_key();
+    init_profile(&mut bank, &other);
+    init_history(&mut bank, &owner);
+    init_history(&mut bank, &other);
+
+    let result = bank.process(ix(
+        flux_core::accounts::CloseUserProfile {
+            user_profile: profile_address(&owner),
+            action_history: Some(history_address(&other)),
+            owner,
+        },
+        flux_core::instruction::CloseUserProfile {},
+    ));
+    assert_eq!(result, Err(err(anchor_lang::error::ErrorCode::ConstraintSeeds)));
+}
+
+#[test]
+fn history_records_actions_with_instruction_identity() {
+    let (mut bank, protocol, owner) = setup();
+    init_history(&mut bank, &owner);
+    transfer_n(&mut bank, &protocol, &owner, 3);
+
+    let (header, entries) = history_entries(&bank, &owner);
+    assert_eq!((header.len, header.head, header.capacity), (3, 3, ActionHistory::INITIAL_CAPACITY));
+    let amounts: Vec<u64> = entries.iter().map(|entry| entry.amount).collect();
+    assert_eq!(amounts, vec![1, 2, 3]);
+    assert!(entries.iter().all(|entry| entry.action_type == ActionType::Withdraw as u8));
+    assert_eq!(entries[2].timestamp, bank.now);
+    assert_eq!(entries[2].slot, bank.slot);
+}
+
+#[test]
+fn transfers_require_the_history_once_opened() {
+    let (mut bank, protocol, owner) = setup();
+    init_history(&mut bank, &owner);
+    // `xfer` only passes the history when it exists
+    bank.remove_account(&history_address(&owner));
+    bank.warp(31);
+
+    assert_eq!(xfer(&mut bank, &protocol, &owner, 1), Err(err(FluxError::MissingActionHistory)));
+}
+
+#[test]
+fn full_history_overwrites_the_oldest_entry() {
+    let (mut bank, protocol, owner) = setup();
+    init_history(&mut bank, &owner);
+    let capacity = ActionHistory::INITIAL_CAPACITY as u64;
+    transfer_n(&mut bank, &protocol, &owner, capacity + 2);
+
+    let (header, entries) = history_entries(&bank, &owner);
+    assert_eq!((header.len, header.head), (capacity as u32, 2));
+    assert_eq!(entries.first().unwrap().amount, 3);
+    assert_eq!(entries.last().unwrap().amount, capacity + 2);
+}
+
+#[test]
+fn resize_keeps_wrapped_entries_in_order() {
+    let (mut bank, protocol, owner) = setup();
+    init_history(&mut bank, &owner);
+    let capacity = ActionHistory::INITIAL_CAPACITY;
+    transfer_n(&mut bank, &protocol, &owner, capacity as u64 + 5);
+
+    resize_history(&mut bank, &owner, capacity * 2).unwrap();
+    let (header, entries) = history_entries(&bank, &owner);
+    assert_eq!((header.capacity, header.len, header.head), (capacity * 2, capacity, capacity));
+    let amounts: Vec<u64> = entries.iter().map(|entry| entry.amount).collect();
+    assert_eq!(amounts, (6..=capacity as u64 + 5).collect::<Vec<_>>());
+    let data_len = bank.account(&history_address(&owner)).unwrap().data.len();
+    assert_eq!(data_len, ActionHistory::space(capacity * 2));
+
+    // New entries go after the newest, not over the oldest
+    bank.warp(31);
+    xfer(&mut bank, &protocol, &owner, 1000).unwrap();
+    let (header, entries) = history_entries(&bank, &owner);
+    assert_eq!(header.len, capacity + 1);
+    assert_eq!((entries[0].amount, entries.last().unwrap().amount), (6, 1000));
+}
+
+#[test]
+fn resize_rejects_invalid_capacities() {
+    let (mut bank, _, owner) = setup();
+    init_history(&mut bank, &owner);
+    let capacity = ActionHistory::INITIAL_CAPACITY;
+
+    for new_capacity in [capacity, capacity - 1, ActionHistory::MAX_CAPACITY + 1, ActionHistory::MAX_CAPACITY] {
+        // MAX_CAPACITY is valid but more than one instruction can grow the account by
+        assert_eq!(resize_history(&mut bank, &owner, new_capacity), Err(err(FluxError::InvalidHistoryCapacity)));
+    }
+}
+
+/// Borsh-encoded v1 profile, as written before the zero-copy conversion.
+fn legacy_profile_data(owner: &Pubkey, bump: u8) -> Vec<u8> {
+    let mut data = UserProfile::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(owner.as_ref());
+    data.push(42); // reputation_score
+    data.extend_from_slice(&1u32.to_le_bytes()); // active_loans
+    data.extend_from_slice(&500u64.to_le_bytes()); // total_borrowed_lifetime
+    data.extend_from_slice(&300u64.to_le_bytes()); // total_repaid_lifetime
+    data.extend_from_slice(&2u16.to_le_bytes()); // liquidation_count
+    data.extend_from_slice(&(START_TIME - 100).to_le_bytes()); // last_active_timestamp
+    data.push(UserRole::Institutional as u8);
+    data.extend_from_slice(&[0u8; 50 * 25]); // action_history
+    data.push(0); // history_idx
+    data.push(1); // kyc_verified
+    data.push(0); // aml_flagged
+    data.extend_from_slice(b"DE");
+    data.push(bump);
+    data.extend_from_slice(&3u32.to_le_bytes()); // case_count
+    data.push(1); // version
+    assert_eq!(data.len(), LegacyUserProfile::LEN);
+    data
+}
+
+#[test]
+fn migrate_converts_a_legacy_profile() {
+    let mut bank = bank();
+    let owner = bank.funded_key();
+    let key = profile_address(&owner);
+    let bump = Pubkey::find_program_address(&[b"profile", owner.as_ref()], &flux_core::ID).1;
+    let data = legacy_profile_data(&owner, bump);
+    let legacy_rent = Rent::default().minimum_balance(data.len());
+    bank.set_account(key, TestAccount { lamports: legacy_rent, owner: flux_core::ID, data, executable: false });
+    let before = bank.lamports(&owner);
+
+    migrate(&mut bank, &owner).unwrap();
+
+    let account = bank.account(&key).unwrap();
+    assert_eq!(account.data.len(), UserProfile::LEN);
+    assert_eq!(account.lamports, Rent::default().minimum_balance(UserProfile::LEN));
+    assert_eq!(bank.lamports(&owner), before + legacy_rent - account.lamports);
+
+    let migrated = profile(&bank, &owner);
+    assert_eq!(migrated.owner, owner);
+    assert_eq!((migrated.active_loans, migrated.total_borrowed_lifetime, migrated.total_repaid_lifetime), (1, 500, 300));
+    assert_eq!((migrated.liquidation_count, migrated.case_count, migrated.reputation_score), (2, 3, 42));
+    assert_eq!(migrated.role(), UserRole::Institutional);
+    assert!(migrated.is_kyc_verified() && !migrated.is_aml_flagged());
+    assert_eq!(&migrated.country_code, b"DE");
+    assert_eq!((migrated.bump, migrated.version), (bump, UserProfile::CURRENT_VERSION));
+    assert_eq!(migrated.created_at, bank.now);
+}
+
+#[test]
+fn migrate_upgrades_a_v2_profile_in_place() {
+    let mut bank = bank();
+    let owner = bank.funded_key();
+    init_profile(&mut bank, &owner);
+    edit_profile(&mut bank, &owner, |profile| {
+        profile.version = 2;
+        profile.created_at = 0;
+        profile.repayment_count = 0;
+    });
+    bank.warp(1000);
+
+    migrate(&mut bank, &owner).unwrap();
+    let upgraded = profile(&bank, &owner);
+    assert_eq!((upgraded.version, upgraded.created_at), (UserProfile::CURRENT_VERSION, bank.now));
+}
+
+#[test]
+fn migrate_rejects_someone_elses_profile() {
+    let mut bank = bank();
+    let owner = bank.funded_key();
+    let attacker = bank.funded_key();
+    init_profile(&mut bank, &owner);
+
+    let result = bank.process(ix(
+        flux_core::accounts::MigrateUserProfile {
+            user_profile: profile_address(&owner),
+            owner: attacker,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::MigrateUserProfile {},
+    ));
+    assert_eq!(result, Err(err(FluxError::UnauthorizedAccess)));
+}
@@ -0,0 +1,280 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::AccountMeta;
+use flux_core::circuit_breaker::CircuitBreakerParams;
+use flux_core::errors::FluxError;
+use flux_core::state::compliance::{TransferRecord, TravelRuleInfo};
+use flux_core::state::pipeline::{PipelineStep, XferVenue};
+
+use common::*;
+use fixtures::*;
+
+const TRAVEL_RULE: TravelRuleInfo = TravelRuleInfo { payload_hash: [7; 32], counterparty_vasp: [9; 32] };
+
+fn xfer_step(amount: u64) -> PipelineStep {
+    PipelineStep::Xfer { amount, travel_rule: None }
+}
+
+fn remove_xfer_venue(bank: &mut Bank, admin: &Pubkey, venue: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::RemoveXferVenue {
+            xfer_venue: xfer_venue_address(venue),
+            protocol_config: protocol_config_address(),
+            admin: *admin,
+        },
+        flux_core::instruction::RemoveXferVenue {},
+    ))
+}
+
+/// Rebalance accounts for `authority` sending `Xfer` steps to `venue`.
+fn venue_accounts(bank: &Bank, market: &Market, authority: &Pubkey, venue: &Pubkey) -> flux_core::accounts::Rebalance {
+    let mut accounts = rebalance_accounts(bank, market, authority);
+    accounts.recipient = *venue;
+    let xfer_venue = xfer_venue_address(venue);
+    accounts.xfer_venue = bank.exists(&xfer_venue).then_some(xfer_venue);
+    accounts
+}
+
+/// Remaining accounts liquidating `owner`'s position: the position and its owner's profile.
+fn group(market: &Market, owner: &Pubkey) -> Vec<Pubkey> {
+    vec![position_address(market, owner), profile_address(owner)]
+}
+
+/// Run `steps` with `remaining` as the remaining accounts.
+fn run(bank: &mut Bank, accounts: flux_core::accounts::Rebalance, steps: Vec<PipelineStep>, remaining: &[Pubkey]) -> TxResult {
+    let mut instruction = ix(accounts, flux_core::instruction::Rebalance { steps });
+    instruction.accounts.extend(remaining.iter().map(|key| AccountMeta::new(*key, false)));
+    bank.process(instruction)
+}
+
+fn rebalance(bank: &mut Bank, market: &Market, venue: &Pubkey, steps: Vec<PipelineStep>, remaining: &[Pubkey]) -> TxResult {
+    let accounts = venue_accounts(bank, market, &market.authority, venue);
+    run(bank, accounts, steps, remaining)
+}
+
+/// 100 SOL of liquidity, a KYC-verified vault authority past its transfer cooldown, an
+/// allowed venue, and a borrower with 0.1 SOL of collateral (worth 10) owing 6 SOL.
+fn setup() -> (Bank, Protocol, Market, Pubkey, Pubkey) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    init_compliance_config(&mut bank, &protocol, &protocol.admin).unwrap();
+    init_profile(&mut bank, &market.authority);
+    edit_profile(&mut bank, &market.authority, |profile| profile.kyc_verified = 1);
+    let venue = Pubkey::new_unique();
+    add_xfer_venue(&mut bank, &protocol.admin, &venue).unwrap();
+    bank.warp(30);
+
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    (bank, protocol, market, venue, owner)
+}
+
+#[test]
+fn pipeline_runs_every_step_in_one_instruction() {
+    let (mut bank, _, market, venue, owner) = setup();
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    let before = vault(&bank, &market);
+
+    let steps = vec![PipelineStep::Fetch { amount: 10 * SOL }, PipelineStep::Liquidate, PipelineStep::Unload, xfer_step(5 * SOL)];
+    rebalance(&mut bank, &market, &venue, steps, &group(&market, &owner)).unwrap();
+
+    // 3 SOL of debt repaid with collateral worth 3 * 1.05 at 70
+    let seized = 45_000_000;
+    let after = position(&bank, &market, &owner);
+    assert_eq!((after.debt_amount, after.collateral_amount), (3 * SOL, SOL / 10 - seized));
+    let state = vault(&bank, &market);
+    assert_eq!(state.total_assets, before.total_assets + 10 * SOL - 5 * SOL);
+    assert_eq!(state.total_liabilities, before.total_liabilities - 3 * SOL);
+    assert_eq!(state.total_collateral, before.total_collateral - seized);
+    assert!(bank.logs_contain("Rebalanced in 4 steps"));
+
+    // The liquidation counts against the owner, whose loan stays open on the remaining debt
+    let owner_profile = profile(&bank, &owner);
+    assert_eq!((owner_profile.liquidation_count, owner_profile.active_loans), (1, 1));
+
+    // The transfer counts against the authority's cooldown, as `xfer_funds` does
+    assert_eq!(profile(&bank, &market.authority).last_active_timestamp, bank.now);
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![xfer_step(SOL)], &[]), Err(err(FluxError::RateLimitExceeded)));
+}
+
+#[test]
+fn only_the_vault_authority_runs_a_pipeline() {
+    let (mut bank, _, market, venue, _) = setup();
+    let stranger = bank.funded_key();
+    init_profile(&mut bank, &stranger);
+    bank.warp(30);
+
+    let accounts = venue_accounts(&bank, &market, &stranger, &venue);
+    assert_eq!(run(&mut bank, accounts, vec![xfer_step(SOL)], &[]), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn pipelines_are_validated() {
+    let (mut bank, _, market, venue, _) = setup();
+
+    for steps in [
+        vec![],
+        vec![PipelineStep::Unload; PipelineStep::MAX_STEPS + 1],
+        vec![PipelineStep::Fetch { amount: 0 }],
+        vec![xfer_step(0)],
+        // Transfers are rate limited per sender, so one per pipeline
+        vec![xfer_step(SOL), xfer_step(SOL)],
+    ] {
+        assert_eq!(rebalance(&mut bank, &market, &venue, steps, &[]), Err(err(FluxError::InvalidPipeline)));
+    }
+}
+
+#[test]
+fn venues_are_governed_by_the_admin() {
+    let (mut bank, protocol, market, venue, _) = setup();
+    let other = Pubkey::new_unique();
+
+    assert_eq!(add_xfer_venue(&mut bank, &market.authority, &other), Err(err(FluxError::UnauthorizedAccess)));
+    assert_eq!(rebalance(&mut bank, &market, &other, vec![xfer_step(SOL)], &[]), Err(err(FluxError::VenueNotAllowed)));
+
+    let allowed: XferVenue = bank.read(&xfer_venue_address(&venue));
+    assert_eq!((allowed.venue, allowed.added_by), (venue, protocol.admin));
+    assert_eq!(remove_xfer_venue(&mut bank, &market.authority, &venue), Err(err(FluxError::UnauthorizedAccess)));
+    remove_xfer_venue(&mut bank, &protocol.admin, &venue).unwrap();
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![xfer_step(SOL)], &[]), Err(err(FluxError::VenueNotAllowed)));
+}
+
+#[test]
+fn transfers_pass_the_xfer_funds_compliance_checks() {
+    let (mut bank, _, market, venue, _) = setup();
+
+    let mut accounts = venue_accounts(&bank, &market, &market.authority, &venue);
+    accounts.user_profile = None;
+    assert_eq!(run(&mut bank, accounts, vec![xfer_step(SOL)], &[]), Err(err(FluxError::MissingComplianceAccounts)));
+
+    edit_profile(&mut bank, &market.authority, |profile| profile.set_aml_flagged(true));
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![xfer_step(SOL)], &[]), Err(err(FluxError::AccountFlagged)));
+
+    // Unverified authorities move at most 10 SOL
+    edit_profile(&mut bank, &market.authority, |profile| {
+        profile.set_aml_flagged(false);
+        profile.kyc_verified = 0;
+    });
+    assert_eq!(
+        rebalance(&mut bank, &market, &venue, vec![xfer_step(10 * SOL + 1)], &[]),
+        Err(err(FluxError::TransferLimitExceeded))
+    );
+    rebalance(&mut bank, &market, &venue, vec![xfer_step(10 * SOL)], &[]).unwrap();
+}
+
+#[test]
+fn large_transfers_carry_travel_rule_data() {
+    let (mut bank, protocol, market, venue, _) = setup();
+    bank.process(ix(
+        flux_core::accounts::UpdateComplianceConfig {
+            compliance_config: compliance_config_address(),
+            protocol_config: protocol.config,
+            authority: protocol.admin,
+        },
+        flux_core::instruction::SetTravelRuleThreshold { threshold: 5 * SOL },
+    ))
+    .unwrap();
+
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![xfer_step(6 * SOL)], &[]), Err(err(FluxError::TravelRuleRequired)));
+
+    let record = transfer_record_address(&market.authority, 0);
+    let step = PipelineStep::Xfer { amount: 6 * SOL, travel_rule: Some(TRAVEL_RULE) };
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![step], &[]), Err(err(FluxError::TravelRuleMismatch)));
+    let mut accounts = venue_accounts(&bank, &market, &market.authority, &venue);
+    accounts.transfer_record = Some(record);
+    run(&mut bank, accounts, vec![step], &[]).unwrap();
+
+    let stored: TransferRecord = bank.read(&record);
+    assert_eq!((stored.sender, stored.recipient, stored.amount), (market.authority, venue, 6 * SOL));
+    assert_eq!((stored.payload_hash, stored.nonce), (TRAVEL_RULE.payload_hash, 0));
+    assert_eq!(profile(&bank, &market.authority).transfer_records, 1);
+}
+
+#[test]
+fn steps_must_consume_exactly_the_remaining_accounts() {
+    let (mut bank, _, market, venue, owner) = setup();
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    let position = position_address(&market, &owner);
+
+    for remaining in [
+        vec![],
+        vec![position],
+        // Only the owner's own profile records the liquidation
+        vec![position, profile_address(&market.authority)],
+        [group(&market, &owner), vec![position]].concat(),
+    ] {
+        let result = rebalance(&mut bank, &market, &venue, vec![PipelineStep::Liquidate], &remaining);
+        assert_eq!(result, Err(err(FluxError::PipelineAccountsMismatch)));
+    }
+    // The same position cannot be liquidated twice in one pipeline
+    let twice = [group(&market, &owner), group(&market, &owner)].concat();
+    let result = rebalance(&mut bank, &market, &venue, vec![PipelineStep::Liquidate, PipelineStep::Liquidate], &twice);
+    assert_eq!(result, Err(err(FluxError::PipelineAccountsMismatch)));
+}
+
+#[test]
+fn transfers_leave_borrowed_liquidity_in_place() {
+    let (mut bank, _, market, venue, _) = setup();
+
+    // 94 of the 100 SOL is idle after the 6 SOL borrow
+    assert_eq!(rebalance(&mut bank, &market, &venue, vec![xfer_step(94 * SOL + 1)], &[]), Err(err(FluxError::InsufficientLiquidity)));
+    rebalance(&mut bank, &market, &venue, vec![xfer_step(94 * SOL)], &[]).unwrap();
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_assets, state.total_liabilities), (6 * SOL, 6 * SOL));
+
+    // At full utilization a year of interest leaves the vault solvent and the pipeline open
+    bank.warp(365 * 24 * 60 * 60);
+    rebalance(&mut bank, &market, &venue, vec![PipelineStep::Unload], &[]).unwrap();
+    let state = vault(&bank, &market);
+    assert!(state.total_liabilities > 6 * SOL && !state.is_frozen);
+    assert_eq!(state.total_assets, state.total_liabilities);
+}
+
+#[test]
+fn a_failing_step_rolls_back_the_earlier_ones() {
+    let (mut bank, _, market, venue, owner) = setup();
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    let before = vault(&bank, &market);
+
+    let steps = vec![PipelineStep::Fetch { amount: 10 * SOL }, PipelineStep::Liquidate, xfer_step(200 * SOL)];
+    let result = rebalance(&mut bank, &market, &venue, steps, &group(&market, &owner));
+    assert_eq!(result, Err(err(FluxError::InsufficientLiquidity)));
+
+    let state = vault(&bank, &market);
+    assert_eq!((state.total_assets, state.total_liabilities), (before.total_assets, before.total_liabilities));
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 6 * SOL);
+    assert_eq!(profile(&bank, &owner).liquidation_count, 0);
+}
+
+#[test]
+fn a_breaker_trip_rolls_back_every_step_but_the_freeze() {
+    let (mut bank, protocol, market, venue, owner) = setup();
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    // 10% net outflow per hour
+    let params = CircuitBreakerParams { max_outflow_bps: 1000, outflow_window: 3600, max_price_move_bps: 0 };
+    bank.process(ix(
+        flux_core::accounts::UpdateConfig {
+            vault: market.vault,
+            protocol_config: protocol_config_address(),
+            authority: protocol.risk_manager,
+        },
+        flux_core::instruction::SetCircuitBreaker { params },
+    ))
+    .unwrap();
+    let before = vault(&bank, &market);
+    let active = profile(&bank, &market.authority).last_active_timestamp;
+
+    let steps = vec![PipelineStep::Fetch { amount: SOL }, PipelineStep::Liquidate, xfer_step(20 * SOL)];
+    rebalance(&mut bank, &market, &venue, steps, &group(&market, &owner)).unwrap();
+
+    let state = vault(&bank, &market);
+    assert!(state.is_frozen);
+    assert_eq!((state.total_assets, state.total_liabilities), (before.total_assets, before.total_liabilities));
+    // Nothing moved, so neither the transfer nor the liquidation is recorded
+    assert_eq!(profile(&bank, &market.authority).last_active_timestamp, active);
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 6 * SOL);
+    assert_eq!(profile(&bank, &owner).liquidation_count, 0);
+    assert!(!bank.logs_contain("Rebalanced in"));
+}
@@ -0,0 +1,233 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::__private::bytemuck::Zeroable;
+use flux_core::errors::FluxError;
+use flux_core::state::reputation::{ReputationConfig, ReputationParams};
+use flux_core::state::user_profile::{UserProfile, UserRole};
+
+use common::*;
+use fixtures::*;
+
+fn update_config(bank: &mut Bank, protocol: &Protocol, authority: &Pubkey, params: ReputationParams) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::UpdateReputationConfig {
+            reputation_config: reputation_config_address(),
+            protocol_config: protocol.config,
+            authority: *authority,
+        },
+        flux_core::instruction::UpdateReputationConfig { params },
+    ))
+}
+
+#[test]
+fn reputation_config_init_requires_upgrade_authority() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    let attacker = bank.funded_key();
+
+    assert_eq!(
+        init_reputation_config(&mut bank, &protocol, &attacker, reputation_params()),
+        Err(err(FluxError::UnauthorizedAccess))
+    );
+    init_reputation_config(&mut bank, &protocol, &protocol.admin, reputation_params()).unwrap();
+    assert_eq!(bank.read::<ReputationConfig>(&reputation_config_address()).params, reputation_params());
+}
+
+#[test]
+fn reputation_config_updates_are_risk_manager_only() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_reputation_config(&mut bank, &protocol, &protocol.admin, reputation_params()).unwrap();
+
+    let mut params = reputation_params();
+    params.premium_min_score = 90;
+    assert_eq!(update_config(&mut bank, &protocol, &protocol.admin, params), Err(err(FluxError::UnauthorizedAccess)));
+    update_config(&mut bank, &protocol, &protocol.risk_manager, params).unwrap();
+    assert_eq!(bank.read::<ReputationConfig>(&reputation_config_address()).params.premium_min_score, 90);
+}
+
+const DAY: i64 = ReputationParams::SECONDS_PER_DAY;
+
+/// A profile created `age_days` ago and active at `START_TIME`.
+fn scored_profile(age_days: i64, repayments: u32, repaid: u64, liquidations: u16) -> UserProfile {
+    let mut profile = UserProfile::zeroed();
+    profile.created_at = START_TIME - age_days * DAY;
+    profile.last_active_timestamp = START_TIME;
+    profile.repayment_count = repayments;
+    profile.total_repaid_lifetime = repaid;
+    profile.liquidation_count = liquidations;
+    profile
+}
+
+#[test]
+fn score_weighs_repayments_age_volume_and_liquidations() {
+    let params = reputation_params();
+
+    // Baseline 50, +0.5 per repayment, +1 per 30 days, +2 per 1000 SOL repaid
+    assert_eq!(params.compute_score(&scored_profile(0, 0, 0, 0), START_TIME), 50);
+    assert_eq!(params.compute_score(&scored_profile(60, 10, 2000 * SOL, 0), START_TIME), 61);
+    // -10 per liquidation, never below zero
+    assert_eq!(params.compute_score(&scored_profile(60, 10, 2000 * SOL, 1), START_TIME), 51);
+    assert_eq!(params.compute_score(&scored_profile(0, 0, 0, 20), START_TIME), 0);
+    // Profiles from before `created_at` was recorded get no age credit
+    let mut legacy = scored_profile(0, 0, 0, 0);
+    legacy.created_at = 0;
+    assert_eq!(params.compute_score(&legacy, START_TIME), 50);
+}
+
+#[test]
+fn score_counters_are_capped() {
+    let params = reputation_params();
+    let capped = ReputationParams::MAX_COUNTED_REPAYMENTS as u32;
+    assert_eq!(
+        params.compute_score(&scored_profile(0, capped * 10, 0, 0), START_TIME),
+        params.compute_score(&scored_profile(0, capped, 0, 0), START_TIME),
+    );
+    assert_eq!(params.compute_score(&scored_profile(10_000, 1000, u64::MAX, 0), START_TIME), 100);
+}
+
+#[test]
+fn idle_days_decay_the_score_toward_baseline() {
+    let params = reputation_params();
+    let profile = scored_profile(60, 10, 2000 * SOL, 0);
+
+    // 1% per idle day of the 11 points above baseline
+    assert_eq!(params.compute_score(&profile, START_TIME + 10 * DAY), 59);
+    assert_eq!(params.compute_score(&profile, START_TIME + 100 * DAY), 50);
+    assert_eq!(params.compute_score(&profile, START_TIME + 1000 * DAY), 50);
+
+    // Below baseline there is nothing to decay
+    let liquidated = scored_profile(0, 0, 0, 2);
+    assert_eq!(params.compute_score(&liquidated, START_TIME + 20 * DAY), 30);
+}
+
+#[test]
+fn tier_is_the_highest_one_the_score_reaches() {
+    let mut params = reputation_params();
+    let min_scores = |score| params.tier_for(score).map(|tier| tier.min_score);
+    assert_eq!(min_scores(0), Some(0));
+    assert_eq!(min_scores(59), Some(0));
+    assert_eq!(min_scores(60), Some(60));
+    assert_eq!(min_scores(79), Some(60));
+    assert_eq!(min_scores(100), Some(80));
+
+    params.tiers[0].min_score = 20;
+    assert!(params.tier_for(19).is_none());
+    assert_eq!(params.tiers[1].required_collateral_ratio(15000), 14000);
+    assert_eq!(params.tiers[2].required_collateral_ratio(11000), 10000);
+}
+
+#[test]
+fn tier_sets_the_borrow_limit() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 1000 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL);
+
+    // Baseline score: the lowest tier allows 10
+    assert_eq!(borrow(&mut bank, &market, &owner, 11 * SOL), Err(err(FluxError::BorrowLimitExceeded)));
+
+    // Twenty repayments lift the score to 60 and the limit to 100
+    edit_profile(&mut bank, &owner, |profile| profile.repayment_count = 20);
+    borrow(&mut bank, &market, &owner, 20 * SOL).unwrap();
+    assert_eq!(profile(&bank, &owner).reputation_score, 60);
+
+    // A score below every tier cannot borrow at all
+    let mut params = reputation_params();
+    params.tiers[0].min_score = 20;
+    update_config(&mut bank, &protocol, &protocol.risk_manager, params).unwrap();
+    edit_profile(&mut bank, &owner, |profile| {
+        profile.repayment_count = 0;
+        profile.liquidation_count = 4;
+    });
+    assert_eq!(borrow(&mut bank, &market, &owner, 1), Err(err(FluxError::LowReputation)));
+}
+
+fn refresh(bank: &mut Bank, owner: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::RefreshReputation {
+            user_profile: profile_address(owner),
+            reputation_config: reputation_config_address(),
+        },
+        flux_core::instruction::RefreshReputation {},
+    ))
+}
+
+#[test]
+fn liquidation_lowers_the_score_and_the_tier() {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 1000 * SOL);
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    let liquidator = bank.funded_key();
+
+    // Twenty repayments put the owner in the 60 tier
+    edit_profile(&mut bank, &owner, |profile| profile.repayment_count = 20);
+    borrow(&mut bank, &market, &owner, 6 * SOL).unwrap();
+    assert_eq!(profile(&bank, &owner).reputation_score, 60);
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+    liquidate(&mut bank, &market, &owner, &liquidator).unwrap();
+    assert_eq!(profile(&bank, &owner).liquidation_count, 1);
+
+    // -10 for the liquidation drops the score back to the lowest tier
+    refresh(&mut bank, &owner).unwrap();
+    let score = profile(&bank, &owner).reputation_score;
+    assert_eq!(score, 50);
+    assert_eq!(reputation_params().tier_for(score).map(|tier| tier.borrow_limit), Some(10 * SOL));
+}
+
+fn promote(bank: &mut Bank, owner: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::RefreshReputation {
+            user_profile: profile_address(owner),
+            reputation_config: reputation_config_address(),
+        },
+        flux_core::instruction::PromoteRole {},
+    ))
+}
+
+#[test]
+fn promote_role_requires_score_and_volume() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_reputation_config(&mut bank, &protocol, &protocol.admin, reputation_params()).unwrap();
+    let owner = bank.funded_key();
+    init_profile(&mut bank, &owner);
+
+    assert_eq!(promote(&mut bank, &owner), Err(err(FluxError::LowReputation)));
+
+    // Score 70, but short of the 100 SOL borrowed
+    edit_profile(&mut bank, &owner, |profile| {
+        profile.repayment_count = 40;
+        profile.last_active_timestamp = START_TIME;
+        profile.total_borrowed_lifetime = 99 * SOL;
+    });
+    assert_eq!(promote(&mut bank, &owner), Err(err(FluxError::LowReputation)));
+
+    edit_profile(&mut bank, &owner, |profile| profile.total_borrowed_lifetime = 100 * SOL);
+    promote(&mut bank, &owner).unwrap();
+    let promoted = profile(&bank, &owner);
+    assert_eq!((promoted.role(), promoted.reputation_score), (UserRole::Premium, 70));
+
+    // Only Standard profiles are promoted
+    assert_eq!(promote(&mut bank, &owner), Err(err(FluxError::UnauthorizedAccess)));
+}
+
+#[test]
+fn promote_role_rejects_flagged_profiles() {
+    let mut bank = bank();
+    let protocol = init_protocol(&mut bank);
+    init_reputation_config(&mut bank, &protocol, &protocol.admin, reputation_params()).unwrap();
+    let owner = bank.funded_key();
+    init_profile(&mut bank, &owner);
+    edit_profile(&mut bank, &owner, |profile| {
+        profile.repayment_count = 100;
+        profile.last_active_timestamp = START_TIME;
+        profile.total_borrowed_lifetime = 1000 * SOL;
+        profile.aml_flagged = 1;
+    });
+
+    assert_eq!(promote(&mut bank, &owner), Err(err(FluxError::AccountFlagged)));
+    assert_eq!(profile(&bank, &owner).role(), UserRole::Standard);
+}
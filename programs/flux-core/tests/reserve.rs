@@ -0,0 +1,335 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use flux_core::errors::FluxError;
+use flux_core::state::reserve::{CollateralDeposit, Reserve, ReserveConfig, RiskCategory, RiskCategoryConfig, RiskCategoryKind};
+
+use common::*;
+use fixtures::*;
+
+/// A reserve for a fresh mint with `decimals`, priced at 100 by its own feed.
+struct Asset {
+    reserve: Pubkey,
+    feed: Pubkey,
+}
+
+fn add_asset(bank: &mut Bank, protocol: &Protocol, market: &Market, decimals: u8, category: Option<Pubkey>) -> Asset {
+    let mint = mint(bank, decimals);
+    let feed = price_feed(bank, COLLATERAL_PRICE, 6);
+    let config = ReserveConfig {
+        category: category.map_or(0, |category| bank.read::<RiskCategory>(&category).id),
+        ..reserve_config(feed)
+    };
+    add_reserve(bank, protocol, market, &mint, config, category).expect("add_reserve");
+    Asset { reserve: reserve_address(market, &mint), feed }
+}
+
+fn category_address(market: &Market, id: u8) -> Pubkey {
+    pda(&[b"risk_category", market.vault.as_ref(), &[id]], &flux_core::ID)
+}
+
+fn add_category(bank: &mut Bank, protocol: &Protocol, market: &Market, id: u8, kind: RiskCategoryKind, config: RiskCategoryConfig) -> Pubkey {
+    let category = category_address(market, id);
+    bank.process(ix(
+        flux_core::accounts::AddRiskCategory {
+            vault: market.vault,
+            risk_category: category,
+            protocol_config: protocol.config,
+            authority: protocol.risk_manager,
+            system_program: system_program::ID,
+        },
+        flux_core::instruction::AddRiskCategory { id, kind, config },
+    ))
+    .expect("add_risk_category");
+    category
+}
+
+fn set_category(
+    bank: &mut Bank,
+    market: &Market,
+    owner: &Pubkey,
+    current: Option<Pubkey>,
+    new: Option<Pubkey>,
+    reserves: &[Pubkey],
+) -> TxResult {
+    let instruction = ix(
+        flux_core::accounts::SetPositionCategory {
+            vault: market.vault,
+            position: position_address(market, owner),
+            current_category: current,
+            new_category: new,
+            owner: *owner,
+            price_feed_collateral: market.collateral_feed,
+            price_feed_debt: market.debt_feed,
+        },
+        flux_core::instruction::SetPositionCategory {},
+    );
+    bank.process(with_reserves(bank, market, owner, reserves, instruction))
+}
+
+fn borrow_with(bank: &mut Bank, market: &Market, owner: &Pubkey, category: Option<Pubkey>, reserves: &[Pubkey], amount: u64) -> TxResult {
+    let accounts = flux_core::accounts::ManageDebt { risk_category: category, ..manage_debt(bank, market, owner) };
+    let instruction = ix(accounts, flux_core::instruction::Borrow { amount });
+    bank.process(with_reserves(bank, market, owner, reserves, instruction))
+}
+
+fn repay_with(bank: &mut Bank, market: &Market, owner: &Pubkey, category: Option<Pubkey>, amount: u64) -> TxResult {
+    let accounts = flux_core::accounts::ManageDebt { risk_category: category, ..manage_debt(bank, market, owner) };
+    bank.process(ix(accounts, flux_core::instruction::Repay { amount }))
+}
+
+/// A borrower holding 0.1 of `asset`, worth 10, and no base collateral.
+fn reserve_borrower(bank: &mut Bank, market: &Market, asset: &Asset) -> Pubkey {
+    let owner = init_borrower(bank, market, 0);
+    open_collateral_deposit(bank, market, &owner, &asset.reserve);
+    deposit_reserve_collateral(bank, market, &owner, &asset.reserve, 100_000).unwrap();
+    owner
+}
+
+fn withdraw(bank: &mut Bank, market: &Market, owner: &Pubkey, reserve: &Pubkey, amount: u64) -> TxResult {
+    let instruction = ix(
+        flux_core::accounts::WithdrawReserveCollateral {
+            vault: market.vault,
+            reserve: *reserve,
+            position: position_address(market, owner),
+            collateral_deposit: collateral_deposit_address(market, owner, reserve),
+            risk_category: None,
+            protocol_config: protocol_config_address(),
+            owner: *owner,
+            owner_token_account: Pubkey::new_unique(),
+            price_feed_collateral: market.collateral_feed,
+            price_feed_debt: market.debt_feed,
+        },
+        flux_core::instruction::WithdrawReserveCollateral { amount },
+    );
+    bank.process(with_reserves(bank, market, owner, &[*reserve], instruction))
+}
+
+fn close(bank: &mut Bank, market: &Market, owner: &Pubkey, reserve: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::CloseCollateralDeposit {
+            vault: market.vault,
+            reserve: *reserve,
+            position: position_address(market, owner),
+            collateral_deposit: collateral_deposit_address(market, owner, reserve),
+            owner: *owner,
+        },
+        flux_core::instruction::CloseCollateralDeposit {},
+    ))
+}
+
+fn deposit_balance(bank: &Bank, market: &Market, owner: &Pubkey, reserve: &Pubkey) -> u64 {
+    bank.read::<CollateralDeposit>(&collateral_deposit_address(market, owner, reserve)).amount
+}
+
+/// A 6-decimal reserve and a `reserve_borrower` of it.
+fn setup() -> (Bank, Protocol, Market, Asset, Pubkey) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, None);
+    let owner = reserve_borrower(&mut bank, &market, &asset);
+    (bank, protocol, market, asset, owner)
+}
+
+#[test]
+fn reserve_collateral_is_valued_in_its_mints_decimals() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+
+    for decimals in [6, 12] {
+        let asset = add_asset(&mut bank, &protocol, &market, decimals, None);
+        assert_eq!(bank.read::<Reserve>(&asset.reserve).decimals, decimals);
+
+        // 0.1 of the asset is worth 10, or 5 of debt at a 50% LTV, whatever its decimals
+        let owner = init_borrower(&mut bank, &market, 0);
+        open_collateral_deposit(&mut bank, &market, &owner, &asset.reserve);
+        deposit_reserve_collateral(&mut bank, &market, &owner, &asset.reserve, 10u64.pow(decimals as u32 - 1)).unwrap();
+        let result = borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 5 * SOL + 1);
+        assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+        borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 5 * SOL).unwrap();
+    }
+}
+
+#[test]
+fn withdrawals_keep_debt_within_the_borrow_limit() {
+    let (mut bank, _, market, asset, owner) = setup();
+    borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 4 * SOL).unwrap();
+
+    // 0.07 left would support only 3.5 of debt
+    let result = withdraw(&mut bank, &market, &owner, &asset.reserve, 30_000);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+    assert_eq!(withdraw(&mut bank, &market, &owner, &asset.reserve, 100_001), Err(err(FluxError::InsufficientCollateral)));
+    assert_eq!(deposit_balance(&bank, &market, &owner, &asset.reserve), 100_000);
+
+    withdraw(&mut bank, &market, &owner, &asset.reserve, 20_000).unwrap();
+    assert_eq!(deposit_balance(&bank, &market, &owner, &asset.reserve), 80_000);
+    assert_eq!(bank.read::<Reserve>(&asset.reserve).total_deposits, 80_000);
+}
+
+#[test]
+fn only_empty_deposits_close() {
+    let (mut bank, _, market, asset, owner) = setup();
+    let deposit = collateral_deposit_address(&market, &owner, &asset.reserve);
+    let rent = bank.lamports(&deposit);
+
+    assert_eq!(close(&mut bank, &market, &owner, &asset.reserve), Err(err(FluxError::CollateralDepositNotEmpty)));
+
+    // Without debt the whole balance can leave
+    withdraw(&mut bank, &market, &owner, &asset.reserve, 100_000).unwrap();
+    let before = bank.lamports(&owner);
+    close(&mut bank, &market, &owner, &asset.reserve).unwrap();
+    assert!(!bank.exists(&deposit));
+    assert_eq!(bank.lamports(&owner), before + rent);
+    assert_eq!(position(&bank, &market, &owner).reserve_count, 0);
+
+    // The freed slot no longer needs accounts in health checks
+    deposit_collateral(&mut bank, &market, &owner, SOL).unwrap();
+    borrow(&mut bank, &market, &owner, SOL).unwrap();
+}
+
+#[test]
+fn liquidation_seizes_reserve_collateral_in_mint_units() {
+    let (mut bank, _, market, asset, owner) = setup();
+    borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 5 * SOL).unwrap();
+    // 0.1 at 60 is worth 6, below 5 of debt at an 80% liquidation threshold
+    set_price(&mut bank, &asset.feed, COLLATERAL_PRICE * 60 / 100, 6);
+
+    let liquidator = bank.funded_key();
+    let mut accounts = liquidate_accounts(&bank, &market, &owner, &liquidator);
+    accounts.collateral_reserve = Some(asset.reserve);
+    accounts.collateral_deposit = Some(collateral_deposit_address(&market, &owner, &asset.reserve));
+    let instruction = ix(accounts, flux_core::instruction::LiquidatePosition {});
+    bank.process(with_reserves(&bank, &market, &owner, &[asset.reserve], instruction)).unwrap();
+
+    // 2.5 of debt repaid with 2.625 of the asset at 60: 0.04375, or 43_750 at 6 decimals
+    let seized = 43_750;
+    assert_eq!(position(&bank, &market, &owner).debt_amount, 5 * SOL / 2);
+    assert_eq!(deposit_balance(&bank, &market, &owner, &asset.reserve), 100_000 - seized);
+    assert_eq!(bank.read::<Reserve>(&asset.reserve).total_deposits, 100_000 - seized);
+    assert_eq!(vault(&bank, &market).total_collateral, 0);
+}
+
+/// 90% LTV and 95% liquidation threshold for correlated collateral.
+const E_MODE: RiskCategoryConfig = RiskCategoryConfig { ltv_bps: 9000, liquidation_threshold_bps: 9500, debt_ceiling: 0 };
+
+/// Collateral that backs at most 6 of debt across the category's positions.
+const ISOLATED: RiskCategoryConfig = RiskCategoryConfig { ltv_bps: 5000, liquidation_threshold_bps: 8000, debt_ceiling: 6 * SOL };
+
+#[test]
+fn e_mode_limits_apply_to_in_category_collateral_once_opted_in() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let category = add_category(&mut bank, &protocol, &market, 1, RiskCategoryKind::Efficiency, E_MODE);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, Some(category));
+    let owner = reserve_borrower(&mut bank, &market, &asset);
+
+    // Outside e-mode the reserve's own 50% LTV applies
+    let result = borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 5 * SOL + 1);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+
+    set_category(&mut bank, &market, &owner, None, Some(category), &[asset.reserve]).unwrap();
+    assert_eq!(position(&bank, &market, &owner).risk_category, 1);
+    let result = borrow_with(&mut bank, &market, &owner, Some(category), &[asset.reserve], 9 * SOL + 1);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+    borrow_with(&mut bank, &market, &owner, Some(category), &[asset.reserve], 9 * SOL).unwrap();
+    // E-mode debt is not tracked against a ceiling
+    assert_eq!(bank.read::<RiskCategory>(&category).total_debt, 0);
+
+    // Borrowing as an e-mode position requires its category account
+    let result = borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], 1);
+    assert_eq!(result, Err(err(FluxError::RiskCategoryMismatch)));
+}
+
+#[test]
+fn leaving_e_mode_with_debt_requires_the_lower_limits_to_hold() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let category = add_category(&mut bank, &protocol, &market, 1, RiskCategoryKind::Efficiency, E_MODE);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, Some(category));
+    let owner = reserve_borrower(&mut bank, &market, &asset);
+    set_category(&mut bank, &market, &owner, None, Some(category), &[asset.reserve]).unwrap();
+    borrow_with(&mut bank, &market, &owner, Some(category), &[asset.reserve], 6 * SOL).unwrap();
+
+    // 6 of debt is over the 5 the reserve supports on its own
+    let result = set_category(&mut bank, &market, &owner, Some(category), None, &[asset.reserve]);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+    // The current category must be the position's, and the new one must differ
+    let result = set_category(&mut bank, &market, &owner, None, None, &[asset.reserve]);
+    assert_eq!(result, Err(err(FluxError::RiskCategoryMismatch)));
+    let result = set_category(&mut bank, &market, &owner, Some(category), Some(category), &[asset.reserve]);
+    assert_eq!(result, Err(err(FluxError::InvalidRiskCategory)));
+
+    repay_with(&mut bank, &market, &owner, Some(category), SOL).unwrap();
+    set_category(&mut bank, &market, &owner, Some(category), None, &[asset.reserve]).unwrap();
+    assert_eq!(position(&bank, &market, &owner).risk_category, 0);
+}
+
+#[test]
+fn isolated_debt_is_capped_by_the_category_ceiling() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let category = add_category(&mut bank, &protocol, &market, 2, RiskCategoryKind::Isolated, ISOLATED);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, Some(category));
+    let owners = [reserve_borrower(&mut bank, &market, &asset), reserve_borrower(&mut bank, &market, &asset)];
+
+    // Isolated collateral backs nothing outside its category
+    let result = borrow_with(&mut bank, &market, &owners[0], None, &[asset.reserve], SOL);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+
+    for owner in &owners {
+        set_category(&mut bank, &market, owner, None, Some(category), &[asset.reserve]).unwrap();
+    }
+    borrow_with(&mut bank, &market, &owners[0], Some(category), &[asset.reserve], 4 * SOL).unwrap();
+    let result = borrow_with(&mut bank, &market, &owners[1], Some(category), &[asset.reserve], 2 * SOL + 1);
+    assert_eq!(result, Err(err(FluxError::DebtCeilingExceeded)));
+    borrow_with(&mut bank, &market, &owners[1], Some(category), &[asset.reserve], 2 * SOL).unwrap();
+    assert_eq!(bank.read::<RiskCategory>(&category).total_debt, 6 * SOL);
+
+    // Repayments free room under the ceiling
+    repay_with(&mut bank, &market, &owners[0], Some(category), SOL).unwrap();
+    assert_eq!(bank.read::<RiskCategory>(&category).total_debt, 5 * SOL);
+    borrow_with(&mut bank, &market, &owners[1], Some(category), &[asset.reserve], SOL).unwrap();
+}
+
+#[test]
+fn isolated_positions_count_only_their_categorys_collateral() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let category = add_category(&mut bank, &protocol, &market, 2, RiskCategoryKind::Isolated, ISOLATED);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, Some(category));
+    let owner = reserve_borrower(&mut bank, &market, &asset);
+    deposit_collateral(&mut bank, &market, &owner, SOL).unwrap();
+    set_category(&mut bank, &market, &owner, None, Some(category), &[asset.reserve]).unwrap();
+
+    // 1 SOL of base collateral, worth 100, does not lift the 5 the isolated asset supports
+    let result = borrow_with(&mut bank, &market, &owner, Some(category), &[asset.reserve], 5 * SOL + 1);
+    assert_eq!(result, Err(err(FluxError::InsufficientCollateral)));
+    borrow_with(&mut bank, &market, &owner, Some(category), &[asset.reserve], 5 * SOL).unwrap();
+}
+
+#[test]
+fn positions_with_debt_cannot_enter_or_leave_isolation() {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 100 * SOL);
+    let isolated = add_category(&mut bank, &protocol, &market, 2, RiskCategoryKind::Isolated, ISOLATED);
+    let asset = add_asset(&mut bank, &protocol, &market, 6, Some(isolated));
+    let owner = reserve_borrower(&mut bank, &market, &asset);
+    deposit_collateral(&mut bank, &market, &owner, SOL).unwrap();
+
+    // Debt taken outside the category would escape its ceiling
+    borrow_with(&mut bank, &market, &owner, None, &[asset.reserve], SOL).unwrap();
+    let result = set_category(&mut bank, &market, &owner, None, Some(isolated), &[asset.reserve]);
+    assert_eq!(result, Err(err(FluxError::CategoryChangeWithDebt)));
+    repay_with(&mut bank, &market, &owner, None, SOL).unwrap();
+    set_category(&mut bank, &market, &owner, None, Some(isolated), &[asset.reserve]).unwrap();
+
+    // ...and debt counted against it cannot leave
+    borrow_with(&mut bank, &market, &owner, Some(isolated), &[asset.reserve], SOL).unwrap();
+    let result = set_category(&mut bank, &market, &owner, Some(isolated), None, &[asset.reserve]);
+    assert_eq!(result, Err(err(FluxError::CategoryChangeWithDebt)));
+    repay_with(&mut bank, &market, &owner, Some(isolated), SOL).unwrap();
+    set_category(&mut bank, &market, &owner, Some(isolated), None, &[asset.reserve]).unwrap();
+    assert_eq!(bank.read::<RiskCategory>(&isolated).total_debt, 0);
+}
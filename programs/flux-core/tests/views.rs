@@ -0,0 +1,150 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+use flux_core::instructions::views::{PositionHealthView, RatesView, VaultHealthView};
+use flux_core::risk;
+use flux_core::state::vault::Vault;
+
+use common::*;
+use fixtures::*;
+
+/// Run a view and decode what it returned.
+fn view<T: AnchorDeserialize>(bank: &mut Bank, instruction: anchor_lang::solana_program::instruction::Instruction) -> std::result::Result<T, ProgramError> {
+    bank.process(instruction)?;
+    let (program, data) = bank.return_data.clone().expect("return data");
+    assert_eq!(program, flux_core::ID);
+    Ok(T::try_from_slice(&data).expect("decode view"))
+}
+
+fn vault_health(bank: &mut Bank, market: &Market) -> std::result::Result<VaultHealthView, ProgramError> {
+    view(bank, ix(
+        flux_core::accounts::GetVaultHealth {
+            vault: market.vault,
+            price_feed_collateral: market.collateral_feed,
+            price_feed_debt: market.debt_feed,
+        },
+        flux_core::instruction::GetVaultHealth {},
+    ))
+}
+
+fn position_health(bank: &mut Bank, market: &Market, owner: &Pubkey) -> std::result::Result<PositionHealthView, ProgramError> {
+    view(bank, ix(
+        flux_core::accounts::GetPositionHealth {
+            vault: market.vault,
+            position: position_address(market, owner),
+            risk_category: None,
+            price_feed_collateral: market.collateral_feed,
+            price_feed_debt: market.debt_feed,
+        },
+        flux_core::instruction::GetPositionHealth {},
+    ))
+}
+
+fn rates(bank: &mut Bank, market: &Market) -> RatesView {
+    view(bank, ix(flux_core::accounts::GetRates { vault: market.vault }, flux_core::instruction::GetRates {}))
+        .expect("get_rates")
+}
+
+/// 100 SOL supplied and a borrower owing 10 SOL against 10 SOL of collateral.
+fn setup() -> (Bank, Market, Pubkey) {
+    let mut bank = bank();
+    let (_, market) = init_lending(&mut bank, 100 * SOL);
+    let owner = init_borrower(&mut bank, &market, 10 * SOL);
+    borrow(&mut bank, &market, &owner, 10 * SOL).unwrap();
+    (bank, market, owner)
+}
+
+#[test]
+fn rates_follow_the_fixed_interest_model() {
+    let (mut bank, market, _) = setup();
+
+    let view = rates(&mut bank, &market);
+    assert_eq!(view.utilization_bps, 1000);
+    // 5 / 1e9 per second over a year, to the nearest bps
+    let annual = (Vault::INTEREST_RATE_PER_SECOND * Vault::SECONDS_PER_YEAR * 10000 + Vault::INTEREST_RATE_SCALE / 2)
+        / Vault::INTEREST_RATE_SCALE;
+    assert_eq!(view.borrow_rate_bps as u128, annual);
+    assert_eq!(view.borrow_rate_bps, Vault::DEFAULT_BORROW_RATE_BPS);
+    assert_eq!(view.supply_rate_bps, 157);
+
+    // Rate curve parameters do not change the model
+    edit_vault(&mut bank, &market, |vault| {
+        vault.base_rate = 200;
+        vault.max_rate = 5000;
+    });
+    assert_eq!(rates(&mut bank, &market).borrow_rate_bps, Vault::DEFAULT_BORROW_RATE_BPS);
+
+    edit_vault(&mut bank, &market, |vault| vault.performance_fee_rate = 1000);
+    assert_eq!(rates(&mut bank, &market).supply_rate_bps, 141);
+}
+
+#[test]
+fn views_simulate_accrual_without_writing_the_vault() {
+    let (mut bank, market, owner) = setup();
+    let before = vault(&bank, &market);
+    bank.warp(24 * 60 * 60);
+
+    // 10 SOL at 5 / 1e9 per second for a day
+    let interest = 10 * SOL * 5 * 24 * 60 * 60 / 1_000_000_000;
+    let view = vault_health(&mut bank, &market).unwrap();
+    assert_eq!((view.total_liabilities, view.timestamp), (10 * SOL + interest, bank.now));
+    assert_eq!(rates(&mut bank, &market).timestamp, bank.now);
+    let after = vault(&bank, &market);
+    assert_eq!((after.total_liabilities, after.last_update), (before.total_liabilities, before.last_update));
+
+    // The next state change accrues exactly what the view showed
+    deposit_collateral(&mut bank, &market, &owner, 1).unwrap();
+    assert_eq!(vault(&bank, &market).total_liabilities, view.total_liabilities);
+}
+
+#[test]
+fn vault_health_reports_utilization_and_limits() {
+    let (mut bank, market, _) = setup();
+
+    let view = vault_health(&mut bank, &market).unwrap();
+    assert_eq!((view.total_assets, view.total_liabilities, view.total_collateral), (100 * SOL, 10 * SOL, 10 * SOL));
+    assert_eq!(view.utilization_bps, 1000);
+    // 10 SOL of collateral at 100 against 10 SOL of debt at 1
+    assert_eq!(view.ltv_bps, 100);
+    assert_eq!((view.max_ltv_bps, view.liquidation_threshold_bps), (6666, 8333));
+    assert!(view.is_solvent && !view.is_frozen);
+}
+
+#[test]
+fn position_health_values_every_leg_at_oracle_prices() {
+    let (mut bank, market, owner) = setup();
+
+    let view = position_health(&mut bank, &market, &owner).unwrap();
+    assert_eq!((view.collateral_amount, view.debt_amount), (10 * SOL, 10 * SOL));
+    // Values carry `VALUE_DECIMALS` on top of the raw amount
+    let unit = 10u128.pow(risk::VALUE_DECIMALS);
+    assert_eq!(view.debt_value, 10 * SOL as u128 * unit);
+    assert_eq!((view.base_collateral_value, view.collateral_value), (view.debt_value * 100, view.debt_value * 100));
+    assert_eq!(view.ltv_bps, 100);
+    assert!(!view.liquidatable);
+
+    set_price(&mut bank, &market.collateral_feed, COLLATERAL_PRICE / 100, 6);
+    let view = position_health(&mut bank, &market, &owner).unwrap();
+    assert_eq!(view.ltv_bps, 10000);
+    assert!(view.liquidatable);
+}
+
+#[test]
+fn views_require_the_vaults_price_feeds() {
+    let (mut bank, market, owner) = setup();
+    let rogue = Market {
+        collateral_feed: price_feed(&mut bank, COLLATERAL_PRICE, 6),
+        debt_feed: price_feed(&mut bank, DEBT_PRICE, 6),
+        ..market
+    };
+
+    for market in [
+        Market { collateral_feed: rogue.collateral_feed, ..market },
+        Market { debt_feed: rogue.debt_feed, ..market },
+    ] {
+        assert_eq!(vault_health(&mut bank, &market), Err(err(FluxError::OracleMismatch)));
+        assert_eq!(position_health(&mut bank, &market, &owner), Err(err(FluxError::OracleMismatch)));
+    }
+}
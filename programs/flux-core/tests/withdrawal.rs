@@ -0,0 +1,158 @@
+mod common;
+mod fixtures;
+
+use anchor_lang::prelude::*;
+use flux_core::errors::FluxError;
+use flux_core::state::order::{OrderAction, OrderKind, OrderParams};
+use flux_core::state::withdrawal_queue::{WithdrawalQueue, WithdrawalTicket};
+
+use common::*;
+use fixtures::*;
+
+fn ticket_address(market: &Market, id: u64) -> Pubkey {
+    pda(&[b"withdrawal_ticket", withdrawal_queue_address(market).as_ref(), &id.to_le_bytes()], &flux_core::ID)
+}
+
+fn queue(bank: &Bank, market: &Market) -> WithdrawalQueue {
+    bank.read(&withdrawal_queue_address(market))
+}
+
+fn claim(bank: &mut Bank, market: &Market, owner: &Pubkey, id: u64) -> TxResult {
+    bank.process(ix(
+        flux_core::accounts::ClaimWithdrawal {
+            vault: market.vault,
+            withdrawal_queue: withdrawal_queue_address(market),
+            ticket: ticket_address(market, id),
+            protocol_config: protocol_config_address(),
+            owner: *owner,
+            owner_token_account: Pubkey::new_unique(),
+        },
+        flux_core::instruction::ClaimWithdrawal {},
+    ))
+}
+
+/// A queued vault whose `supplied` liquidity is lent out in full to a borrower with 0.1 SOL
+/// of collateral (worth 10).
+fn setup(supplied: &[u64]) -> (Bank, Market, Vec<Pubkey>, Pubkey) {
+    let mut bank = bank();
+    let (protocol, market) = init_lending(&mut bank, 0);
+    init_withdrawal_queue(&mut bank, &protocol, &market).unwrap();
+    let depositors: Vec<Pubkey> = supplied.iter().map(|amount| {
+        let depositor = bank.funded_key();
+        supply(&mut bank, &market, &depositor, *amount).unwrap();
+        depositor
+    }).collect();
+    let owner = init_borrower(&mut bank, &market, SOL / 10);
+    borrow(&mut bank, &market, &owner, supplied.iter().sum()).unwrap();
+    (bank, market, depositors, owner)
+}
+
+#[test]
+fn requests_lock_supply_shares() {
+    let (mut bank, market, depositors, owner) = setup(&[6 * SOL]);
+    let depositor = depositors[0];
+    assert_eq!(supply_position(&bank, &market, &depositor).shares, 6 * SOL);
+
+    assert_eq!(request_withdrawal(&mut bank, &market, &depositor, 6 * SOL + 1), Err(err(FluxError::InsufficientShares)));
+    // Borrowers have no shares to queue; their collateral is not the vault's liquidity
+    assert_eq!(
+        request_withdrawal(&mut bank, &market, &owner, SOL),
+        Err(err(anchor_lang::error::ErrorCode::AccountNotInitialized))
+    );
+
+    request_withdrawal(&mut bank, &market, &depositor, 4 * SOL).unwrap();
+    assert_eq!(supply_position(&bank, &market, &depositor).shares, 2 * SOL);
+    let ticket: WithdrawalTicket = bank.read(&ticket_address(&market, 0));
+    assert_eq!((ticket.owner, ticket.supply_position), (depositor, supply_position_address(&market, &depositor)));
+    assert_eq!((ticket.amount, ticket.start), (4 * SOL, 0));
+    // Everything is lent out, so nothing fills yet
+    assert_eq!((queue(&bank, &market).pending(), queue(&bank, &market).total_filled), (4 * SOL, 0));
+    assert_eq!(position(&bank, &market, &owner).collateral_amount, SOL / 10);
+    assert_eq!(vault(&bank, &market).total_collateral, SOL / 10);
+}
+
+#[test]
+fn repayments_fill_tickets_in_order() {
+    let (mut bank, market, depositors, owner) = setup(&[4 * SOL, 2 * SOL]);
+    let [first, second] = [depositors[0], depositors[1]];
+    request_withdrawal(&mut bank, &market, &first, 4 * SOL).unwrap();
+    request_withdrawal(&mut bank, &market, &second, 2 * SOL).unwrap();
+
+    repay(&mut bank, &market, &owner, 3 * SOL).unwrap();
+    assert_eq!(claim(&mut bank, &market, &second, 1), Err(err(FluxError::NothingToClaim)));
+    claim(&mut bank, &market, &first, 0).unwrap();
+    assert_eq!(bank.read::<WithdrawalTicket>(&ticket_address(&market, 0)).claimed, 3 * SOL);
+
+    // A deposit fills the rest of the first ticket before the second
+    supply(&mut bank, &market, &owner, 2 * SOL).unwrap();
+    claim(&mut bank, &market, &first, 0).unwrap();
+    assert!(!bank.exists(&ticket_address(&market, 0)));
+    claim(&mut bank, &market, &second, 1).unwrap();
+    assert_eq!(bank.read::<WithdrawalTicket>(&ticket_address(&market, 1)).claimed, SOL);
+
+    let state = queue(&bank, &market);
+    assert_eq!((state.total_filled, state.pending(), state.escrowed), (5 * SOL, SOL, 0));
+}
+
+type Sale = fn(&mut Bank, &Market, &Pubkey, Option<Pubkey>) -> TxResult;
+
+/// Instructions that sell collateral to repay debt, passed the given withdrawal queue.
+fn collateral_sales() -> [(&'static str, Sale); 4] {
+    [
+        ("liquidate_position", |bank, market, owner, queue| {
+            set_price(bank, &market.collateral_feed, COLLATERAL_PRICE * 70 / 100, 6);
+            let liquidator = bank.funded_key();
+            let accounts = flux_core::accounts::LiquidatePosition {
+                withdrawal_queue: queue,
+                ..liquidate_accounts(bank, market, owner, &liquidator)
+            };
+            bank.process(ix(accounts, flux_core::instruction::LiquidatePosition {}))
+        }),
+        ("deleverage", |bank, market, owner, queue| {
+            let accounts = flux_core::accounts::Deleverage {
+                withdrawal_queue: queue,
+                ..deleverage_accounts(bank, market, owner, owner)
+            };
+            bank.process(ix(accounts, flux_core::instruction::Deleverage { collateral_amount: SOL / 100, min_amount_out: 0 }))
+        }),
+        ("unwind", |bank, market, owner, queue| {
+            let accounts = flux_core::accounts::Unwind { withdrawal_queue: queue, ..unwind_accounts(bank, market, owner) };
+            bank.process(ix(accounts, flux_core::instruction::Unwind { target_leverage_bps: 10000, min_amount_out: 0 }))
+        }),
+        ("execute_order", |bank, market, owner, queue| {
+            let params = OrderParams {
+                kind: OrderKind::StopLoss,
+                trigger_price: COLLATERAL_PRICE,
+                action: OrderAction::Repay { amount: 2 * SOL },
+                max_slippage_bps: 0,
+                keeper_tip: 0,
+            };
+            if !bank.exists(&order_address(market, owner, 0)) {
+                place_order(bank, market, owner, 0, params).unwrap();
+            }
+            let executor = bank.funded_key();
+            let accounts = flux_core::accounts::ExecuteOrder {
+                withdrawal_queue: queue,
+                ..execute_order_accounts(bank, market, owner, 0, &executor)
+            };
+            bank.process(ix(accounts, flux_core::instruction::ExecuteOrder {}))
+        }),
+    ]
+}
+
+#[test]
+fn debt_repaid_from_collateral_sales_fills_the_queue() {
+    for (name, sale) in collateral_sales() {
+        let (mut bank, market, depositors, owner) = setup(&[6 * SOL]);
+        request_withdrawal(&mut bank, &market, &depositors[0], 6 * SOL).unwrap();
+
+        // The queue cannot be left out once the vault has one
+        assert_eq!(sale(&mut bank, &market, &owner, None), Err(err(FluxError::MissingWithdrawalQueue)), "{name}");
+
+        sale(&mut bank, &market, &owner, Some(withdrawal_queue_address(&market))).unwrap();
+        let repaid = 6 * SOL - position(&bank, &market, &owner).debt_amount;
+        assert!(repaid > 0, "{name}");
+        assert_eq!(queue(&bank, &market).total_filled, repaid, "{name}");
+        assert_eq!(vault(&bank, &market).total_assets, 6 * SOL - repaid, "{name}");
+    }
+}
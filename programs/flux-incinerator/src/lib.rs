@@ -50,6 +50,51 @@ pub mod flux_incinerator {
         msg!("Burn complete. Entropy reduced.");
         Ok(())
     }
+
+    /// Propose a new registry authority. Takes effect once the new key accepts.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        require_keys_neq!(new_authority, ctx.accounts.authority.key(), IncineratorError::InvalidAuthority);
+        require_keys_neq!(new_authority, Pubkey::default(), IncineratorError::InvalidAuthority);
+
+        let pending = &mut ctx.accounts.pending_authority;
+        pending.proposer = ctx.accounts.authority.key();
+        pending.new_authority = new_authority;
+        pending.proposed_at = Clock::get()?.unix_timestamp;
+        pending.bump = ctx.bumps.pending_authority;
+
+        emit!(AuthorityProposedEvent {
+            current_authority: pending.proposer,
+            new_authority,
+            timestamp: pending.proposed_at,
+        });
+        Ok(())
+    }
+
+    /// Accept a pending registry authority transfer. Signed by the new authority.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let registry = &mut ctx.accounts.burn_registry;
+        require_keys_eq!(registry.authority, ctx.accounts.pending_authority.proposer, IncineratorError::Unauthorized);
+
+        let previous = registry.authority;
+        registry.authority = ctx.accounts.new_authority.key();
+
+        emit!(AuthorityAcceptedEvent {
+            previous_authority: previous,
+            new_authority: registry.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Withdraw a pending registry authority transfer. Signed by the current authority.
+    pub fn cancel_authority_transfer(ctx: Context<CancelAuthorityTransfer>) -> Result<()> {
+        emit!(AuthorityTransferCancelledEvent {
+            cancelled_by: ctx.accounts.authority.key(),
+            new_authority: ctx.accounts.pending_authority.new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
 }
 
 #[derive(Accounts)]
@@ -82,6 +127,57 @@ pub struct Incinerate<'info> {
     pub token_program: Program<'info, token::Token>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(seeds = [b"registry"], bump, has_one = authority @ IncineratorError::Unauthorized)]
+    pub burn_registry: Account<'info, BurnRegistry>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PendingAuthority::LEN,
+        seeds = [b"pending_authority", burn_registry.key().as_ref()],
+        bump
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, seeds = [b"registry"], bump)]
+    pub burn_registry: Account<'info, BurnRegistry>,
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [b"pending_authority", burn_registry.key().as_ref()],
+        bump = pending_authority.bump,
+        has_one = proposer,
+        has_one = new_authority @ IncineratorError::Unauthorized,
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    /// CHECK: Receives the proposal's rent; matched via `has_one`
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuthorityTransfer<'info> {
+    #[account(seeds = [b"registry"], bump, has_one = authority @ IncineratorError::Unauthorized)]
+    pub burn_registry: Account<'info, BurnRegistry>,
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"pending_authority", burn_registry.key().as_ref()],
+        bump = pending_authority.bump,
+    )]
+    pub pending_authority: Account<'info, PendingAuthority>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
 #[account]
 pub struct BurnRegistry {
     pub authority: Pubkey,
@@ -95,6 +191,19 @@ impl BurnRegistry {
     pub const LEN: usize = 32 + 16 + 8 + 8 + (8 * 10);
 }
 
+/// A proposed registry authority handover, completed when `new_authority` accepts.
+#[account]
+pub struct PendingAuthority {
+    pub proposer: Pubkey, // registry authority at proposal time, receives the rent back
+    pub new_authority: Pubkey,
+    pub proposed_at: i64,
+    pub bump: u8,
+}
+
+impl PendingAuthority {
+    pub const LEN: usize = 32 + 32 + 8 + 1;
+}
+
 #[event]
 pub struct IncinerationEvent {
     pub amount: u64,
@@ -102,3 +211,32 @@ pub struct IncinerationEvent {
     pub timestamp: i64,
     pub total_burned_lifetime: u128,
 }
+
+#[event]
+pub struct AuthorityProposedEvent {
+    pub current_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityAcceptedEvent {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferCancelledEvent {
+    pub cancelled_by: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[error_code]
+pub enum IncineratorError {
+    #[msg("Signer is not the registry authority")]
+    Unauthorized,
+    #[msg("Invalid new authority")]
+    InvalidAuthority,
+}
@@ -0,0 +1,139 @@
+// The in-process harness is shared with flux-core's tests
+#[path = "../../flux-core/tests/common/mod.rs"]
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{system_program, InstructionData, ToAccountMetas};
+use flux_incinerator::{BurnRegistry, IncineratorError, PendingAuthority};
+
+use common::*;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: flux_incinerator::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+fn registry_address() -> Pubkey {
+    pda(&[b"registry"], &flux_incinerator::ID)
+}
+
+fn pending_address() -> Pubkey {
+    pda(&[b"pending_authority", registry_address().as_ref()], &flux_incinerator::ID)
+}
+
+/// A bank with the registry initialized by a fresh authority, which is returned.
+fn setup() -> (Bank, Pubkey) {
+    let mut bank = Bank::new();
+    bank.add_program(flux_incinerator::ID, flux_incinerator::entry);
+    let authority = bank.funded_key();
+    bank.process(ix(
+        flux_incinerator::accounts::InitRegistry {
+            burn_registry: registry_address(),
+            authority,
+            system_program: system_program::ID,
+        },
+        flux_incinerator::instruction::InitializeRegistry {},
+    ))
+    .unwrap();
+    (bank, authority)
+}
+
+fn propose(bank: &mut Bank, authority: &Pubkey, new_authority: Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_incinerator::accounts::ProposeAuthority {
+            burn_registry: registry_address(),
+            pending_authority: pending_address(),
+            authority: *authority,
+            system_program: system_program::ID,
+        },
+        flux_incinerator::instruction::ProposeAuthority { new_authority },
+    ))
+}
+
+fn accept(bank: &mut Bank, proposer: &Pubkey, signer: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_incinerator::accounts::AcceptAuthority {
+            burn_registry: registry_address(),
+            pending_authority: pending_address(),
+            proposer: *proposer,
+            new_authority: *signer,
+        },
+        flux_incinerator::instruction::AcceptAuthority {},
+    ))
+}
+
+fn cancel(bank: &mut Bank, authority: &Pubkey) -> TxResult {
+    bank.process(ix(
+        flux_incinerator::accounts::CancelAuthorityTransfer {
+            burn_registry: registry_address(),
+            pending_authority: pending_address(),
+            authority: *authority,
+        },
+        flux_incinerator::instruction::CancelAuthorityTransfer {},
+    ))
+}
+
+fn registry_authority(bank: &Bank) -> Pubkey {
+    bank.read::<BurnRegistry>(&registry_address()).authority
+}
+
+#[test]
+fn only_the_registry_authority_proposes_a_valid_successor() {
+    let (mut bank, authority) = setup();
+    let outsider = bank.funded_key();
+
+    assert_eq!(propose(&mut bank, &outsider, outsider), Err(err(IncineratorError::Unauthorized)));
+    assert_eq!(propose(&mut bank, &authority, authority), Err(err(IncineratorError::InvalidAuthority)));
+    assert_eq!(propose(&mut bank, &authority, Pubkey::default()), Err(err(IncineratorError::InvalidAuthority)));
+
+    let successor = bank.funded_key();
+    propose(&mut bank, &authority, successor).unwrap();
+    let pending: PendingAuthority = bank.read(&pending_address());
+    assert_eq!((pending.proposer, pending.new_authority), (authority, successor));
+    assert_eq!(registry_authority(&bank), authority);
+}
+
+#[test]
+fn only_the_proposed_key_accepts() {
+    let (mut bank, authority) = setup();
+    let successor = bank.funded_key();
+    let outsider = bank.funded_key();
+    propose(&mut bank, &authority, successor).unwrap();
+    let rent = bank.lamports(&pending_address());
+    let before = bank.lamports(&authority);
+
+    assert_eq!(accept(&mut bank, &authority, &outsider), Err(err(IncineratorError::Unauthorized)));
+    assert_eq!(accept(&mut bank, &authority, &authority), Err(err(IncineratorError::Unauthorized)));
+    assert_eq!(
+        accept(&mut bank, &outsider, &successor),
+        Err(err(anchor_lang::error::ErrorCode::ConstraintHasOne))
+    );
+
+    accept(&mut bank, &authority, &successor).unwrap();
+    assert_eq!(registry_authority(&bank), successor);
+    assert!(!bank.exists(&pending_address()));
+    assert_eq!(bank.lamports(&authority), before + rent);
+    assert_eq!(propose(&mut bank, &authority, outsider), Err(err(IncineratorError::Unauthorized)));
+}
+
+#[test]
+fn only_the_registry_authority_cancels() {
+    let (mut bank, authority) = setup();
+    let successor = bank.funded_key();
+    propose(&mut bank, &authority, successor).unwrap();
+
+    assert_eq!(cancel(&mut bank, &successor), Err(err(IncineratorError::Unauthorized)));
+    cancel(&mut bank, &authority).unwrap();
+    assert!(!bank.exists(&pending_address()));
+
+    assert_eq!(
+        accept(&mut bank, &authority, &successor),
+        Err(err(anchor_lang::error::ErrorCode::AccountNotInitialized))
+    );
+    assert_eq!(registry_authority(&bank), authority);
+    propose(&mut bank, &authority, successor).unwrap();
+}
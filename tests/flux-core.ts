@@ -2,7 +2,9 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { FluxCore } from "../target/types/flux_core";
 import { assert, expect } from "chai";
-import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL } from "@solana/web3.js";
+import { PublicKey, Keypair, SystemProgram, LAMPORTS_PER_SOL, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
+
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111");
 
 describe("FLUX Protocol Integration Suite", () => {
     // --- Test Setup ---
@@ -21,6 +23,14 @@ describe("FLUX Protocol Integration Suite", () => {
     // PDAs
     let vaultPda: PublicKey;
     let userProfilePda: PublicKey;
+    const [protocolConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("protocol_config")],
+        program.programId
+    );
+    const [complianceConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("compliance_config")],
+        program.programId
+    );
 
     before(async () => {
         // Airdrop SOL to all actors
@@ -46,29 +56,182 @@ describe("FLUX Protocol Integration Suite", () => {
         console.log("Vault initialized at:", vaultPda.toBase58());
     });
 
-    it("Admin can update configuration", async () => {
-        const newRiskFactor = 250; // 2.5%
-        
+    it("Initializes the protocol config with admin as risk manager", async () => {
+        // Only the upgrade authority (the deploying wallet) may create the config
+        const [programDataPda] = PublicKey.findProgramAddressSync(
+            [program.programId.toBuffer()],
+            BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+        );
+        try {
+            await program.methods
+                .initProtocolConfig(admin.publicKey, admin.publicKey, treasury.publicKey, treasury.publicKey)
+                .accounts({
+                    protocolConfig: protocolConfigPda,
+                    program: program.programId,
+                    programData: programDataPda,
+                    admin: admin.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([admin])
+                .rpc();
+            assert.fail("Should have failed with UnauthorizedAccess");
+        } catch (e: any) {
+            assert.include(e.toString(), "UnauthorizedAccess");
+        }
+
         await program.methods
-            .updateConfig(newRiskFactor)
+            .initProtocolConfig(admin.publicKey, admin.publicKey, treasury.publicKey, treasury.publicKey)
+            .accounts({
+                protocolConfig: protocolConfigPda,
+                program: program.programId,
+                programData: programDataPda,
+                admin: provider.wallet.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .rpc();
+
+        // Hand the admin role to the test admin
+        await program.methods
+            .updateProtocolRoles({
+                admin: admin.publicKey,
+                guardian: admin.publicKey,
+                riskManager: admin.publicKey,
+                feeManager: treasury.publicKey,
+            })
+            .accounts({
+                protocolConfig: protocolConfigPda,
+                admin: provider.wallet.publicKey,
+            })
+            .rpc();
+
+        const config = await program.account.protocolConfig.fetch(protocolConfigPda);
+        assert.isTrue(config.riskManager.equals(admin.publicKey), "Risk manager mismatch");
+    });
+
+    it("Initializes a 2-of-3 admin multisig and rejects non-member proposals", async () => {
+        const [multisigPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("admin_multisig")],
+            program.programId
+        );
+        const members = [admin.publicKey, userB.publicKey, treasury.publicKey];
+
+        await program.methods
+            .initAdminMultisig(members, 2)
+            .accounts({
+                multisig: multisigPda,
+                protocolConfig: protocolConfigPda,
+                admin: admin.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([admin])
+            .rpc();
+
+        const multisig = await program.account.adminMultisig.fetch(multisigPda);
+        assert.equal(multisig.threshold, 2);
+
+        const [proposalPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("admin_proposal"), new BN(0).toArrayLike(Buffer, "le", 8)],
+            program.programId
+        );
+        try {
+            await program.methods
+                .createAdminProposal({ accounts: [], data: Buffer.from([]) }, new BN(86400))
+                .accounts({
+                    multisig: multisigPda,
+                    proposal: proposalPda,
+                    proposer: userA.publicKey, // Not a member
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([userA])
+                .rpc();
+            assert.fail("Should have failed with NotMultisigMember");
+        } catch (e: any) {
+            assert.include(e.toString(), "NotMultisigMember");
+        }
+    });
+
+    // VaultConfigUpdate: null leaves a field unchanged
+    const proposedConfig = {
+        minCollateralRatio: 12000,
+        maxLeverage: 3,
+        optimalUtilization: 8000,
+        baseRate: 200,
+        maxRate: 3000,
+        collateralRatio: 15000,
+        riskFactor: 250, // 2.5%
+        liquidationPenalty: 800,
+        liquidationBonus: 500,
+        performanceFeeRate: null,
+        managementFeeRate: null,
+        flashLoanFeeRate: null,
+        oracleConfig: new PublicKey("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH"),
+        debtOracle: new PublicKey("Gnt27xtC473ZT2Mw5u8wZ68Z3gULkSTb5DuxJy7eJotD"),
+        minRiskFactor: 100,
+        maxRiskFactor: 600,
+        minLiquidationPenalty: 600,
+        maxLiquidationPenalty: 1500,
+        supplyCap: new BN(1_000_000 * LAMPORTS_PER_SOL),
+        borrowCap: new BN(500_000 * LAMPORTS_PER_SOL),
+        userBorrowCap: null,
+    };
+
+    it("Risk manager can queue a configuration change", async () => {
+        const [pendingConfigPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("pending_config"), vaultPda.toBuffer()],
+            program.programId
+        );
+
+        await program.methods
+            .proposeConfigChange(proposedConfig)
             .accounts({
                 vault: vaultPda,
+                pendingConfig: pendingConfigPda,
+                protocolConfig: protocolConfigPda,
                 authority: admin.publicKey,
+                systemProgram: SystemProgram.programId,
             })
             .signers([admin])
             .rpc();
 
-        const vaultAccount = await program.account.vault.fetch(vaultPda);
-        assert.equal(vaultAccount.riskFactor, newRiskFactor, "Risk factor mismatch");
-    });
+        const pending = await program.account.pendingConfigChange.fetch(pendingConfigPda);
+        assert.equal(pending.update.riskFactor, proposedConfig.riskFactor, "Risk factor mismatch");
 
-    it("Rejects config update from unauthorized user", async () => {
+        // Executing before the 48h timelock must fail
         try {
             await program.methods
-                .updateConfig(500)
+                .executeConfigChange()
                 .accounts({
                     vault: vaultPda,
+                    pendingConfig: pendingConfigPda,
+                    proposer: admin.publicKey,
+                })
+                .rpc();
+            assert.fail("Should have failed with TimelockNotElapsed");
+        } catch (e: any) {
+            assert.include(e.toString(), "TimelockNotElapsed");
+        }
+    });
+
+    it("Rejects config proposal from unauthorized user", async () => {
+        // A vault with no proposal yet, so the pending PDA is free and the role check is reached
+        const [otherVaultPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("vault"), userB.publicKey.toBuffer()],
+            program.programId
+        );
+        const [pendingConfigPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("pending_config"), otherVaultPda.toBuffer()],
+            program.programId
+        );
+
+        try {
+            await program.methods
+                .proposeConfigChange({ ...proposedConfig, riskFactor: 500 })
+                .accounts({
+                    vault: otherVaultPda,
+                    pendingConfig: pendingConfigPda,
+                    protocolConfig: protocolConfigPda,
                     authority: userA.publicKey, // Wrong signer
+                    systemProgram: SystemProgram.programId,
                 })
                 .signers([userA])
                 .rpc();
@@ -80,23 +243,64 @@ describe("FLUX Protocol Integration Suite", () => {
 
     // --- User Flow Tests ---
 
+    it("Creates a user profile for User A", async () => {
+        [userProfilePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("profile"), userA.publicKey.toBuffer()],
+            program.programId
+        );
+
+        await program.methods
+            .initUserProfile()
+            .accounts({
+                userProfile: userProfilePda,
+                owner: userA.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([userA])
+            .rpc();
+
+        const profile = await program.account.userProfile.fetch(userProfilePda);
+        assert.isTrue(profile.owner.equals(userA.publicKey), "Profile owner mismatch");
+        assert.equal(profile.activeLoans, 0);
+    });
+
     it("User A deposits assets (Fetch Phase)", async () => {
         const depositAmount = new BN(10 * LAMPORTS_PER_SOL);
-        
+        const [supplyPositionPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("supply_position"), vaultPda.toBuffer(), userA.publicKey.toBuffer()],
+            program.programId
+        );
+
+        await program.methods
+            .openSupplyPosition()
+            .accounts({
+                vault: vaultPda,
+                supplyPosition: supplyPositionPda,
+                owner: userA.publicKey,
+                systemProgram: SystemProgram.programId,
+            })
+            .signers([userA])
+            .rpc();
+
         await program.methods
             .fetchAssets(depositAmount)
             .accounts({
                 vault: vaultPda,
+                supplyPosition: supplyPositionPda,
                 authority: userA.publicKey,
                 systemProgram: SystemProgram.programId,
+                protocolConfig: protocolConfigPda,
                 oracleFeed: new PublicKey("FsJ3A3u2vn5cTVofAjvy6y5kwABJAqYWpe4975bi2epH"), // Mock Pyth
                 historyBuffer: Keypair.generate().publicKey,
+                withdrawalQueue: null, // No queue opened for this vault
             })
             .signers([userA])
             .rpc();
 
         const vaultAccount = await program.account.vault.fetch(vaultPda);
         assert.isTrue(vaultAccount.totalAssets.eq(depositAmount), "Total assets mismatch");
+        const supplyPosition = await program.account.supplyPosition.fetch(supplyPositionPda);
+        assert.isTrue(supplyPosition.shares.eq(depositAmount), "Shares mismatch");
     });
 
     it("Calculates health factor correctly under stress", async () => {
@@ -120,11 +324,21 @@ describe("FLUX Protocol Integration Suite", () => {
                 .liquidatePosition()
                 .accounts({
                     vault: vaultPda,
+                    position: PublicKey.findProgramAddressSync(
+                        [Buffer.from("position"), vaultPda.toBuffer(), userA.publicKey.toBuffer()],
+                        program.programId
+                    )[0],
+                    userProfile: userProfilePda,
+                    collateralReserve: null, // Seize base collateral
+                    collateralDeposit: null,
+                    riskCategory: null, // Position has not opted into a category
+                    withdrawalQueue: null,
                     jupiterProgram: new PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"),
                     tokenIn: userA.publicKey, // Mocks
                     tokenOut: liquidator.publicKey, // Mocks
                     liquidatorTokenAccount: liquidator.publicKey,
                     authority: liquidator.publicKey,
+                    protocolConfig: protocolConfigPda,
                     tokenProgram: spl.TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
                     priceFeedCollateral: PublicKey.default,
@@ -154,6 +368,51 @@ describe("FLUX Protocol Integration Suite", () => {
         // ...
     });
 
+    // --- Rebalance Pipeline Tests ---
+
+    it("Rejects a rebalance pipeline from a non-authority", async () => {
+        const [xferVenuePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("xfer_venue"), treasury.publicKey.toBuffer()],
+            program.programId
+        );
+
+        try {
+            await program.methods
+                .rebalance([
+                    { fetch: { amount: new BN(1_000_000) } },
+                    { unload: {} },
+                    { xfer: { amount: new BN(500_000), travelRule: null } },
+                ])
+                .accounts({
+                    vault: vaultPda,
+                    withdrawalQueue: null,
+                    protocolConfig: protocolConfigPda,
+                    authority: liquidator.publicKey,
+                    authorityTokenAccount: liquidator.publicKey, // Mocks
+                    recipient: treasury.publicKey,
+                    xferVenue: xferVenuePda,
+                    userProfile: null,
+                    actionHistory: null,
+                    complianceConfig: complianceConfigPda,
+                    transferRecord: null,
+                    instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+                    systemProgram: SystemProgram.programId,
+                    jupiterProgram: new PublicKey("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4"),
+                    tokenIn: liquidator.publicKey,
+                    tokenOut: treasury.publicKey,
+                    incineratorProgram: PublicKey.default,
+                    // The vault's own feeds, so only the authority check can fail
+                    priceFeedCollateral: proposedConfig.oracleConfig,
+                    priceFeedDebt: proposedConfig.debtOracle,
+                })
+                .signers([liquidator])
+                .rpc();
+            assert.fail("Should have failed with UnauthorizedAccess");
+        } catch (e: any) {
+            assert.include(e.toString(), "UnauthorizedAccess");
+        }
+    });
+
     // --- Transfer / KYC Tests ---
 
     it("Blocks high-value transfer for unverified user", async () => {
@@ -161,13 +420,18 @@ describe("FLUX Protocol Integration Suite", () => {
         
         try {
             await program.methods
-                .xferFunds(hugeAmount)
+                .xferFunds(hugeAmount, null)
                 .accounts({
                     userProfile: userProfilePda,
                     authority: userA.publicKey,
                     recipient: userB.publicKey,
                     systemProgram: SystemProgram.programId,
+                    protocolConfig: protocolConfigPda,
                     blacklistRegistry: PublicKey.default,
+                    actionHistory: null,
+                    complianceConfig: complianceConfigPda,
+                    transferRecord: null,
+                    instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
                 })
                 .signers([userA])
                 .rpc();
@@ -176,4 +440,30 @@ describe("FLUX Protocol Integration Suite", () => {
              console.log("Transfer correctly blocked");
         }
     });
+
+    // --- Compliance Tests ---
+
+    it("Rejects AML flag from a non-auditor profile", async () => {
+        const [caseRecordPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("case"), userProfilePda.toBuffer(), new BN(0).toArrayLike(Buffer, "le", 4)],
+            program.programId
+        );
+
+        try {
+            await program.methods
+                .flagAccount(new BN(1001), 1)
+                .accounts({
+                    userProfile: userProfilePda,
+                    caseRecord: caseRecordPda,
+                    officerProfile: userProfilePda, // Standard role, not an auditor
+                    officer: userA.publicKey,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([userA])
+                .rpc();
+            assert.fail("Should have failed with UnauthorizedAccess");
+        } catch (e: any) {
+            assert.include(e.toString(), "UnauthorizedAccess");
+        }
+    });
 });